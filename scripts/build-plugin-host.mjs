@@ -0,0 +1,32 @@
+// 构建插件宿主进程 plugin-host，并按 Tauri sidecar 的命名规则复制到 src-tauri/binaries
+//
+// Tauri 要求 externalBin 文件名带有目标三元组后缀（如 plugin-host-x86_64-unknown-linux-gnu），
+// 打包时会去掉后缀放在主程序旁边。tauri-build 在编译主程序时检查该文件，因此必须先运行本脚本。
+
+import { execFileSync } from 'node:child_process'
+import { copyFileSync, mkdirSync } from 'node:fs'
+import { dirname, join, resolve } from 'node:path'
+import { fileURLToPath } from 'node:url'
+
+const tauriDir = resolve(dirname(fileURLToPath(import.meta.url)), '..', 'src-tauri')
+const release = process.argv.includes('--release') || process.env.TAURI_ENV_DEBUG === 'false'
+
+// tauri build --target 会通过 TAURI_ENV_TARGET_TRIPLE 传入目标三元组
+const hostTriple = /^host: (\S+)$/m.exec(execFileSync('rustc', ['-vV'], { encoding: 'utf8' }))[1]
+const targetTriple = process.env.TAURI_ENV_TARGET_TRIPLE || hostTriple
+const crossCompile = targetTriple !== hostTriple
+
+const args = ['build', '-p', 'plugin-host']
+if (release) args.push('--release')
+if (crossCompile) args.push('--target', targetTriple)
+execFileSync('cargo', args, { cwd: tauriDir, stdio: 'inherit' })
+
+const extension = targetTriple.includes('windows') ? '.exe' : ''
+const profileDir = join(tauriDir, 'target', crossCompile ? targetTriple : '', release ? 'release' : 'debug')
+const binariesDir = join(tauriDir, 'binaries')
+mkdirSync(binariesDir, { recursive: true })
+copyFileSync(
+  join(profileDir, `plugin-host${extension}`),
+  join(binariesDir, `plugin-host-${targetTriple}${extension}`)
+)
+console.log(`plugin-host sidecar 已复制到 binaries/plugin-host-${targetTriple}${extension}`)
@@ -1,3 +1,66 @@
+use std::path::PathBuf;
+use std::process::Command;
+
 fn main() {
+    let target = std::env::var("TARGET").unwrap();
+    // 供插件包按目标三元组选择动态库
+    println!("cargo:rustc-env=TARGET_TRIPLE={}", target);
+    ensure_plugin_host_sidecar(&target);
     tauri_build::build()
 }
+
+/// tauri-build 要求 `bundle.externalBin` 中的 plugin-host 已经存在
+///
+/// 该文件通常由 `pnpm build:plugin-host`（tauri dev / build 的 before*Command）生成；
+/// 直接运行 `cargo build` 且文件不存在时在独立的 target 目录中构建，避免与当前构建争用目录锁
+fn ensure_plugin_host_sidecar(target: &str) {
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let extension = if target.contains("windows") {
+        ".exe"
+    } else {
+        ""
+    };
+    let sidecar = manifest_dir
+        .join("binaries")
+        .join(format!("plugin-host-{}{}", target, extension));
+    println!("cargo:rerun-if-changed={}", sidecar.display());
+    if sidecar.exists() {
+        return;
+    }
+
+    let profile = std::env::var("PROFILE").unwrap();
+    let target_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("plugin-host");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let mut command = Command::new(cargo);
+    command
+        .args(["build", "-p", "plugin-host", "--target", target])
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .current_dir(&manifest_dir)
+        // 构建脚本环境中的编译参数属于主程序，不传给 plugin-host 的构建
+        .env_remove("CARGO_ENCODED_RUSTFLAGS");
+    if profile == "release" {
+        command.arg("--release");
+    }
+
+    let status = command
+        .status()
+        .expect("无法运行 cargo 构建 plugin-host，请先运行 pnpm build:plugin-host");
+    assert!(
+        status.success(),
+        "构建 plugin-host 失败，请先运行 pnpm build:plugin-host"
+    );
+
+    std::fs::create_dir_all(sidecar.parent().unwrap()).unwrap();
+    let built = target_dir
+        .join(target)
+        .join(&profile)
+        .join(format!("plugin-host{}", extension));
+    std::fs::copy(&built, &sidecar).unwrap_or_else(|e| {
+        panic!("复制 plugin-host 到 {:?} 失败: {}", sidecar, e);
+    });
+    println!(
+        "cargo:warning=未找到 plugin-host sidecar，已自动构建到 {}",
+        sidecar.display()
+    );
+}
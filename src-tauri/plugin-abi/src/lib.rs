@@ -0,0 +1,145 @@
+//! chat-client 插件 ABI 版本
+//!
+//! `PluginInterface` 等结构体按 C 布局跨越动态库边界传递，主程序与插件的结构定义必须一致。
+//! 插件通过 [`export_plugin_abi_version!`] 导出构建时依赖的 ABI 版本，
+//! 主程序和插件宿主进程在调用 `create_plugin` 之前用 [`check_abi_version`] 检查该版本。
+//! 未导出版本函数的插件无法确认其结构布局，默认拒绝加载，用户可在设置中允许加载这类旧插件。
+//!
+//! ABI 版本历史：
+//! - 1：当前 `plugin-interfaces` 的 `PluginInterface` / `HostCallbacks` 布局，
+//!   扩展回调 `HostExtensionCallbacks { get_secret, free_string }`
+//! - 2：插件必须导出 `free_plugin_string`，主程序复制插件返回的字符串后通过它归还内存，
+//!   未导出该函数的插件拒绝加载
+
+/// 插件导出的 ABI 版本函数名称
+pub const PLUGIN_ABI_VERSION_SYMBOL: &[u8] = b"plugin_abi_version";
+
+/// 插件导出的 ABI 版本函数签名
+pub type PluginAbiVersionFn = unsafe extern "C" fn() -> u32;
+
+/// 当前 ABI 版本，插件构建时通过 [`export_plugin_abi_version!`] 导出
+pub const PLUGIN_ABI_VERSION: u32 = 2;
+
+/// 主程序仍然兼容的最低 ABI 版本
+pub const MIN_SUPPORTED_ABI_VERSION: u32 = 1;
+
+/// 从此版本开始插件必须导出 [`FREE_PLUGIN_STRING_SYMBOL`]
+pub const FREE_PLUGIN_STRING_ABI_VERSION: u32 = 2;
+
+/// 插件导出的字符串释放函数名称，主程序复制插件返回的字符串后调用它归还内存
+pub const FREE_PLUGIN_STRING_SYMBOL: &[u8] = b"free_plugin_string";
+
+/// 未导出 ABI 版本函数的旧插件记录为此版本，低于 [`MIN_SUPPORTED_ABI_VERSION`]
+pub const LEGACY_ABI_VERSION: u32 = 0;
+
+/// 导出插件构建时使用的 ABI 版本函数 `plugin_abi_version`
+///
+/// ```ignore
+/// plugin_abi::export_plugin_abi_version!();
+/// ```
+#[macro_export]
+macro_rules! export_plugin_abi_version {
+    () => {
+        /// 声明插件构建时使用的 ABI 版本
+        #[no_mangle]
+        pub extern "C" fn plugin_abi_version() -> u32 {
+            $crate::PLUGIN_ABI_VERSION
+        }
+    };
+}
+
+/// 检查插件声明的 ABI 版本，返回协商得到的版本
+///
+/// `declared` 为 `None` 表示插件没有导出版本函数，只有 `allow_legacy` 为 `true` 时才按
+/// [`LEGACY_ABI_VERSION`] 加载，否则视为不兼容
+pub fn check_abi_version(
+    plugin_id: &str,
+    declared: Option<u32>,
+    allow_legacy: bool,
+) -> Result<u32, String> {
+    let Some(version) = declared else {
+        if allow_legacy {
+            return Ok(LEGACY_ABI_VERSION);
+        }
+        return Err(format!(
+            "插件 {} 未导出 ABI 版本信息，无法确认与主程序兼容，请使用 plugin_abi::export_plugin_abi_version! 重新编译，或在设置中允许加载旧版插件",
+            plugin_id
+        ));
+    };
+    if !(MIN_SUPPORTED_ABI_VERSION..=PLUGIN_ABI_VERSION).contains(&version) {
+        return Err(format!(
+            "插件 {} 的 ABI 版本 {} 不兼容，主程序支持的版本范围为 {}-{}",
+            plugin_id, version, MIN_SUPPORTED_ABI_VERSION, PLUGIN_ABI_VERSION
+        ));
+    }
+    Ok(version)
+}
+
+/// 检查插件是否按其 ABI 版本的要求导出了 `free_plugin_string`
+///
+/// 声明 [`FREE_PLUGIN_STRING_ABI_VERSION`] 及以上版本却未导出该函数的插件视为不兼容，
+/// 更早版本的插件仍然允许加载，其返回的字符串无法释放
+pub fn check_free_plugin_string(
+    plugin_id: &str,
+    abi_version: u32,
+    exported: bool,
+) -> Result<(), String> {
+    if !exported && abi_version >= FREE_PLUGIN_STRING_ABI_VERSION {
+        return Err(format!(
+            "插件 {} 声明的 ABI 版本 {} 要求导出 free_plugin_string，但动态库中没有该函数",
+            plugin_id, abi_version
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_version_is_rejected_unless_legacy_allowed() {
+        let error = check_abi_version("old", None, false).unwrap_err();
+        assert!(error.contains("未导出 ABI 版本信息"), "{}", error);
+        assert_eq!(check_abi_version("old", None, true), Ok(LEGACY_ABI_VERSION));
+    }
+
+    #[test]
+    fn supported_versions_are_accepted() {
+        for version in MIN_SUPPORTED_ABI_VERSION..=PLUGIN_ABI_VERSION {
+            assert_eq!(
+                check_abi_version("plugin", Some(version), false),
+                Ok(version)
+            );
+        }
+    }
+
+    #[test]
+    fn older_versions_are_rejected() {
+        for version in 0..MIN_SUPPORTED_ABI_VERSION {
+            // 允许旧插件只影响未导出版本的插件，明确声明的旧版本仍然拒绝
+            let error = check_abi_version("plugin", Some(version), true).unwrap_err();
+            assert!(error.contains("不兼容"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        for version in [PLUGIN_ABI_VERSION + 1, u32::MAX] {
+            let error = check_abi_version("plugin", Some(version), true).unwrap_err();
+            assert!(error.contains("不兼容"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn free_plugin_string_is_required_from_its_abi_version() {
+        for version in FREE_PLUGIN_STRING_ABI_VERSION..=PLUGIN_ABI_VERSION {
+            let error = check_free_plugin_string("plugin", version, false).unwrap_err();
+            assert!(error.contains("free_plugin_string"), "{}", error);
+            assert_eq!(check_free_plugin_string("plugin", version, true), Ok(()));
+        }
+        for version in LEGACY_ABI_VERSION..FREE_PLUGIN_STRING_ABI_VERSION {
+            assert_eq!(check_free_plugin_string("plugin", version, false), Ok(()));
+        }
+    }
+}
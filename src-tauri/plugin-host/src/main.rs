@@ -0,0 +1,592 @@
+//! chat-client 插件宿主进程
+//!
+//! 在独立进程中加载插件动态库，通过标准输入输出与主程序通信，插件崩溃时不会影响主程序。
+//!
+//! 协议为逐行 JSON：
+//! - 主程序请求：`{"id": 1, "method": "mount", "params": {...}}`
+//! - 宿主响应：`{"id": 1, "result": ...}` 或 `{"id": 1, "error": "..."}`
+//! - 宿主回调主程序：`{"call_id": 1, "method": "send_to_frontend", "params": {...}}`
+//! - 主程序回复回调：`{"call_id": 1, "result": ...}` 或 `{"call_id": 1, "error": "..."}`
+//!
+//! 宿主写往标准输出的协议行以 [`PROTOCOL_PREFIX`] 开头，其余输出（例如插件日志）由主程序原样记录。
+//!
+//! `handle_message` 请求携带主程序一侧的插件调用链 `call_chain`，插件处理请求期间调用其他插件时，
+//! 宿主将该调用链随 `call_other_plugin` 回调带回，主程序据此检测跨进程的循环调用。
+
+use libloading::{Library, Symbol};
+use plugin_abi::{
+    PluginAbiVersionFn, FREE_PLUGIN_STRING_SYMBOL, LEGACY_ABI_VERSION, PLUGIN_ABI_VERSION_SYMBOL,
+};
+use plugin_interfaces::{
+    pluginui::{Context, Ui},
+    CreatePluginFn, DestroyPluginFn, HostCallbacks, PluginInterface, PluginMetadata,
+    CREATE_PLUGIN_SYMBOL, DESTROY_PLUGIN_SYMBOL,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::any::Any;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::io::{BufRead, Write};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// 协议行前缀，必须与主程序 `plugins::isolation::PROTOCOL_PREFIX` 保持一致
+const PROTOCOL_PREFIX: &str = "@@chat-client-rpc@@";
+
+/// 以下扩展接口定义必须与主程序 `plugins::extensions` 保持一致
+const RECEIVE_SETTINGS_SYMBOL: &[u8] = b"receive_plugin_settings";
+const REGISTER_HOST_EXTENSIONS_SYMBOL: &[u8] = b"register_host_extensions";
+const LAST_ERROR_SYMBOL: &[u8] = b"plugin_last_error";
+
+type ReceiveSettingsFn =
+    unsafe extern "C" fn(instance_id: *const c_char, settings_json: *const c_char) -> i32;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct HostExtensionCallbacks {
+    get_secret: extern "C" fn(scope_token: *const c_char, name: *const c_char) -> *const c_char,
+    free_string: extern "C" fn(ptr: *mut c_char),
+}
+
+type FreePluginStringFn = unsafe extern "C" fn(ptr: *mut c_char);
+
+type LastErrorFn = unsafe extern "C" fn(instance_id: *const c_char) -> *const c_char;
+
+type RegisterHostExtensionsFn = unsafe extern "C" fn(
+    instance_id: *const c_char,
+    scope_token: *const c_char,
+    callbacks: HostExtensionCallbacks,
+) -> i32;
+
+/// 主程序发来的请求
+#[derive(Debug, Deserialize)]
+struct Request {
+    id: u64,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// 等待主程序回复的回调，键为 call_id
+type PendingCalls = Mutex<HashMap<u64, Sender<Result<Value, String>>>>;
+
+static PENDING_CALLS: OnceLock<PendingCalls> = OnceLock::new();
+static NEXT_CALL_ID: AtomicU64 = AtomicU64::new(1);
+static STDOUT_LOCK: Mutex<()> = Mutex::new(());
+
+/// 当前请求的插件调用链，请求按顺序处理，插件在任意线程发起的回调都使用该调用链
+static CALL_CHAIN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn pending_calls() -> &'static PendingCalls {
+    PENDING_CALLS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 向主程序写入一条协议消息
+fn send(message: Value) {
+    let _guard = STDOUT_LOCK.lock().unwrap();
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{}{}", PROTOCOL_PREFIX, message);
+    let _ = stdout.flush();
+}
+
+/// 回调主程序并等待结果
+fn call_host(method: &str, params: Value) -> Result<Value, String> {
+    let call_id = NEXT_CALL_ID.fetch_add(1, Ordering::SeqCst);
+    let (sender, receiver) = mpsc::channel();
+    pending_calls().lock().unwrap().insert(call_id, sender);
+
+    send(json!({ "call_id": call_id, "method": method, "params": params }));
+
+    receiver
+        .recv()
+        .unwrap_or_else(|_| Err("主程序连接已断开".to_string()))
+}
+
+/// 将回调结果转换为插件可用的C字符串，插件需通过 `free_string` 扩展回调释放
+fn into_c_string(result: Result<Value, String>) -> *const c_char {
+    match result {
+        Ok(Value::String(value)) => match CString::new(value) {
+            Ok(c_string) => c_string.into_raw(),
+            Err(_) => std::ptr::null(),
+        },
+        _ => std::ptr::null(),
+    }
+}
+
+extern "C" fn host_free_string(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(unsafe { CString::from_raw(ptr) });
+    }
+}
+
+unsafe fn read_c_str<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    CStr::from_ptr(ptr).to_str().ok()
+}
+
+extern "C" fn host_send_to_frontend(event: *const c_char, payload: *const c_char) -> bool {
+    let (Some(event), Some(payload)) =
+        (unsafe { read_c_str(event) }, unsafe { read_c_str(payload) })
+    else {
+        return false;
+    };
+    matches!(
+        call_host(
+            "send_to_frontend",
+            json!({ "event": event, "payload": payload })
+        ),
+        Ok(Value::Bool(true))
+    )
+}
+
+extern "C" fn host_get_app_config(key: *const c_char) -> *const c_char {
+    let Some(key) = (unsafe { read_c_str(key) }) else {
+        return std::ptr::null();
+    };
+    into_c_string(call_host("get_app_config", json!({ "key": key })))
+}
+
+extern "C" fn host_call_other_plugin(
+    plugin_id: *const c_char,
+    message: *const c_char,
+) -> *const c_char {
+    let (Some(plugin_id), Some(message)) = (unsafe { read_c_str(plugin_id) }, unsafe {
+        read_c_str(message)
+    }) else {
+        return std::ptr::null();
+    };
+    let call_chain = CALL_CHAIN.lock().unwrap().clone();
+    into_c_string(call_host(
+        "call_other_plugin",
+        json!({ "plugin_id": plugin_id, "message": message, "call_chain": call_chain }),
+    ))
+}
+
+/// 宿主进程中只运行一个插件实例，作用域由主程序根据进程确定，无需校验令牌
+extern "C" fn host_get_secret(_scope_token: *const c_char, name: *const c_char) -> *const c_char {
+    let Some(name) = (unsafe { read_c_str(name) }) else {
+        return std::ptr::null();
+    };
+    into_c_string(call_host("get_secret", json!({ "name": name })))
+}
+
+/// 宿主进程中运行的插件实例
+struct HostedPlugin {
+    instance_id: String,
+    handler: *mut PluginInterface,
+    library: Library,
+    ui: Arc<Mutex<Ui>>,
+}
+
+impl HostedPlugin {
+    fn mount(params: &Value) -> Result<(Self, Value), String> {
+        let library_path = str_param(params, "library_path")?;
+        let metadata = &params["metadata"];
+        let instance_id = str_param(metadata, "instance_id")?.to_string();
+
+        let plugin_metadata = PluginMetadata {
+            id: str_param(metadata, "id")?.to_string(),
+            disabled: false,
+            name: str_param(metadata, "name")?.to_string(),
+            description: str_param(metadata, "description")?.to_string(),
+            version: str_param(metadata, "version")?.to_string(),
+            author: metadata["author"].as_str().map(|s| s.to_string()),
+            library_path: Some(library_path.to_string()),
+            config_path: str_param(metadata, "config_path")?.to_string(),
+            instance_id: Some(instance_id.clone()),
+            require_history: metadata["require_history"].as_bool().unwrap_or(false),
+        };
+
+        let library = unsafe {
+            Library::new(library_path).map_err(|e| format!("加载动态库失败: {}", e))?
+        };
+        let allow_legacy_abi = params["allow_legacy_abi"].as_bool().unwrap_or(false);
+        let abi_version = negotiate_abi_version(&library, &plugin_metadata.id, allow_legacy_abi)?;
+        let exports_free_string =
+            unsafe { library.get::<FreePluginStringFn>(FREE_PLUGIN_STRING_SYMBOL) }.is_ok();
+        plugin_abi::check_free_plugin_string(
+            &plugin_metadata.id,
+            abi_version,
+            exports_free_string,
+        )?;
+        let create_plugin: Symbol<CreatePluginFn> = unsafe {
+            library
+                .get(CREATE_PLUGIN_SYMBOL)
+                .map_err(|e| format!("找不到插件创建函数: {}", e))?
+        };
+        let handler = unsafe { create_plugin() };
+        if handler.is_null() {
+            return Err("插件创建失败".to_string());
+        }
+
+        let callbacks = HostCallbacks {
+            send_to_frontend: host_send_to_frontend,
+            get_app_config: host_get_app_config,
+            call_other_plugin: host_call_other_plugin,
+        };
+        let metadata_ffi = plugin_metadata.to_ffi();
+        let init_result =
+            unsafe { ((*handler).initialize)((*handler).plugin_ptr, callbacks, metadata_ffi) };
+        unsafe {
+            plugin_interfaces::metadata::free_plugin_metadata_ffi(metadata_ffi);
+        }
+
+        let ui = Ui::new(instance_id.clone());
+        let plugin = HostedPlugin {
+            instance_id,
+            handler,
+            library,
+            ui,
+        };
+
+        if init_result != 0 {
+            let error = plugin.failure("initialize", init_result);
+            plugin.destroy();
+            return Err(error);
+        }
+
+        plugin.register_extensions();
+        if !params["settings"].is_null() {
+            if let Err(e) = plugin.deliver_settings(&params["settings"]) {
+                eprintln!("[plugin-host] 下发插件设置失败: {}", e);
+            }
+        }
+
+        let mount_result = unsafe { ((*handler).on_mount)((*handler).plugin_ptr) };
+        if mount_result != 0 {
+            let error = plugin.failure("mount", mount_result);
+            plugin.destroy();
+            return Err(error);
+        }
+
+        let context = Context::new(plugin.instance_id.clone());
+        plugin.run_update_ui(&context);
+        let ui = plugin.ui_json();
+        Ok((plugin, json!({ "ui": ui, "abi_version": abi_version })))
+    }
+
+    /// 构造插件调用失败的错误信息，优先使用插件导出的错误描述
+    fn failure(&self, phase: &str, code: i32) -> String {
+        let message = unsafe { self.library.get::<LastErrorFn>(LAST_ERROR_SYMBOL) }
+            .ok()
+            .and_then(|last_error| {
+                let instance_cstr = CString::new(self.instance_id.as_str()).ok()?;
+                let message = unsafe { last_error(instance_cstr.as_ptr()) };
+                if message.is_null() {
+                    return None;
+                }
+                unsafe { CStr::from_ptr(message) }
+                    .to_str()
+                    .ok()
+                    .map(|message| message.to_string())
+            })
+            .unwrap_or_else(|| format!("插件返回错误码 {}", code));
+        format!("插件在 {} 阶段失败，错误码 {}: {}", phase, code, message)
+    }
+
+    fn register_extensions(&self) {
+        let register: Symbol<RegisterHostExtensionsFn> =
+            match unsafe { self.library.get(REGISTER_HOST_EXTENSIONS_SYMBOL) } {
+                Ok(symbol) => symbol,
+                Err(_) => return,
+            };
+        let (Ok(instance_cstr), Ok(token_cstr)) =
+            (CString::new(self.instance_id.as_str()), CString::new(""))
+        else {
+            return;
+        };
+        let callbacks = HostExtensionCallbacks {
+            get_secret: host_get_secret,
+            free_string: host_free_string,
+        };
+        unsafe { register(instance_cstr.as_ptr(), token_cstr.as_ptr(), callbacks) };
+    }
+
+    fn deliver_settings(&self, settings: &Value) -> Result<Value, String> {
+        let receive_settings: Symbol<ReceiveSettingsFn> =
+            match unsafe { self.library.get(RECEIVE_SETTINGS_SYMBOL) } {
+                Ok(symbol) => symbol,
+                Err(_) => return Ok(json!(false)),
+            };
+        let instance_cstr =
+            CString::new(self.instance_id.as_str()).map_err(|_| "实例ID转换失败".to_string())?;
+        let settings_cstr =
+            CString::new(settings.to_string()).map_err(|_| "插件设置转换失败".to_string())?;
+        let result = unsafe { receive_settings(instance_cstr.as_ptr(), settings_cstr.as_ptr()) };
+        if result != 0 {
+            return Err(format!("插件拒绝了设置，返回码: {}", result));
+        }
+        Ok(json!(true))
+    }
+
+    fn lifecycle(&self, method: &str) -> Result<Value, String> {
+        let result = unsafe {
+            match method {
+                "connect" => ((*self.handler).on_connect)((*self.handler).plugin_ptr),
+                "disconnect" => ((*self.handler).on_disconnect)((*self.handler).plugin_ptr),
+                _ => return Err(format!("未知的生命周期方法: {}", method)),
+            }
+        };
+        if result != 0 {
+            return Err(self.failure(method, result));
+        }
+        Ok(Value::Null)
+    }
+
+    fn handle_message(&self, params: &Value) -> Result<Value, String> {
+        if params["require_history"].as_bool().unwrap_or(false) {
+            match params["history"].as_str() {
+                Some(history_json) => {
+                    let history_cstr =
+                        CString::new(history_json).map_err(|_| "历史记录转换失败".to_string())?;
+                    let result = unsafe {
+                        ((*self.handler).set_history)(
+                            (*self.handler).plugin_ptr,
+                            history_cstr.as_ptr(),
+                        )
+                    };
+                    if result != 0 {
+                        eprintln!("[plugin-host] 设置插件历史记录失败");
+                    }
+                }
+                None => unsafe {
+                    ((*self.handler).set_history)((*self.handler).plugin_ptr, std::ptr::null());
+                },
+            }
+        }
+
+        let message_cstr =
+            CString::new(str_param(params, "message")?).map_err(|_| "消息转换失败".to_string())?;
+        let mut response_ptr: *mut c_char = std::ptr::null_mut();
+        let result = unsafe {
+            ((*self.handler).handle_message)(
+                (*self.handler).plugin_ptr,
+                message_cstr.as_ptr(),
+                &mut response_ptr,
+            )
+        };
+        if result != 0 {
+            return Err(self.failure("handle_message", result));
+        }
+        if response_ptr.is_null() {
+            return Err("插件返回空响应".to_string());
+        }
+        let response = unsafe { CStr::from_ptr(response_ptr) }
+            .to_str()
+            .map(|response| response.to_string());
+        // 响应由插件分配，复制后交还插件释放
+        if let Ok(free_string) = unsafe {
+            self.library
+                .get::<FreePluginStringFn>(FREE_PLUGIN_STRING_SYMBOL)
+        } {
+            unsafe { free_string(response_ptr) };
+        }
+        response
+            .map(Value::String)
+            .map_err(|_| "响应转换失败".to_string())
+    }
+
+    /// 处理UI更新或事件，`dispatch_event` 为 true 时先由UI实例处理事件
+    fn handle_ui(&self, params: &Value, dispatch_event: bool) -> Result<Value, String> {
+        let component_id = str_param(params, "component_id")?;
+        let value = str_param(params, "value")?;
+
+        if dispatch_event && !self.ui.lock().unwrap().handle_ui_event(component_id, value) {
+            return Ok(json!({ "handled": false, "ui": self.ui_json() }));
+        }
+
+        let mut ui_event_data = HashMap::new();
+        ui_event_data.insert(component_id.to_string(), value.to_string());
+        let context = Context::with_ui_event_data(self.instance_id.clone(), ui_event_data);
+        if dispatch_event {
+            self.ui.lock().unwrap().handle_ui_event(component_id, value);
+        }
+        let updated = self.run_update_ui(&context);
+        self.ui.lock().unwrap().clear_events();
+
+        Ok(json!({ "handled": true, "updated": updated, "ui": self.ui_json() }))
+    }
+
+    fn run_update_ui(&self, context: &Context) -> bool {
+        let mut ui = self.ui.lock().unwrap();
+        ui.clear_components_only();
+        let result = unsafe {
+            ((*self.handler).update_ui)(
+                (*self.handler).plugin_ptr,
+                context as *const Context as *const std::ffi::c_void,
+                &mut *ui as *mut Ui as *mut std::ffi::c_void,
+            )
+        };
+        result == 0
+    }
+
+    fn ui_json(&self) -> String {
+        let ui = self.ui.lock().unwrap();
+        serde_json::to_string(&ui.get_components()).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    fn dispose(self) -> Result<Value, String> {
+        let result = unsafe { ((*self.handler).on_dispose)((*self.handler).plugin_ptr) };
+        let error = (result != 0).then(|| self.failure("dispose", result));
+        self.destroy();
+        match error {
+            Some(error) => Err(error),
+            None => Ok(Value::Null),
+        }
+    }
+
+    fn destroy(&self) {
+        unsafe {
+            let destroy_plugin: Result<Symbol<DestroyPluginFn>, _> =
+                self.library.get(DESTROY_PLUGIN_SYMBOL);
+            if let Ok(destroy_fn) = destroy_plugin {
+                destroy_fn(self.handler);
+            }
+        }
+    }
+}
+
+/// 读取并检查插件的 ABI 版本，未导出版本函数的旧插件只有主程序允许时才加载
+fn negotiate_abi_version(
+    library: &Library,
+    plugin_id: &str,
+    allow_legacy: bool,
+) -> Result<u32, String> {
+    let declared = unsafe { library.get::<PluginAbiVersionFn>(PLUGIN_ABI_VERSION_SYMBOL) }
+        .ok()
+        .map(|abi_version| unsafe { abi_version() });
+    let version = plugin_abi::check_abi_version(plugin_id, declared, allow_legacy)?;
+    if declared.is_none() {
+        eprintln!(
+            "[plugin-host] 插件 {} 未导出 ABI 版本信息，按旧版 ABI {} 加载",
+            plugin_id, LEGACY_ABI_VERSION
+        );
+    }
+    Ok(version)
+}
+
+fn panic_message(method: &str, payload: &(dyn Any + Send)) -> String {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "未知的 panic".to_string()
+    };
+    format!("插件在 {} 阶段发生 panic: {}", method, message)
+}
+
+fn str_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, String> {
+    params[name]
+        .as_str()
+        .ok_or_else(|| format!("缺少参数: {}", name))
+}
+
+/// 读取主程序输入：回调回复直接交给等待方，请求转发给工作线程
+fn spawn_stdin_reader(requests: Sender<Request>) {
+    std::thread::spawn(move || {
+        let stdin = std::io::stdin();
+        for line in stdin.lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                eprintln!("[plugin-host] 无法解析的输入: {}", line);
+                continue;
+            };
+
+            if let Some(call_id) = message["call_id"].as_u64() {
+                if let Some(sender) = pending_calls().lock().unwrap().remove(&call_id) {
+                    let result = match message["error"].as_str() {
+                        Some(error) => Err(error.to_string()),
+                        None => Ok(message["result"].clone()),
+                    };
+                    let _ = sender.send(result);
+                }
+                continue;
+            }
+
+            match serde_json::from_value::<Request>(message) {
+                Ok(request) => {
+                    if requests.send(request).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => eprintln!("[plugin-host] 无效的请求: {}", e),
+            }
+        }
+
+        // 主程序关闭了输入，释放所有等待中的回调
+        for (_, sender) in pending_calls().lock().unwrap().drain() {
+            let _ = sender.send(Err("主程序连接已断开".to_string()));
+        }
+    });
+}
+
+fn respond(id: u64, result: Result<Value, String>) {
+    match result {
+        Ok(result) => send(json!({ "id": id, "result": result })),
+        Err(error) => send(json!({ "id": id, "error": error })),
+    }
+}
+
+fn run(requests: Receiver<Request>) {
+    let mut plugin: Option<HostedPlugin> = None;
+
+    for request in requests {
+        *CALL_CHAIN.lock().unwrap() =
+            serde_json::from_value(request.params["call_chain"].clone()).unwrap_or_default();
+        let result = match request.method.as_str() {
+            "mount" if plugin.is_none() => match HostedPlugin::mount(&request.params) {
+                Ok((hosted, result)) => {
+                    plugin = Some(hosted);
+                    Ok(result)
+                }
+                Err(e) => Err(e),
+            },
+            "mount" => Err("插件已挂载".to_string()),
+            "dispose" | "shutdown" => {
+                let result = match plugin.take() {
+                    Some(hosted) => hosted.dispose(),
+                    None => Ok(Value::Null),
+                };
+                respond(request.id, result);
+                return;
+            }
+            method => match plugin.as_ref() {
+                None => Err("插件未挂载".to_string()),
+                Some(hosted) => {
+                    // 捕获展开到宿主一侧的 panic，作为错误返回给主程序
+                    panic::catch_unwind(AssertUnwindSafe(|| match method {
+                        "connect" | "disconnect" => hosted.lifecycle(method),
+                        "handle_message" => hosted.handle_message(&request.params),
+                        "ui_update" => hosted.handle_ui(&request.params, false),
+                        "ui_event" => hosted.handle_ui(&request.params, true),
+                        "get_ui" => Ok(json!({ "ui": hosted.ui_json() })),
+                        "settings" => hosted.deliver_settings(&request.params["settings"]),
+                        _ => Err(format!("未知的方法: {}", method)),
+                    }))
+                    .unwrap_or_else(|payload| Err(panic_message(method, payload.as_ref())))
+                }
+            },
+        };
+        respond(request.id, result);
+    }
+
+    // 主程序关闭了输入，清理插件后退出
+    if let Some(hosted) = plugin.take() {
+        let _ = hosted.dispose();
+    }
+}
+
+fn main() {
+    let (sender, receiver) = mpsc::channel();
+    spawn_stdin_reader(sender);
+    run(receiver);
+}
@@ -1,8 +1,12 @@
 use crate::plugins::{
-    AvailablePluginInfo, DownloadResponse, PluginDownloadResult, PluginManager, PluginMetadata,
-    PluginRepository,
+    download::{self, DownloadTask},
+    AvailablePluginInfo, DownloadResponse, IncompatiblePluginInfo, PluginDownloadResult,
+    PluginInstanceState, PluginManager, PluginRepository, PluginSettingField, PluginSettingsView,
+    PluginUpdateInfo, RepositoryLock, RepositorySource, RepositorySources, ResolvedRepository,
+    ScannedPlugin,
 };
 use plugin_interfaces::metadata::HistoryMessage;
+use std::collections::HashMap;
 use std::sync::{Arc, OnceLock};
 use tauri::AppHandle;
 
@@ -11,7 +15,8 @@ static PLUGIN_MANAGER: OnceLock<Arc<PluginManager>> = OnceLock::new();
 
 /// 初始化插件管理器（应用启动时调用）
 pub fn initialize_plugin_manager(app_handle: AppHandle) {
-    let manager = Arc::new(PluginManager::new(app_handle));
+    let manager = Arc::new(PluginManager::new(Some(app_handle)));
+    PluginManager::register_global(&manager);
     PLUGIN_MANAGER
         .set(manager)
         .expect("Failed to initialize plugin manager");
@@ -26,9 +31,16 @@ fn get_plugin_manager() -> Result<&'static Arc<PluginManager>, String> {
 
 /// 扫描并返回所有可用的插件列表
 #[tauri::command]
-pub fn scan_plugins() -> Result<Vec<PluginMetadata>, String> {
+pub fn scan_plugins() -> Result<Vec<ScannedPlugin>, String> {
     let manager = get_plugin_manager()?;
-    Ok(manager.scan_plugins())
+    Ok(manager.scan_plugins_with_abi())
+}
+
+/// 扫描与当前客户端不兼容的本地插件
+#[tauri::command]
+pub fn scan_incompatible_plugins() -> Result<Vec<IncompatiblePluginInfo>, String> {
+    let manager = get_plugin_manager()?;
+    Ok(manager.scan_incompatible_plugins())
 }
 
 /// 挂载插件实例
@@ -45,6 +57,13 @@ pub fn dispose_plugin(instance_id: String) -> Result<String, String> {
     manager.dispose_plugin(&instance_id)
 }
 
+/// 关闭插件实例并删除其实例级别设置
+#[tauri::command]
+pub fn remove_plugin_instance(instance_id: String) -> Result<String, String> {
+    let manager = get_plugin_manager()?;
+    manager.remove_plugin_instance(&instance_id)
+}
+
 /// 连接插件实例
 #[tauri::command]
 pub fn connect_plugin(instance_id: String) -> Result<String, String> {
@@ -66,6 +85,29 @@ pub fn get_plugin_status(instance_id: String) -> Result<Option<(bool, bool)>, St
     Ok(manager.get_plugin_status(&instance_id))
 }
 
+/// 获取插件握手得到的 ABI 版本
+#[tauri::command]
+pub fn get_plugin_abi_version(plugin_id: String) -> Result<Option<u32>, String> {
+    let manager = get_plugin_manager()?;
+    Ok(manager.get_plugin_abi_version(&plugin_id))
+}
+
+/// 获取插件实例生命周期状态（包括隔离模式下的崩溃状态）
+#[tauri::command]
+pub fn get_plugin_instance_state(
+    instance_id: String,
+) -> Result<Option<PluginInstanceState>, String> {
+    let manager = get_plugin_manager()?;
+    Ok(manager.get_plugin_instance_state(&instance_id))
+}
+
+/// 重启插件实例
+#[tauri::command]
+pub fn restart_plugin(instance_id: String) -> Result<String, String> {
+    let manager = get_plugin_manager()?;
+    manager.restart_plugin(&instance_id)
+}
+
 /// 向指定插件实例发送消息
 #[tauri::command]
 pub fn send_message_to_plugin(
@@ -107,31 +149,144 @@ pub fn handle_plugin_ui_event(
     manager.handle_plugin_ui_event(&instance_id, &component_id, &value)
 }
 
+/// 获取插件声明的设置项定义，供前端生成设置表单
 #[tauri::command]
-pub async fn download_github_repo() -> Result<DownloadResponse, String> {
+pub fn get_plugin_settings_schema(plugin_id: String) -> Result<Vec<PluginSettingField>, String> {
+    let manager = get_plugin_manager()?;
+    manager.get_plugin_settings_schema(&plugin_id)
+}
+
+/// 获取插件设置，传入实例ID时返回该实例生效的设置
+#[tauri::command]
+pub fn get_plugin_settings(
+    plugin_id: String,
+    instance_id: Option<String>,
+) -> Result<PluginSettingsView, String> {
+    let manager = get_plugin_manager()?;
+    manager.get_plugin_settings(&plugin_id, instance_id.as_deref())
+}
+
+/// 更新插件设置，传入实例ID时只修改该实例的设置，值为 null 表示清除
+#[tauri::command]
+pub fn set_plugin_settings(
+    plugin_id: String,
+    instance_id: Option<String>,
+    values: HashMap<String, serde_json::Value>,
+) -> Result<PluginSettingsView, String> {
+    let manager = get_plugin_manager()?;
+    manager.set_plugin_settings(&plugin_id, instance_id.as_deref(), values)
+}
+
+/// 刷新所有已启用的插件仓库源
+/// 下载进度通过 `plugin-download-progress` 事件报告，可用 `download_id` 取消
+#[tauri::command]
+pub async fn download_github_repo(
+    app: AppHandle,
+    download_id: Option<String>,
+) -> Result<DownloadResponse, String> {
     let repository = PluginRepository::new();
-    repository.download_github_repo().await
+    let task = DownloadTask::new(download_id, Some(app))?;
+    repository.refresh_repositories(&task).await
+}
+
+/// 获取插件仓库源配置
+#[tauri::command]
+pub fn get_plugin_repositories() -> Result<Vec<RepositorySource>, String> {
+    Ok(RepositorySources::load().repositories)
+}
+
+/// 保存插件仓库源配置
+#[tauri::command]
+pub fn set_plugin_repositories(repositories: Vec<RepositorySource>) -> Result<(), String> {
+    RepositorySources { repositories }.save()
+}
+
+/// 获取各仓库源最近一次下载的快照及其对应的提交
+#[tauri::command]
+pub fn get_resolved_repositories() -> Result<Vec<ResolvedRepository>, String> {
+    Ok(RepositoryLock::load()?.repositories)
 }
 
 /// 扫描可用插件列表（从插件仓库）
+/// 不兼容的插件会带有 `incompatible_reason` 标记，`hide_incompatible` 为 true 时直接过滤掉
 #[tauri::command]
-pub fn scan_available_plugins() -> Result<Vec<AvailablePluginInfo>, String> {
+pub fn scan_available_plugins(
+    hide_incompatible: Option<bool>,
+) -> Result<Vec<AvailablePluginInfo>, String> {
     let repository = PluginRepository::new();
-    Ok(repository.scan_available_plugins())
+    let mut plugins = repository.scan_available_plugins();
+    if hide_incompatible.unwrap_or(false) {
+        plugins.retain(|plugin| plugin.incompatible_reason.is_none());
+    }
+    Ok(plugins)
+}
+
+/// 下载并安装插件，插件已安装时在替换插件目录前销毁其所有实例
+/// 下载进度通过 `plugin-download-progress` 事件报告，可用 `download_id` 取消
+#[tauri::command]
+pub async fn download_plugin(
+    app: AppHandle,
+    plugin_id: String,
+    download_id: Option<String>,
+) -> Result<PluginDownloadResult, String> {
+    let manager = get_plugin_manager()?;
+    let task = DownloadTask::new(download_id, Some(app))?;
+    Ok(manager.download_plugin(&plugin_id, &task).await)
 }
 
-/// 下载并安装插件
+/// 取消进行中的下载
 #[tauri::command]
-pub async fn download_plugin(plugin_id: String) -> Result<PluginDownloadResult, String> {
+pub fn cancel_download(download_id: String) -> Result<(), String> {
+    download::cancel_download(&download_id)
+}
+
+/// 检查已安装插件的可用更新
+#[tauri::command]
+pub fn check_plugin_updates() -> Result<Vec<PluginUpdateInfo>, String> {
     let repository = PluginRepository::new();
-    Ok(repository.download_plugin(&plugin_id).await)
+    Ok(repository.check_plugin_updates())
+}
+
+/// 升级已安装的插件，保留插件设置和用户数据；替换插件目录前销毁该插件的所有实例
+#[tauri::command]
+pub async fn upgrade_plugin(
+    app: AppHandle,
+    plugin_id: String,
+    download_id: Option<String>,
+) -> Result<PluginDownloadResult, String> {
+    let manager = get_plugin_manager()?;
+    let task = DownloadTask::new(download_id, Some(app))?;
+    Ok(manager.upgrade_plugin(&plugin_id, &task).await)
 }
 
-/// 卸载已安装的插件
+/// 从本地插件包（`.ccplugin`、`.zip`、`.tar.gz` 或插件目录）安装插件
 #[tauri::command]
-pub fn uninstall_plugin(plugin_id: String) -> Result<PluginDownloadResult, String> {
+pub fn install_plugin_from_path(path: String) -> Result<PluginDownloadResult, String> {
     let repository = PluginRepository::new();
-    Ok(repository.uninstall_plugin(&plugin_id))
+    Ok(repository.install_plugin_from_path(std::path::Path::new(&path)))
+}
+
+/// 回滚插件到安装或升级前的版本，互换插件目录前销毁该插件的所有实例
+#[tauri::command]
+pub fn rollback_plugin(plugin_id: String) -> Result<PluginDownloadResult, String> {
+    let manager = get_plugin_manager()?;
+    Ok(manager.rollback_plugin(&plugin_id))
+}
+
+/// 卸载已安装的插件，先销毁该插件的所有实例
+/// 其他插件依赖该插件时默认拒绝，`force` 为 true 时强制卸载；`keep_user_data` 为 true 时保留用户数据
+#[tauri::command]
+pub fn uninstall_plugin(
+    plugin_id: String,
+    force: Option<bool>,
+    keep_user_data: Option<bool>,
+) -> Result<PluginDownloadResult, String> {
+    let manager = get_plugin_manager()?;
+    Ok(manager.uninstall_plugin(
+        &plugin_id,
+        force.unwrap_or(false),
+        keep_user_data.unwrap_or(false),
+    ))
 }
 
 /// 取消流式消息
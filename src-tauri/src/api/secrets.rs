@@ -0,0 +1,44 @@
+use crate::secrets::{get_secret_vault, SecretVaultStatus};
+
+/// 获取密钥库状态
+#[tauri::command]
+pub fn get_secret_vault_status() -> Result<SecretVaultStatus, String> {
+    Ok(get_secret_vault().lock().unwrap().status())
+}
+
+/// 使用口令解锁密钥库，密钥库不存在时创建
+#[tauri::command]
+pub fn unlock_secret_vault(passphrase: String) -> Result<SecretVaultStatus, String> {
+    let mut vault = get_secret_vault().lock().unwrap();
+    vault.unlock(&passphrase)?;
+    Ok(vault.status())
+}
+
+/// 锁定密钥库
+#[tauri::command]
+pub fn lock_secret_vault() -> Result<SecretVaultStatus, String> {
+    let mut vault = get_secret_vault().lock().unwrap();
+    vault.lock();
+    Ok(vault.status())
+}
+
+/// 设置插件密钥
+#[tauri::command]
+pub fn set_plugin_secret(plugin_id: String, name: String, value: String) -> Result<(), String> {
+    get_secret_vault()
+        .lock()
+        .unwrap()
+        .set(&plugin_id, &name, &value)
+}
+
+/// 列出插件的密钥名称（不返回密钥值）
+#[tauri::command]
+pub fn list_plugin_secrets(plugin_id: String) -> Result<Vec<String>, String> {
+    get_secret_vault().lock().unwrap().list(&plugin_id)
+}
+
+/// 删除插件密钥
+#[tauri::command]
+pub fn delete_plugin_secret(plugin_id: String, name: String) -> Result<bool, String> {
+    get_secret_vault().lock().unwrap().delete(&plugin_id, &name)
+}
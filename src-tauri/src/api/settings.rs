@@ -0,0 +1,71 @@
+use crate::plugins::http::PROXY_PASSWORD_SECRET;
+use crate::secrets::get_secret_vault;
+use crate::settings::{
+    get_app_config_store, AppConfigChange, AppSettings, APP_CONFIG_CHANGED_EVENT,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter};
+
+/// 获取完整的应用配置
+#[tauri::command]
+pub fn get_app_settings() -> Result<AppSettings, String> {
+    let store = get_app_config_store().lock().unwrap();
+    Ok(store.settings().clone())
+}
+
+/// 按命名空间键获取单个配置值，例如 `general.theme`
+#[tauri::command]
+pub fn get_app_config(key: String) -> Result<Value, String> {
+    let store = get_app_config_store().lock().unwrap();
+    store.get(&key)
+}
+
+/// 设置单个配置值
+#[tauri::command]
+pub fn set_app_config(
+    app: AppHandle,
+    key: String,
+    value: Value,
+) -> Result<AppConfigChange, String> {
+    let change = get_app_config_store().lock().unwrap().set(&key, value)?;
+    emit_config_changes(&app, std::slice::from_ref(&change));
+    Ok(change)
+}
+
+/// 批量设置配置值
+#[tauri::command]
+pub fn update_app_config(
+    app: AppHandle,
+    values: HashMap<String, Value>,
+) -> Result<Vec<AppConfigChange>, String> {
+    let changes = get_app_config_store().lock().unwrap().update(values)?;
+    emit_config_changes(&app, &changes);
+    Ok(changes)
+}
+
+/// 代理密码是否已保存在密钥库中（需要先解锁密钥库）
+#[tauri::command]
+pub fn is_proxy_password_set() -> Result<bool, String> {
+    Ok(get_secret_vault()
+        .lock()
+        .unwrap()
+        .get_host_secret(PROXY_PASSWORD_SECRET)?
+        .is_some())
+}
+
+/// 在密钥库中保存代理密码，传入空字符串时删除
+#[tauri::command]
+pub fn set_proxy_password(password: String) -> Result<(), String> {
+    get_secret_vault()
+        .lock()
+        .unwrap()
+        .set_host_secret(PROXY_PASSWORD_SECRET, &password)
+}
+
+/// 向前端发送配置变更事件
+fn emit_config_changes(app: &AppHandle, changes: &[AppConfigChange]) {
+    for change in changes {
+        let _ = app.emit(APP_CONFIG_CHANGED_EVENT, change);
+    }
+}
@@ -1,13 +1,22 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 mod api;
 pub mod plugins;
+pub mod secrets;
+pub mod settings;
 
 // 导入所有 API 命令
 use api::{
-    cancel_stream_message, connect_plugin, disconnect_plugin, dispose_plugin, download_github_repo,
-    download_plugin, get_plugin_status, get_plugin_ui, greet, handle_plugin_ui_event,
-    handle_plugin_ui_update, mount_plugin, scan_available_plugins, scan_plugins,
-    send_message_to_plugin, uninstall_plugin,
+    cancel_download, cancel_stream_message, check_plugin_updates, connect_plugin,
+    delete_plugin_secret, disconnect_plugin, dispose_plugin, download_github_repo, download_plugin,
+    get_app_config, get_app_settings, get_plugin_abi_version, get_plugin_instance_state,
+    get_plugin_repositories, get_plugin_settings, get_plugin_settings_schema, get_plugin_status,
+    get_plugin_ui, get_resolved_repositories, get_secret_vault_status, greet,
+    handle_plugin_ui_event, handle_plugin_ui_update, install_plugin_from_path,
+    is_proxy_password_set, list_plugin_secrets, lock_secret_vault, mount_plugin,
+    remove_plugin_instance, restart_plugin, rollback_plugin, scan_available_plugins,
+    scan_incompatible_plugins, scan_plugins, send_message_to_plugin, set_app_config,
+    set_plugin_repositories, set_plugin_secret, set_plugin_settings, set_proxy_password,
+    uninstall_plugin, unlock_secret_vault, update_app_config, upgrade_plugin,
 };
 
 use plugin_interfaces::log_info;
@@ -20,20 +29,48 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             greet,
             scan_plugins,
+            scan_incompatible_plugins,
             mount_plugin,
             dispose_plugin,
+            remove_plugin_instance,
             connect_plugin,
             disconnect_plugin,
             get_plugin_status,
+            get_plugin_instance_state,
+            get_plugin_abi_version,
+            restart_plugin,
             send_message_to_plugin,
             get_plugin_ui,
             handle_plugin_ui_update,
             handle_plugin_ui_event,
+            get_plugin_settings_schema,
+            get_plugin_settings,
+            set_plugin_settings,
             download_github_repo,
+            get_plugin_repositories,
+            set_plugin_repositories,
+            get_resolved_repositories,
             scan_available_plugins,
             download_plugin,
+            cancel_download,
+            install_plugin_from_path,
             uninstall_plugin,
-            cancel_stream_message
+            rollback_plugin,
+            check_plugin_updates,
+            upgrade_plugin,
+            cancel_stream_message,
+            get_app_settings,
+            get_app_config,
+            set_app_config,
+            update_app_config,
+            is_proxy_password_set,
+            set_proxy_password,
+            get_secret_vault_status,
+            unlock_secret_vault,
+            lock_secret_vault,
+            set_plugin_secret,
+            list_plugin_secrets,
+            delete_plugin_secret
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
@@ -0,0 +1,84 @@
+//! 插件 ABI 版本协商
+//!
+//! 版本常量与检查规则定义在 `plugin-abi` crate 中，插件通过 `plugin_abi::export_plugin_abi_version!`
+//! 导出构建时的版本。主程序在调用 `create_plugin` 之前检查该版本，拒绝加载不兼容的插件，避免内存被破坏；
+//! 未导出版本函数的旧插件只有在设置 `plugin.allow_legacy_abi` 开启时才按 [`LEGACY_ABI_VERSION`] 加载。
+
+use libloading::Library;
+use plugin_interfaces::log_warn;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use crate::settings::store::get_app_config_store;
+
+pub use plugin_abi::{
+    check_free_plugin_string, PluginAbiVersionFn, FREE_PLUGIN_STRING_ABI_VERSION,
+    LEGACY_ABI_VERSION, MIN_SUPPORTED_ABI_VERSION, PLUGIN_ABI_VERSION, PLUGIN_ABI_VERSION_SYMBOL,
+};
+
+/// 按动态库路径缓存插件声明的 ABI 版本，动态库修改后重新读取
+type DeclaredVersionCache = Mutex<HashMap<PathBuf, (SystemTime, Option<u32>)>>;
+
+static DECLARED_VERSIONS: OnceLock<DeclaredVersionCache> = OnceLock::new();
+
+/// 用户是否允许加载未导出 ABI 版本信息的旧插件
+pub fn legacy_abi_allowed() -> bool {
+    get_app_config_store()
+        .lock()
+        .unwrap()
+        .settings()
+        .plugin
+        .allow_legacy_abi
+}
+
+/// 读取并检查插件的 ABI 版本，返回协商得到的版本
+pub fn negotiate_abi_version(library: &Library, plugin_id: &str) -> Result<u32, String> {
+    let declared = declared_abi_version(library);
+    let version = plugin_abi::check_abi_version(plugin_id, declared, legacy_abi_allowed())?;
+    if declared.is_none() {
+        log_warn!(
+            "插件 {} 未导出 ABI 版本信息，已按设置允许加载旧版插件，建议使用 plugin_abi::export_plugin_abi_version! 重新编译",
+            plugin_id
+        );
+    }
+    Ok(version)
+}
+
+/// 扫描插件时读取动态库的 ABI 版本，返回协商得到的版本
+pub fn read_library_abi_version(library_path: &Path, plugin_id: &str) -> Result<u32, String> {
+    let modified = std::fs::metadata(library_path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| format!("读取动态库 {:?} 失败: {}", library_path, e))?;
+    let cache = DECLARED_VERSIONS.get_or_init(|| Mutex::new(HashMap::new()));
+
+    let cached = cache
+        .lock()
+        .unwrap()
+        .get(library_path)
+        .filter(|(cached_modified, _)| *cached_modified == modified)
+        .map(|(_, declared)| *declared);
+    let declared = match cached {
+        Some(declared) => declared,
+        None => {
+            let library = unsafe {
+                Library::new(library_path).map_err(|e| format!("加载动态库失败: {}", e))?
+            };
+            let declared = declared_abi_version(&library);
+            cache
+                .lock()
+                .unwrap()
+                .insert(library_path.to_path_buf(), (modified, declared));
+            declared
+        }
+    };
+    plugin_abi::check_abi_version(plugin_id, declared, legacy_abi_allowed())
+}
+
+/// 调用插件导出的版本函数，未导出时返回 `None`
+fn declared_abi_version(library: &Library) -> Option<u32> {
+    unsafe { library.get::<PluginAbiVersionFn>(PLUGIN_ABI_VERSION_SYMBOL) }
+        .ok()
+        .map(|abi_version| unsafe { abi_version() })
+}
@@ -0,0 +1,301 @@
+//! 受限的归档解压
+//!
+//! 插件仓库快照和插件包都来自外部，解压时限制总大小、单个文件大小和条目数量，
+//! 拒绝符号链接、设备文件等特殊条目以及越界路径。内容先解压到目标目录旁的临时目录，
+//! 全部成功（包括调用方对临时目录的校验）后才替换目标目录，失败时原有内容保持不变。
+
+use flate2::read::GzDecoder;
+use plugin_interfaces::log_warn;
+use std::fs;
+use std::io::{Read, Seek};
+use std::path::{Component, Path, PathBuf};
+use uuid::Uuid;
+use zip::ZipArchive;
+
+/// 默认解压总大小上限（MB）
+pub const DEFAULT_MAX_EXTRACTED_SIZE_MB: u64 = 512;
+
+/// 默认单个文件大小上限（MB）
+pub const DEFAULT_MAX_FILE_SIZE_MB: u64 = 256;
+
+/// 默认条目数量上限
+pub const DEFAULT_MAX_ENTRIES: u32 = 10_000;
+
+// Unix 文件类型位
+const S_IFMT: u32 = 0o170_000;
+const S_IFREG: u32 = 0o100_000;
+const S_IFDIR: u32 = 0o040_000;
+
+/// 解压限制
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractLimits {
+    /// 解压后的总字节数上限
+    pub max_total_size: u64,
+    /// 单个文件的字节数上限
+    pub max_file_size: u64,
+    /// 条目数量上限
+    pub max_entries: usize,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_total_size: DEFAULT_MAX_EXTRACTED_SIZE_MB * 1024 * 1024,
+            max_file_size: DEFAULT_MAX_FILE_SIZE_MB * 1024 * 1024,
+            max_entries: DEFAULT_MAX_ENTRIES as usize,
+        }
+    }
+}
+
+impl ExtractLimits {
+    /// 读取应用设置中的解压限制
+    pub fn from_settings() -> Self {
+        let store = crate::settings::get_app_config_store().lock().unwrap();
+        let settings = &store.settings().plugin;
+        Self {
+            max_total_size: settings.archive_max_extracted_size_mb * 1024 * 1024,
+            max_file_size: settings.archive_max_file_size_mb * 1024 * 1024,
+            max_entries: settings.archive_max_entries as usize,
+        }
+    }
+}
+
+/// 解压过程中的计数
+struct ExtractBudget<'a> {
+    limits: &'a ExtractLimits,
+    total_size: u64,
+}
+
+impl<'a> ExtractBudget<'a> {
+    fn new(limits: &'a ExtractLimits) -> Self {
+        Self {
+            limits,
+            total_size: 0,
+        }
+    }
+
+    fn check_entries(&self, count: usize) -> Result<(), String> {
+        if count > self.limits.max_entries {
+            return Err(format!(
+                "归档包含 {} 个条目，超过上限 {}",
+                count, self.limits.max_entries
+            ));
+        }
+        Ok(())
+    }
+
+    /// 按上限复制单个文件，不信任归档中声明的大小
+    fn copy_file(&mut self, reader: &mut impl Read, outpath: &Path) -> Result<(), String> {
+        if let Some(parent) = outpath.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("无法创建父目录 {:?}: {}", parent, e))?;
+        }
+        let mut outfile =
+            fs::File::create(outpath).map_err(|e| format!("无法创建文件 {:?}: {}", outpath, e))?;
+
+        let remaining_total = self.limits.max_total_size.saturating_sub(self.total_size);
+        let limit = self.limits.max_file_size.min(remaining_total);
+        let written = std::io::copy(&mut reader.take(limit + 1), &mut outfile)
+            .map_err(|e| format!("无法写入文件 {:?}: {}", outpath, e))?;
+
+        if written > self.limits.max_file_size {
+            return Err(format!(
+                "归档中的文件 {:?} 超过单个文件大小上限 {} 字节",
+                outpath, self.limits.max_file_size
+            ));
+        }
+        self.total_size += written;
+        if self.total_size > self.limits.max_total_size {
+            return Err(format!(
+                "归档解压后超过总大小上限 {} 字节",
+                self.limits.max_total_size
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 检查归档中的相对路径：不能是绝对路径或包含 `..`，`strip_root` 为 true 时去掉第一级目录
+/// 返回 `None` 表示条目本身是被去掉的根目录
+fn sanitize_entry_path(path: &Path, strip_root: bool) -> Result<Option<PathBuf>, String> {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => components.push(part),
+            Component::CurDir => {}
+            _ => return Err(format!("归档条目路径无效: {:?}", path)),
+        }
+    }
+    let skip = usize::from(strip_root);
+    if components.len() <= skip {
+        return Ok(None);
+    }
+    Ok(Some(components[skip..].iter().collect()))
+}
+
+/// 在目标目录旁创建临时目录，执行解压并通过校验后替换目标目录
+fn extract_with_swap<F, V>(target_dir: &Path, extract: F, verify: V) -> Result<(), String>
+where
+    F: FnOnce(&Path) -> Result<(), String>,
+    V: FnOnce(&Path) -> Result<(), String>,
+{
+    let parent = target_dir
+        .parent()
+        .ok_or_else(|| format!("无效的解压目录: {:?}", target_dir))?;
+    fs::create_dir_all(parent).map_err(|e| format!("无法创建目录 {:?}: {}", parent, e))?;
+
+    let name = target_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_dir = parent.join(format!(".{}.extract-{}", name, Uuid::new_v4()));
+    fs::create_dir_all(&temp_dir).map_err(|e| format!("无法创建临时解压目录: {}", e))?;
+
+    let result = extract(&temp_dir)
+        .and_then(|_| verify(&temp_dir))
+        .and_then(|_| {
+            if target_dir.exists() {
+                fs::remove_dir_all(target_dir).map_err(|e| format!("无法删除现有目录: {}", e))?;
+            }
+            fs::rename(&temp_dir, target_dir).map_err(|e| format!("无法替换解压目录: {}", e))
+        });
+
+    if result.is_err() && temp_dir.exists() {
+        if let Err(e) = fs::remove_dir_all(&temp_dir) {
+            log_warn!("清理临时解压目录失败: {:?}, 错误: {}", temp_dir, e);
+        }
+    }
+    result
+}
+
+/// 解压 zip 归档到 `target_dir`，`strip_root` 为 true 时去掉归档中的根目录（如 `repo-main/`）
+pub fn extract_zip<R: Read + Seek>(
+    reader: R,
+    target_dir: &Path,
+    strip_root: bool,
+    limits: &ExtractLimits,
+) -> Result<(), String> {
+    extract_zip_verified(reader, target_dir, strip_root, limits, |_| Ok(()))
+}
+
+/// 解压 zip 归档到 `target_dir`，替换目标目录之前先用 `verify` 校验解压得到的临时目录
+///
+/// 校验失败时丢弃临时目录，`target_dir` 中原有的内容保持不变
+pub fn extract_zip_verified<R, V>(
+    reader: R,
+    target_dir: &Path,
+    strip_root: bool,
+    limits: &ExtractLimits,
+    verify: V,
+) -> Result<(), String>
+where
+    R: Read + Seek,
+    V: FnOnce(&Path) -> Result<(), String>,
+{
+    let mut archive = ZipArchive::new(reader).map_err(|e| format!("无法打开ZIP文件: {}", e))?;
+    let mut budget = ExtractBudget::new(limits);
+    budget.check_entries(archive.len())?;
+
+    extract_with_swap(
+        target_dir,
+        |temp_dir| {
+            for i in 0..archive.len() {
+                let mut entry = archive
+                    .by_index(i)
+                    .map_err(|e| format!("无法读取ZIP文件条目: {}", e))?;
+                let name = entry.name().to_string();
+
+                // 只允许普通文件和目录
+                if let Some(mode) = entry.unix_mode() {
+                    let file_type = mode & S_IFMT;
+                    if file_type != 0 && file_type != S_IFREG && file_type != S_IFDIR {
+                        return Err(format!("归档条目 {} 不是普通文件或目录，已拒绝", name));
+                    }
+                }
+
+                let path = entry
+                    .enclosed_name()
+                    .ok_or_else(|| format!("归档条目路径无效: {}", name))?;
+                let Some(relative_path) = sanitize_entry_path(&path, strip_root)? else {
+                    continue;
+                };
+                let outpath = temp_dir.join(relative_path);
+
+                if entry.is_dir() {
+                    fs::create_dir_all(&outpath)
+                        .map_err(|e| format!("无法创建目录 {:?}: {}", outpath, e))?;
+                    continue;
+                }
+                if entry.size() > limits.max_file_size {
+                    return Err(format!(
+                        "归档中的文件 {} 超过单个文件大小上限 {} 字节",
+                        name, limits.max_file_size
+                    ));
+                }
+                budget.copy_file(&mut entry, &outpath)?;
+            }
+            Ok(())
+        },
+        verify,
+    )
+}
+
+/// 读取 zip 归档的注释，GitHub 和 `git archive` 生成的归档注释为对应的提交 SHA
+pub fn zip_comment<R: Read + Seek>(reader: R) -> Option<String> {
+    let archive = ZipArchive::new(reader).ok()?;
+    let comment = String::from_utf8_lossy(archive.comment())
+        .trim()
+        .to_string();
+    (!comment.is_empty()).then_some(comment)
+}
+
+/// 解压 tar.gz 归档到 `target_dir`，限制与 [`extract_zip`] 相同
+pub fn extract_tar_gz<R: Read>(
+    reader: R,
+    target_dir: &Path,
+    strip_root: bool,
+    limits: &ExtractLimits,
+) -> Result<(), String> {
+    let mut archive = tar::Archive::new(GzDecoder::new(reader));
+    let mut budget = ExtractBudget::new(limits);
+
+    extract_with_swap(
+        target_dir,
+        |temp_dir| {
+            let entries = archive
+                .entries()
+                .map_err(|e| format!("无法读取 tar.gz 归档: {}", e))?;
+            for (index, entry) in entries.enumerate() {
+                budget.check_entries(index + 1)?;
+                let mut entry = entry.map_err(|e| format!("无法读取 tar.gz 条目: {}", e))?;
+                let path = entry
+                    .path()
+                    .map_err(|e| format!("归档条目路径无效: {}", e))?
+                    .to_path_buf();
+
+                let entry_type = entry.header().entry_type();
+                // git archive 生成的全局 pax 头不对应文件
+                if entry_type == tar::EntryType::XGlobalHeader {
+                    continue;
+                }
+                if !entry_type.is_file() && !entry_type.is_dir() {
+                    return Err(format!("归档条目 {:?} 不是普通文件或目录，已拒绝", path));
+                }
+
+                let Some(relative_path) = sanitize_entry_path(&path, strip_root)? else {
+                    continue;
+                };
+                let outpath = temp_dir.join(relative_path);
+
+                if entry_type.is_dir() {
+                    fs::create_dir_all(&outpath)
+                        .map_err(|e| format!("无法创建目录 {:?}: {}", outpath, e))?;
+                    continue;
+                }
+                budget.copy_file(&mut entry, &outpath)?;
+            }
+            Ok(())
+        },
+        |_| Ok(()),
+    )
+}
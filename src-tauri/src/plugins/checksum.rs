@@ -0,0 +1,83 @@
+//! 插件下载校验
+//!
+//! `PlatformDownload.checksum` 支持带算法前缀的写法：`sha256:<hex>`、`sha512:<hex>`，
+//! 不带前缀时按 SHA-256 处理。
+
+use sha2::{Digest, Sha256, Sha512};
+
+/// 支持的摘要算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+impl ChecksumAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Sha512 => "sha512",
+        }
+    }
+
+    /// 计算数据的十六进制摘要
+    pub fn digest_hex(&self, data: &[u8]) -> String {
+        match self {
+            ChecksumAlgorithm::Sha256 => to_hex(&Sha256::digest(data)),
+            ChecksumAlgorithm::Sha512 => to_hex(&Sha512::digest(data)),
+        }
+    }
+}
+
+/// 解析声明的校验值，返回算法和小写的十六进制摘要
+pub fn parse_checksum(checksum: &str) -> Result<(ChecksumAlgorithm, String), String> {
+    let checksum = checksum.trim();
+    if checksum.is_empty() {
+        return Err("插件未声明校验值，拒绝安装未经校验的文件".to_string());
+    }
+
+    let (algorithm, digest) = match checksum.split_once(':') {
+        Some((prefix, digest)) => {
+            let algorithm = match prefix.to_ascii_lowercase().as_str() {
+                "sha256" => ChecksumAlgorithm::Sha256,
+                "sha512" => ChecksumAlgorithm::Sha512,
+                other => return Err(format!("不支持的校验算法: {}", other)),
+            };
+            (algorithm, digest)
+        }
+        None => (ChecksumAlgorithm::Sha256, checksum),
+    };
+
+    let expected_len = match algorithm {
+        ChecksumAlgorithm::Sha256 => 64,
+        ChecksumAlgorithm::Sha512 => 128,
+    };
+    if digest.len() != expected_len || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "无效的 {} 校验值: {}",
+            algorithm.as_str(),
+            checksum
+        ));
+    }
+
+    Ok((algorithm, digest.to_ascii_lowercase()))
+}
+
+/// 校验数据摘要是否与声明的校验值一致
+pub fn verify_checksum(data: &[u8], checksum: &str) -> Result<(), String> {
+    let (algorithm, expected) = parse_checksum(checksum)?;
+    let actual = algorithm.digest_hex(data);
+    if actual != expected {
+        return Err(format!(
+            "{} 校验失败，期望 {}，实际 {}",
+            algorithm.as_str(),
+            expected,
+            actual
+        ));
+    }
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,399 @@
+//! 插件兼容性检查
+//!
+//! 根据 config.toml 中声明的 `min_client_version` / `max_client_version` 和 `platform`，
+//! 在扫描和安装阶段判断插件能否在当前客户端上运行，避免不兼容的插件到运行时才失败。
+
+use semver::Version;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// 当前客户端版本
+pub const CLIENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// 当前运行平台名称，与 config.toml 中 `platform` 字段的取值一致
+pub fn current_platform() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "windows"
+    } else if cfg!(target_os = "macos") {
+        "macos"
+    } else if cfg!(target_os = "linux") {
+        "linux"
+    } else {
+        "unknown"
+    }
+}
+
+/// 当前客户端构建的目标三元组，如 `x86_64-unknown-linux-gnu`
+pub const CLIENT_TARGET: &str = env!("TARGET_TRIPLE");
+
+/// 当前运行的 CPU 架构，取值同目标三元组的第一段（如 `x86_64`、`aarch64`）
+pub fn current_arch() -> &'static str {
+    std::env::consts::ARCH
+}
+
+/// 目标三元组能否在当前客户端上运行：完全一致，或架构、操作系统和环境/ABI 均一致，
+/// 仅厂商字段不同（如 `x86_64-linux-gnu` 与 `x86_64-unknown-linux-gnu`）。
+/// gnu 与 musl、windows-gnu 与 msvc 的动态库互不兼容，不视为匹配
+pub fn target_matches_current(target: &str) -> bool {
+    let os_keyword = match current_platform() {
+        "macos" => "darwin",
+        platform => platform,
+    };
+    target_matches(target, CLIENT_TARGET, current_arch(), os_keyword)
+}
+
+/// 目标三元组能否在 `client_target` 上运行，`os_keyword` 为目标三元组中的操作系统名称
+fn target_matches(target: &str, client_target: &str, arch: &str, os_keyword: &str) -> bool {
+    if target == client_target {
+        return true;
+    }
+    if target.split('-').next() != Some(arch) {
+        return false;
+    }
+    match (
+        target_environment(target, os_keyword),
+        target_environment(client_target, os_keyword),
+    ) {
+        (Some(target_env), Some(client_env)) => target_env == client_env,
+        _ => false,
+    }
+}
+
+/// 目标三元组中操作系统之后的环境/ABI 部分（如 `gnu`、`musleabihf`、`msvc`，darwin 为空），
+/// 不包含该操作系统时返回 `None`
+fn target_environment(target: &str, os_keyword: &str) -> Option<String> {
+    let parts: Vec<&str> = target.split('-').collect();
+    let os_index = parts.iter().skip(1).position(|part| *part == os_keyword)? + 1;
+    Some(parts[os_index + 1..].join("-"))
+}
+
+/// 宽松解析版本号，允许 `v` 前缀以及省略次版本号、修订号（如 `1.2`）
+pub fn parse_version(version: &str) -> Result<Version, String> {
+    let trimmed = version.trim().trim_start_matches('v');
+    let padded = match trimmed.split('.').count() {
+        1 => format!("{}.0.0", trimmed),
+        2 => format!("{}.0", trimmed),
+        _ => trimmed.to_string(),
+    };
+    Version::parse(&padded).map_err(|e| format!("无效的版本号 \"{}\": {}", version, e))
+}
+
+/// 客户端版本是否超出 `max_client_version`
+///
+/// 省略的次版本号、修订号视为通配：`0.1` 允许所有 0.1.x，`1` 允许所有 1.x.y
+fn exceeds_max_version(client_version: &Version, max_version: &str) -> Result<bool, String> {
+    let max = parse_version(max_version)?;
+    let components = max_version
+        .trim()
+        .trim_start_matches('v')
+        .split('.')
+        .count();
+    let exceeds = match components {
+        1 => client_version.major > max.major,
+        2 => (client_version.major, client_version.minor) > (max.major, max.minor),
+        _ => *client_version > max,
+    };
+    Ok(exceeds)
+}
+
+/// 检查插件与当前客户端是否兼容，不兼容时返回原因
+pub fn check_compatibility(
+    min_client_version: Option<&str>,
+    max_client_version: Option<&str>,
+    platform: &[String],
+) -> Result<(), String> {
+    let client_version = parse_version(CLIENT_VERSION)?;
+
+    if let Some(min_version) = min_client_version {
+        if client_version < parse_version(min_version)? {
+            return Err(format!(
+                "需要客户端版本 >= {}，当前版本为 {}",
+                min_version, CLIENT_VERSION
+            ));
+        }
+    }
+
+    if let Some(max_version) = max_client_version {
+        if exceeds_max_version(&client_version, max_version)? {
+            return Err(format!(
+                "需要客户端版本 <= {}，当前版本为 {}",
+                max_version, CLIENT_VERSION
+            ));
+        }
+    }
+
+    // 未声明平台时视为支持所有平台
+    let current = current_platform();
+    if !platform.is_empty() && !platform.iter().any(|p| p.eq_ignore_ascii_case(current)) {
+        return Err(format!(
+            "插件仅支持 {} 平台，当前平台为 {}",
+            platform.join(", "),
+            current
+        ));
+    }
+
+    Ok(())
+}
+
+/// 从动态库文件头识别 CPU 架构（ELF、PE、Mach-O 及 macOS 通用二进制），无法识别时返回 `None`
+pub fn binary_architectures(header: &[u8]) -> Option<Vec<&'static str>> {
+    let u16_le = |offset: usize| -> Option<u16> {
+        Some(u16::from_le_bytes(
+            header.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    };
+    let u32_le = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(
+            header.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+    let u32_be = |offset: usize| -> Option<u32> {
+        Some(u32::from_be_bytes(
+            header.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+
+    // ELF：e_machine 位于偏移 18，字节序由 EI_DATA 决定
+    if header.starts_with(b"\x7fELF") {
+        let machine = match header.get(5)? {
+            2 => u16::from_be_bytes(header.get(18..20)?.try_into().ok()?),
+            _ => u16_le(18)?,
+        };
+        return Some(vec![match machine {
+            3 => "x86",
+            62 => "x86_64",
+            40 => "arm",
+            183 => "aarch64",
+            243 => "riscv64",
+            _ => "unknown",
+        }]);
+    }
+
+    // PE：e_lfanew 指向 "PE\0\0"，其后是 Machine 字段
+    if header.starts_with(b"MZ") {
+        let pe_offset = u32_le(0x3c)? as usize;
+        if header.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+            return None;
+        }
+        return Some(vec![match u16_le(pe_offset + 4)? {
+            0x014c => "x86",
+            0x8664 => "x86_64",
+            0x01c4 => "arm",
+            0xaa64 => "aarch64",
+            _ => "unknown",
+        }]);
+    }
+
+    let mach_cpu = |cpu_type: u32| match cpu_type {
+        7 => "x86",
+        0x0100_0007 => "x86_64",
+        12 => "arm",
+        0x0100_000c => "aarch64",
+        _ => "unknown",
+    };
+
+    // Mach-O（小端 32/64 位）
+    if matches!(u32_le(0)?, 0xfeed_face | 0xfeed_facf) {
+        return Some(vec![mach_cpu(u32_le(4)?)]);
+    }
+
+    // macOS 通用二进制：大端的架构列表
+    if u32_be(0)? == 0xcafe_babe {
+        let count = u32_be(4)? as usize;
+        return (0..count)
+            .map(|index| u32_be(8 + index * 20).map(mach_cpu))
+            .collect();
+    }
+
+    None
+}
+
+/// 检查动态库的 CPU 架构是否与当前系统一致，无法识别的格式视为兼容
+pub fn check_binary_arch(header: &[u8]) -> Result<(), String> {
+    check_binary_arch_for(header, current_arch())
+}
+
+/// 检查动态库的 CPU 架构是否包含 `arch`
+fn check_binary_arch_for(header: &[u8], arch: &str) -> Result<(), String> {
+    let Some(architectures) = binary_architectures(header) else {
+        return Ok(());
+    };
+    if architectures.contains(&arch) {
+        return Ok(());
+    }
+    Err(format!(
+        "动态库架构为 {}，与当前系统架构 {} 不匹配",
+        architectures.join("/"),
+        arch
+    ))
+}
+
+/// 读取动态库文件头并检查 CPU 架构
+pub fn check_library_arch(library_path: &Path) -> Result<(), String> {
+    let mut header = Vec::with_capacity(4096);
+    File::open(library_path)
+        .and_then(|file| file.take(4096).read_to_end(&mut header))
+        .map_err(|e| format!("读取动态库 {:?} 失败: {}", library_path, e))?;
+    check_binary_arch(&header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_max_version_is_a_wildcard() {
+        let cases = [
+            // (客户端版本, max_client_version, 是否超出)
+            ("0.1.6", "0.1", false),
+            ("0.1.99", "0.1", false),
+            ("0.2.0", "0.1", true),
+            ("0.9.3", "0", false),
+            ("1.0.0", "0", true),
+            ("1.4.2", "v1", false),
+            ("2.0.0", "1", true),
+            ("0.1.6", "0.1.6", false),
+            ("0.1.7", "0.1.6", true),
+            ("0.1.6", "0.1.5", true),
+        ];
+        for (client, max, expected) in cases {
+            let client_version = parse_version(client).unwrap();
+            assert_eq!(
+                exceeds_max_version(&client_version, max),
+                Ok(expected),
+                "客户端 {} / max {}",
+                client,
+                max
+            );
+        }
+        assert!(exceeds_max_version(&parse_version("0.1.6").unwrap(), "latest").is_err());
+    }
+
+    #[test]
+    fn compatibility_uses_client_version_and_platform() {
+        let client = parse_version(CLIENT_VERSION).unwrap();
+        let major = client.major.to_string();
+        let major_minor = format!("{}.{}", client.major, client.minor);
+        let next_major = (client.major + 1).to_string();
+        let any: &[String] = &[];
+        let current = &[current_platform().to_uppercase()][..];
+        let other = &["plan9".to_string()][..];
+
+        let cases = [
+            (None, None, any, true),
+            (Some(CLIENT_VERSION), Some(CLIENT_VERSION), any, true),
+            (None, Some(major.as_str()), any, true),
+            (None, Some(major_minor.as_str()), current, true),
+            (Some(next_major.as_str()), None, any, false),
+            (None, Some("0.0.1"), any, false),
+            (None, None, other, false),
+            (Some("not a version"), None, any, false),
+        ];
+        for (min, max, platform, compatible) in cases {
+            assert_eq!(
+                check_compatibility(min, max, platform).is_ok(),
+                compatible,
+                "min {:?} / max {:?} / platform {:?}",
+                min,
+                max,
+                platform
+            );
+        }
+    }
+
+    #[test]
+    fn targets_match_only_with_same_arch_os_and_environment() {
+        // (客户端目标三元组, 架构, 操作系统)
+        let linux = ("x86_64-unknown-linux-gnu", "x86_64", "linux");
+        let armhf = ("armv7-unknown-linux-gnueabihf", "arm", "linux");
+        let windows = ("x86_64-pc-windows-msvc", "x86_64", "windows");
+        let macos = ("aarch64-apple-darwin", "aarch64", "darwin");
+
+        let cases = [
+            (linux, "x86_64-unknown-linux-gnu", true),
+            (linux, "x86_64-linux-gnu", true),
+            (linux, "x86_64-unknown-linux-musl", false),
+            (linux, "aarch64-unknown-linux-gnu", false),
+            (armhf, "arm-unknown-linux-gnueabihf", true),
+            (armhf, "arm-unknown-linux-musleabihf", false),
+            (windows, "x86_64-uwp-windows-msvc", true),
+            (windows, "x86_64-pc-windows-gnu", false),
+            (macos, "aarch64-unknown-darwin", true),
+            (macos, "aarch64-apple-darwin", true),
+            (macos, "x86_64-apple-darwin", false),
+            (macos, "aarch64-apple-ios", false),
+        ];
+        for ((client, arch, os), target, expected) in cases {
+            assert_eq!(
+                target_matches(target, client, arch, os),
+                expected,
+                "{} -> {}",
+                target,
+                client
+            );
+        }
+        assert!(target_matches_current(CLIENT_TARGET));
+    }
+
+    /// 构造只包含文件头的动态库
+    fn elf(machine: u16) -> Vec<u8> {
+        let mut header = vec![0u8; 64];
+        header[..4].copy_from_slice(b"\x7fELF");
+        header[5] = 1;
+        header[18..20].copy_from_slice(&machine.to_le_bytes());
+        header
+    }
+
+    fn pe(machine: u16) -> Vec<u8> {
+        let mut header = vec![0u8; 0x90];
+        header[..2].copy_from_slice(b"MZ");
+        header[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        header[0x80..0x84].copy_from_slice(b"PE\0\0");
+        header[0x84..0x86].copy_from_slice(&machine.to_le_bytes());
+        header
+    }
+
+    fn mach_o(cpu_type: u32) -> Vec<u8> {
+        let mut header = 0xfeed_facfu32.to_le_bytes().to_vec();
+        header.extend_from_slice(&cpu_type.to_le_bytes());
+        header
+    }
+
+    fn universal(cpu_types: &[u32]) -> Vec<u8> {
+        let mut header = 0xcafe_babeu32.to_be_bytes().to_vec();
+        header.extend_from_slice(&(cpu_types.len() as u32).to_be_bytes());
+        for cpu_type in cpu_types {
+            let mut arch = cpu_type.to_be_bytes().to_vec();
+            arch.resize(20, 0);
+            header.extend_from_slice(&arch);
+        }
+        header
+    }
+
+    #[test]
+    fn binary_arch_is_checked_against_current_arch() {
+        let cases = [
+            (elf(62), "x86_64", true),
+            (elf(183), "x86_64", false),
+            (elf(183), "aarch64", true),
+            (elf(40), "arm", true),
+            (pe(0x8664), "x86_64", true),
+            (pe(0x014c), "x86_64", false),
+            (pe(0xaa64), "aarch64", true),
+            (mach_o(0x0100_000c), "aarch64", true),
+            (mach_o(0x0100_0007), "aarch64", false),
+            (universal(&[0x0100_0007, 0x0100_000c]), "aarch64", true),
+            (universal(&[0x0100_0007]), "aarch64", false),
+            // 无法识别的格式视为兼容
+            (b"#!/bin/sh\n".to_vec(), "x86_64", true),
+        ];
+        for (header, arch, compatible) in cases {
+            let result = check_binary_arch_for(&header, arch);
+            assert_eq!(result.is_ok(), compatible, "{:?}", result);
+        }
+
+        let error = check_binary_arch_for(&universal(&[0x0100_0007, 7]), "aarch64").unwrap_err();
+        assert!(error.contains("x86_64/x86"), "{}", error);
+    }
+}
@@ -1,4 +1,4 @@
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 
 /// 插件配置文件结构
@@ -9,6 +9,51 @@ pub struct PluginConfig {
     pub download: Option<DownloadConfig>,
     #[serde(default)]
     pub metadata: HashMap<String, toml::Value>,
+    /// 插件声明的用户可配置项（config.toml 中的 `[settings]`），按声明顺序排列
+    #[serde(
+        default,
+        deserialize_with = "deserialize_settings",
+        serialize_with = "serialize_settings"
+    )]
+    pub settings: Vec<PluginSettingField>,
+}
+
+/// 解析 `[settings]` 段：每个子表为一个设置项，表名即设置项的键名
+///
+/// ```toml
+/// [settings.api_key]
+/// label = "API Key"
+/// type = "string"
+/// secret = true
+/// required = true
+///
+/// [settings.temperature]
+/// type = "number"
+/// default = 0.7
+/// min = 0.0
+/// max = 2.0
+/// ```
+fn deserialize_settings<'de, D>(deserializer: D) -> Result<Vec<PluginSettingField>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    toml::Table::deserialize(deserializer)?
+        .into_iter()
+        .map(|(key, value)| {
+            let mut field: PluginSettingField = value
+                .try_into()
+                .map_err(|e| D::Error::custom(format!("设置项 {} 无效: {}", key, e)))?;
+            field.key = key;
+            Ok(field)
+        })
+        .collect()
+}
+
+fn serialize_settings<S>(settings: &[PluginSettingField], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_map(settings.iter().map(|field| (&field.key, field)))
 }
 
 /// 插件基本信息
@@ -55,6 +100,13 @@ pub struct DownloadConfig {
     pub macos: Option<PlatformDownload>,
     #[serde(default)]
     pub linux: Option<PlatformDownload>,
+    /// 按目标三元组（如 `aarch64-unknown-linux-gnu`）或“系统-架构”（如 `linux-aarch64`）区分的下载项，
+    /// 优先于只按操作系统区分的 `windows` / `macos` / `linux`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub targets: HashMap<String, PlatformDownload>,
+    /// 跨平台 `.ccplugin` 插件包，当前平台没有单独的下载项时使用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<PlatformDownload>,
 }
 
 /// 平台特定下载信息
@@ -62,6 +114,85 @@ pub struct DownloadConfig {
 pub struct PlatformDownload {
     pub checksum: String,
     pub download_url: String,
+    /// 发布者对二进制文件的 Ed25519 分离签名，格式为 `<key_id>:<base64 签名>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// 插件设置项定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginSettingField {
+    /// 设置项键名，来自 `[settings.<key>]` 的表名
+    #[serde(default)]
+    pub key: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    pub field_type: PluginSettingType,
+    #[serde(default)]
+    pub default: Option<toml::Value>,
+    /// 是否必填
+    #[serde(default)]
+    pub required: bool,
+    /// 是否为敏感信息（如 API Key），敏感值不会返回给前端
+    #[serde(default)]
+    pub secret: bool,
+    /// 可选值列表，仅用于 select 类型
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// 数值下限，仅用于 number / integer 类型
+    #[serde(default)]
+    pub min: Option<f64>,
+    /// 数值上限，仅用于 number / integer 类型
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+/// 插件设置项类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginSettingType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Select,
+}
+
+impl PluginSettingField {
+    /// 校验设置值是否符合定义
+    pub fn validate(&self, value: &toml::Value) -> Result<(), String> {
+        let number = match (self.field_type, value) {
+            (PluginSettingType::String, toml::Value::String(_)) => None,
+            (PluginSettingType::Boolean, toml::Value::Boolean(_)) => None,
+            (PluginSettingType::Integer, toml::Value::Integer(v)) => Some(*v as f64),
+            (PluginSettingType::Number, toml::Value::Integer(v)) => Some(*v as f64),
+            (PluginSettingType::Number, toml::Value::Float(v)) => Some(*v),
+            (PluginSettingType::Select, toml::Value::String(v)) => {
+                if !self.options.contains(v) {
+                    return Err(format!("设置项 {} 的值 {} 不在可选范围内", self.key, v));
+                }
+                None
+            }
+            _ => {
+                return Err(format!(
+                    "设置项 {} 的类型不匹配，期望 {:?}",
+                    self.key, self.field_type
+                ))
+            }
+        };
+
+        if let Some(number) = number {
+            if self.min.is_some_and(|min| number < min) || self.max.is_some_and(|max| number > max)
+            {
+                return Err(format!("设置项 {} 的值 {} 超出范围", self.key, number));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl PluginConfig {
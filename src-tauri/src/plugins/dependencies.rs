@@ -0,0 +1,130 @@
+//! 插件依赖解析
+//!
+//! config.toml 中的 `dependencies` 由插件 ID 和可选的版本范围组成，例如：
+//!
+//! ```toml
+//! dependencies = ["rag-core >= 1.2", "markdown-utils ^0.3", "logger"]
+//! ```
+//!
+//! 版本范围使用 semver 语法，省略时表示接受任意版本。
+
+use plugin_interfaces::{log_warn, PluginMetadata};
+use semver::VersionReq;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::plugins::{compatibility::parse_version, config::PluginConfig, PluginLoader};
+
+/// 单个插件依赖
+#[derive(Debug, Clone)]
+pub struct PluginDependency {
+    pub id: String,
+    /// 版本范围，为 `None` 时接受任意版本
+    pub requirement: Option<VersionReq>,
+}
+
+impl PluginDependency {
+    /// 解析依赖声明，如 `"rag-core >= 1.2"`、`"rag-core>=1.2, <2"` 或 `"rag-core"`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let split_at = spec
+            .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '=' | '^' | '~' | '*'))
+            .unwrap_or(spec.len());
+        let (id, requirement) = spec.split_at(split_at);
+
+        if id.is_empty() {
+            return Err(format!("无效的依赖声明 \"{}\": 缺少插件 ID", spec));
+        }
+
+        let requirement = requirement.trim();
+        let requirement = if requirement.is_empty() {
+            None
+        } else {
+            Some(
+                VersionReq::parse(requirement)
+                    .map_err(|e| format!("无效的依赖版本范围 \"{}\": {}", spec, e))?,
+            )
+        };
+
+        Ok(Self {
+            id: id.to_string(),
+            requirement,
+        })
+    }
+
+    /// 检查给定版本是否满足依赖的版本范围
+    pub fn matches(&self, version: &str) -> bool {
+        match &self.requirement {
+            None => true,
+            Some(requirement) => parse_version(version)
+                .map(|version| requirement.matches(&version))
+                .unwrap_or(false),
+        }
+    }
+}
+
+impl fmt::Display for PluginDependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.requirement {
+            Some(requirement) => write!(f, "{} {}", self.id, requirement),
+            None => f.write_str(&self.id),
+        }
+    }
+}
+
+/// 解析依赖声明列表
+pub fn parse_dependencies(specs: &[String]) -> Result<Vec<PluginDependency>, String> {
+    specs
+        .iter()
+        .map(|spec| PluginDependency::parse(spec))
+        .collect()
+}
+
+/// 读取本地插件声明的依赖
+pub fn load_plugin_dependencies(
+    metadata: &PluginMetadata,
+) -> Result<Vec<PluginDependency>, String> {
+    let config = PluginConfig::from_file(&metadata.config_path)
+        .map_err(|e| format!("读取插件配置失败: {}", e))?;
+    parse_dependencies(&config.plugin.dependencies)
+}
+
+/// 本地已有插件的版本表（插件 ID -> 版本）
+pub fn installed_plugin_versions() -> HashMap<String, String> {
+    PluginLoader::new()
+        .scan_plugins()
+        .into_iter()
+        .map(|plugin| (plugin.id, plugin.version))
+        .collect()
+}
+
+/// 找出本地插件中缺失或版本不满足的依赖
+pub fn find_missing_dependencies(
+    dependencies: &[PluginDependency],
+    installed: &HashMap<String, String>,
+) -> Vec<String> {
+    dependencies
+        .iter()
+        .filter_map(|dependency| match installed.get(&dependency.id) {
+            Some(version) if dependency.matches(version) => None,
+            Some(version) => Some(format!("{}（已安装 {}）", dependency, version)),
+            None => Some(dependency.to_string()),
+        })
+        .collect()
+}
+
+/// 找出依赖指定插件的其他本地插件
+pub fn find_dependents(plugin_id: &str) -> Vec<PluginMetadata> {
+    PluginLoader::new()
+        .scan_plugins()
+        .into_iter()
+        .filter(|plugin| plugin.id != plugin_id)
+        .filter(|plugin| match load_plugin_dependencies(plugin) {
+            Ok(dependencies) => dependencies.iter().any(|d| d.id == plugin_id),
+            Err(e) => {
+                log_warn!("插件 {} 的依赖声明无效: {}", plugin.id, e);
+                false
+            }
+        })
+        .collect()
+}
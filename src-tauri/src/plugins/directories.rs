@@ -5,10 +5,38 @@ pub fn get_plugin_repository_root() -> PathBuf {
     home_dir.join(".chat_client")
 }
 
+pub fn get_app_settings_file() -> PathBuf {
+    get_plugin_repository_root().join("settings.toml")
+}
+
+pub fn get_secret_vault_file() -> PathBuf {
+    get_plugin_repository_root().join("secrets.vault")
+}
+
+pub fn get_trusted_keys_file() -> PathBuf {
+    get_plugin_repository_root().join("trusted_keys.toml")
+}
+
+pub fn get_plugin_settings_directory() -> PathBuf {
+    get_plugin_repository_root().join("plugin_settings")
+}
+
 pub fn get_root_plugin_installed_directory() -> PathBuf {
     get_plugin_repository_root().join("installed_plugins")
 }
 
+pub fn get_installed_lock_file() -> PathBuf {
+    get_plugin_repository_root().join("installed.lock")
+}
+
+pub fn get_plugin_staging_directory() -> PathBuf {
+    get_plugin_repository_root().join("plugin_staging")
+}
+
+pub fn get_plugin_backup_directory() -> PathBuf {
+    get_plugin_repository_root().join("plugin_backups")
+}
+
 pub fn get_plugins_directories() -> Vec<PathBuf> {
     let current_dir = std::env::current_dir().unwrap_or_default();
     let mut directories = Vec::new();
@@ -24,10 +52,14 @@ pub fn get_plugins_directories() -> Vec<PathBuf> {
     directories
 }
 
-pub fn get_repository_directory() -> PathBuf {
-    get_plugin_repository_root().join("chat-client-plugin")
+pub fn get_repository_sources_file() -> PathBuf {
+    get_plugin_repository_root().join("repositories.toml")
+}
+
+pub fn get_repository_lock_file() -> PathBuf {
+    get_plugin_repository_root().join("repositories.lock")
 }
 
-pub fn get_plugin_repository_directory() -> PathBuf {
-    get_repository_directory().join("plugins")
+pub fn get_repository_cache_directory() -> PathBuf {
+    get_plugin_repository_root().join("repositories")
 }
@@ -0,0 +1,254 @@
+//! 可取消的流式下载
+//!
+//! 插件和仓库快照按块下载，下载过程中通过 `plugin-download-progress` 事件向前端报告进度。
+//! 每次下载操作（可能包含依赖、配置文件等多个文件）对应一个下载 ID，前端可通过
+//! `cancel_download` 命令取消。
+
+use plugin_interfaces::{log_info, log_warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::plugins::http::send_with_retry;
+
+/// 下载进度事件名称
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "plugin-download-progress";
+
+/// 两次进度事件之间的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 下载状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadStatus {
+    Downloading,
+    Finished,
+    Cancelled,
+    Failed,
+}
+
+/// 下载进度事件
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub download_id: String,
+    /// 正在下载的文件地址
+    pub url: String,
+    /// 已下载字节数
+    pub downloaded: u64,
+    /// 总字节数，服务器未返回长度时为空
+    pub total: Option<u64>,
+    /// 平均下载速度（字节/秒）
+    pub speed: f64,
+    pub status: DownloadStatus,
+}
+
+/// 下载的取消标记，取消时唤醒正在等待网络数据的下载
+#[derive(Default)]
+struct CancelSignal {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancelSignal {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 等待直到下载被取消
+    async fn cancelled(&self) {
+        // 先注册等待再检查标记，避免错过两者之间发出的通知
+        let notified = self.notify.notified();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+// 进行中的下载，键为下载 ID，值为取消标记
+static ACTIVE_DOWNLOADS: OnceLock<Mutex<HashMap<String, Arc<CancelSignal>>>> = OnceLock::new();
+
+fn active_downloads() -> &'static Mutex<HashMap<String, Arc<CancelSignal>>> {
+    ACTIVE_DOWNLOADS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 一次下载操作，析构时注销下载 ID
+pub struct DownloadTask {
+    id: String,
+    cancel: Arc<CancelSignal>,
+    app_handle: Option<AppHandle>,
+}
+
+impl DownloadTask {
+    /// 注册下载操作，未指定下载 ID 时自动生成；没有 `AppHandle` 时不发送进度事件
+    ///
+    /// 下载 ID 已被进行中的下载使用时返回错误
+    pub fn new(download_id: Option<String>, app_handle: Option<AppHandle>) -> Result<Self, String> {
+        let id = download_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+        let cancel = Arc::new(CancelSignal::default());
+        let mut downloads = active_downloads().lock().unwrap();
+        if downloads.contains_key(&id) {
+            return Err(format!("下载 {} 已在进行中", id));
+        }
+        downloads.insert(id.clone(), Arc::clone(&cancel));
+        drop(downloads);
+
+        Ok(Self {
+            id,
+            cancel,
+            app_handle,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// 已取消时返回错误，用于在各个下载步骤之间检查
+    pub fn check_cancelled(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err(format!("下载 {} 已取消", self.id))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// 等待 `future` 完成，期间下载被取消时立即放弃并返回错误
+    async fn until_cancelled<F: Future>(&self, future: F) -> Result<F::Output, String> {
+        tokio::select! {
+            output = future => Ok(output),
+            _ = self.cancel.cancelled() => Err(format!("下载 {} 已取消", self.id)),
+        }
+    }
+
+    /// 按块下载文件并报告进度，取消后立即停止（包括等待重试和等待网络数据期间）
+    pub async fn fetch(&self, client: &reqwest::Client, url: &str) -> Result<Vec<u8>, String> {
+        self.check_cancelled()?;
+
+        let mut response = self
+            .until_cancelled(send_with_retry(client.get(url)))
+            .await
+            .inspect_err(|_| log_info!("下载已取消: {} ({})", url, self.id))?
+            .map_err(|e| format!("下载失败: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("下载失败，HTTP状态码: {}", response.status()));
+        }
+
+        let total = response.content_length();
+        let mut data = Vec::with_capacity(total.unwrap_or(0).min(64 * 1024 * 1024) as usize);
+        let started = Instant::now();
+        let mut next_report = started;
+
+        loop {
+            let chunk = match self.until_cancelled(response.chunk()).await {
+                Ok(Ok(Some(chunk))) => chunk,
+                Ok(Ok(None)) => break,
+                Ok(Err(e)) => {
+                    self.report(
+                        url,
+                        data.len() as u64,
+                        total,
+                        started,
+                        DownloadStatus::Failed,
+                    );
+                    return Err(format!("读取下载数据失败: {}", e));
+                }
+                Err(cancelled) => {
+                    self.report(
+                        url,
+                        data.len() as u64,
+                        total,
+                        started,
+                        DownloadStatus::Cancelled,
+                    );
+                    log_info!("下载已取消: {} ({})", url, self.id);
+                    return Err(cancelled);
+                }
+            };
+            data.extend_from_slice(&chunk);
+
+            if Instant::now() >= next_report {
+                self.report(
+                    url,
+                    data.len() as u64,
+                    total,
+                    started,
+                    DownloadStatus::Downloading,
+                );
+                next_report = Instant::now() + PROGRESS_INTERVAL;
+            }
+        }
+
+        self.report(
+            url,
+            data.len() as u64,
+            total,
+            started,
+            DownloadStatus::Finished,
+        );
+        Ok(data)
+    }
+
+    /// 发送进度事件
+    fn report(
+        &self,
+        url: &str,
+        downloaded: u64,
+        total: Option<u64>,
+        started: Instant,
+        status: DownloadStatus,
+    ) {
+        let Some(app_handle) = &self.app_handle else {
+            return;
+        };
+        let elapsed = started.elapsed().as_secs_f64();
+        let progress = DownloadProgress {
+            download_id: self.id.clone(),
+            url: url.to_string(),
+            downloaded,
+            total,
+            speed: if elapsed > 0.0 {
+                downloaded as f64 / elapsed
+            } else {
+                0.0
+            },
+            status,
+        };
+        if let Err(e) = app_handle.emit(DOWNLOAD_PROGRESS_EVENT, &progress) {
+            log_warn!("发送下载进度事件失败: {}", e);
+        }
+    }
+}
+
+impl Drop for DownloadTask {
+    fn drop(&mut self) {
+        active_downloads().lock().unwrap().remove(&self.id);
+    }
+}
+
+/// 取消进行中的下载
+pub fn cancel_download(download_id: &str) -> Result<(), String> {
+    match active_downloads().lock().unwrap().get(download_id) {
+        Some(cancel) => {
+            cancel.cancel();
+            log_info!("请求取消下载: {}", download_id);
+            Ok(())
+        }
+        None => Err(format!("下载 {} 不存在或已结束", download_id)),
+    }
+}
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 调用插件时所处的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginPhase {
+    Create,
+    Initialize,
+    Mount,
+    Connect,
+    Disconnect,
+    Dispose,
+    UpdateUi,
+    SetHistory,
+    HandleMessage,
+    Settings,
+}
+
+impl PluginPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PluginPhase::Create => "create",
+            PluginPhase::Initialize => "initialize",
+            PluginPhase::Mount => "mount",
+            PluginPhase::Connect => "connect",
+            PluginPhase::Disconnect => "disconnect",
+            PluginPhase::Dispose => "dispose",
+            PluginPhase::UpdateUi => "update_ui",
+            PluginPhase::SetHistory => "set_history",
+            PluginPhase::HandleMessage => "handle_message",
+            PluginPhase::Settings => "settings",
+        }
+    }
+}
+
+impl fmt::Display for PluginPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 插件调用错误，序列化后通过 `plugin-error` 事件发送给前端
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PluginError {
+    /// 插件返回了非零错误码
+    Failed {
+        code: i32,
+        message: String,
+        plugin_id: String,
+        instance_id: String,
+        phase: PluginPhase,
+    },
+    /// 插件返回了空指针或无法解析的数据
+    InvalidResponse {
+        message: String,
+        plugin_id: String,
+        instance_id: String,
+        phase: PluginPhase,
+    },
+    /// 隔离模式下宿主进程返回的错误
+    Remote {
+        message: String,
+        plugin_id: String,
+        instance_id: String,
+        phase: PluginPhase,
+    },
+    /// 插件函数发生 panic 并展开到主程序，实例状态不再可信
+    Panicked {
+        message: String,
+        plugin_id: String,
+        instance_id: String,
+        phase: PluginPhase,
+    },
+}
+
+impl PluginError {
+    /// 插件返回的错误码，非 [`PluginError::Failed`] 时为 `None`
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            PluginError::Failed { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            PluginError::Failed { message, .. }
+            | PluginError::InvalidResponse { message, .. }
+            | PluginError::Remote { message, .. }
+            | PluginError::Panicked { message, .. } => message,
+        }
+    }
+
+    pub fn plugin_id(&self) -> &str {
+        match self {
+            PluginError::Failed { plugin_id, .. }
+            | PluginError::InvalidResponse { plugin_id, .. }
+            | PluginError::Remote { plugin_id, .. }
+            | PluginError::Panicked { plugin_id, .. } => plugin_id,
+        }
+    }
+
+    pub fn instance_id(&self) -> &str {
+        match self {
+            PluginError::Failed { instance_id, .. }
+            | PluginError::InvalidResponse { instance_id, .. }
+            | PluginError::Remote { instance_id, .. }
+            | PluginError::Panicked { instance_id, .. } => instance_id,
+        }
+    }
+
+    pub fn phase(&self) -> PluginPhase {
+        match self {
+            PluginError::Failed { phase, .. }
+            | PluginError::InvalidResponse { phase, .. }
+            | PluginError::Remote { phase, .. }
+            | PluginError::Panicked { phase, .. } => *phase,
+        }
+    }
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "插件 {} ({}) 在 {} 阶段",
+            self.plugin_id(),
+            self.instance_id(),
+            self.phase()
+        )?;
+        match self {
+            PluginError::Failed { code, message, .. } => {
+                write!(f, "失败，错误码 {}: {}", code, message)
+            }
+            PluginError::InvalidResponse { message, .. } => {
+                write!(f, "返回了无效响应: {}", message)
+            }
+            PluginError::Remote { message, .. } => write!(f, "失败: {}", message),
+            PluginError::Panicked { message, .. } => write!(f, "发生 panic: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PluginError {}
@@ -4,9 +4,114 @@ use plugin_interfaces::{
     pluginui::{Context, Ui},
     PluginHandler, PluginInstanceContext, PluginInterface, PluginStreamMessage,
 };
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr, CString};
+use std::sync::{Arc, OnceLock};
 use tokio::{runtime::Runtime, sync::Mutex};
 
+/// 主程序下发的各实例设置，键为 instance_id
+static INSTANCE_SETTINGS: OnceLock<std::sync::Mutex<HashMap<String, serde_json::Value>>> =
+    OnceLock::new();
+
+fn instance_settings() -> &'static std::sync::Mutex<HashMap<String, serde_json::Value>> {
+    INSTANCE_SETTINGS.get_or_init(|| std::sync::Mutex::new(HashMap::new()))
+}
+
+/// 各实例最近一次的错误信息，键为 instance_id
+static LAST_ERRORS: OnceLock<std::sync::Mutex<HashMap<String, CString>>> = OnceLock::new();
+
+fn last_errors() -> &'static std::sync::Mutex<HashMap<String, CString>> {
+    LAST_ERRORS.get_or_init(|| std::sync::Mutex::new(HashMap::new()))
+}
+
+/// 主程序扩展回调，布局与主程序的 `HostExtensionCallbacks` 一致
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct HostExtensionCallbacks {
+    pub get_secret: extern "C" fn(scope_token: *const c_char, name: *const c_char) -> *const c_char,
+    pub free_string: extern "C" fn(ptr: *mut c_char),
+}
+
+/// 主程序为各实例注册的作用域令牌和扩展回调，键为 instance_id
+type HostExtensions = HashMap<String, (CString, HostExtensionCallbacks)>;
+
+static HOST_EXTENSIONS: OnceLock<std::sync::Mutex<HostExtensions>> = OnceLock::new();
+
+fn host_extensions() -> &'static std::sync::Mutex<HostExtensions> {
+    HOST_EXTENSIONS.get_or_init(|| std::sync::Mutex::new(HashMap::new()))
+}
+
+/// 通过主程序扩展回调读取本插件的密钥
+///
+/// 主程序返回的字符串复制后立即通过 `free_string` 交还主程序释放
+fn read_host_secret(instance_id: &str, name: &str) -> Option<String> {
+    let (scope_token, callbacks) = host_extensions()
+        .lock()
+        .unwrap()
+        .get(instance_id)
+        .map(|(scope_token, callbacks)| (scope_token.clone(), *callbacks))?;
+    let name = CString::new(name).ok()?;
+
+    let secret = (callbacks.get_secret)(scope_token.as_ptr(), name.as_ptr());
+    if secret.is_null() {
+        return None;
+    }
+    let value = unsafe { CStr::from_ptr(secret) }
+        .to_string_lossy()
+        .into_owned();
+    (callbacks.free_string)(secret as *mut c_char);
+    Some(value)
+}
+
+/// 实例在上述全局表中的登记，插件实例被销毁（最后一个副本释放）时清理该实例的条目
+struct InstanceRegistration {
+    instance_id: String,
+}
+
+impl Drop for InstanceRegistration {
+    fn drop(&mut self) {
+        if let Ok(mut settings) = instance_settings().lock() {
+            settings.remove(&self.instance_id);
+        }
+        if let Ok(mut errors) = last_errors().lock() {
+            errors.remove(&self.instance_id);
+        }
+        if let Ok(mut extensions) = host_extensions().lock() {
+            extensions.remove(&self.instance_id);
+        }
+    }
+}
+
+/// 记录实例的错误信息，供主程序通过 `plugin_last_error` 查询
+fn record_error(instance_id: &str, message: String) {
+    log_warn!("{}", message);
+    if let Ok(message) = CString::new(message) {
+        last_errors()
+            .lock()
+            .unwrap()
+            .insert(instance_id.to_string(), message);
+    }
+}
+
+/// 在导出函数边界捕获 panic
+///
+/// panic 不能跨越 `extern "C"` 边界展开，否则会直接终止主程序。
+/// 捕获后记录错误信息并返回错误码，主程序通过 `plugin_last_error` 读取错误信息
+fn catch_panic(instance_id: &str, f: impl FnOnce() -> i32) -> i32 {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(code) => code,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            record_error(instance_id, format!("Plugin panicked: {}", message));
+            -1
+        }
+    }
+}
+
 /// 示例插件实现 - 使用新的UI框架
 #[derive(Clone)]
 pub struct ExamplePlugin {
@@ -14,7 +119,8 @@ pub struct ExamplePlugin {
     age: Arc<Mutex<u32>>, // 使用 Arc<Mutex<T>> 包装以支持异步修改
     selected_option: Option<String>,
     dark_mode: bool,
-    runtime: Option<Arc<Runtime>>, // tokio 异步运行时
+    runtime: Option<Arc<Runtime>>,                   // tokio 异步运行时
+    registration: Option<Arc<InstanceRegistration>>, // 实例销毁时清理全局表
 }
 
 impl ExamplePlugin {
@@ -26,6 +132,7 @@ impl ExamplePlugin {
             selected_option: None,
             dark_mode: false,
             runtime: None, // 在 on_mount 时初始化
+            registration: None,
         }
     }
     fn theme_switcher(&mut self, ui: &mut Ui, _ctx: &Context, plugin_ctx: &PluginInstanceContext) {
@@ -278,6 +385,10 @@ impl PluginHandler for ExamplePlugin {
         plugin_ctx: &PluginInstanceContext,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let metadata = plugin_ctx.get_metadata();
+        self.registration = metadata
+            .instance_id
+            .clone()
+            .map(|instance_id| Arc::new(InstanceRegistration { instance_id }));
         log_info!("[{}] Plugin mount successfully", metadata.name);
         log_info!(
             "Config Metadata: id={}, name={}, version={}, instance_id={}",
@@ -367,6 +478,12 @@ impl PluginHandler for ExamplePlugin {
         message: &str,
         plugin_ctx: &PluginInstanceContext,
     ) -> Result<String, Box<dyn std::error::Error>> {
+        // 演示进程隔离：隔离模式下只有插件宿主进程退出，主程序将实例标记为崩溃，可通过重启恢复；
+        // 非隔离模式下会使整个应用退出
+        if message == "/crash" {
+            std::process::abort();
+        }
+
         let metadata = plugin_ctx.get_metadata();
         log_info!(
             "Plugin Recive Message. Metadata: id={}, name={}, version={}, instance_id={}, require_history={}",
@@ -388,11 +505,35 @@ impl PluginHandler for ExamplePlugin {
             "".to_string()
         };
 
+        // 读取主程序下发的回复前缀设置
+        let reply_prefix = metadata
+            .instance_id
+            .as_ref()
+            .and_then(|id| {
+                let settings = instance_settings().lock().unwrap();
+                settings
+                    .get(id)
+                    .and_then(|value| value.get("reply_prefix"))
+                    .and_then(|value| value.as_str())
+                    .map(|value| value.to_string())
+            })
+            .unwrap_or_else(|| "Echo".to_string());
+
+        // 签名保存在主程序的密钥库中，通过扩展回调读取
+        let signature = metadata
+            .instance_id
+            .as_ref()
+            .and_then(|id| read_host_secret(id, "signature"))
+            .map(|signature| format!(" -- {}", signature))
+            .unwrap_or_default();
+
         let response = format!(
-            "Echo from {}: {}{}",
+            "{} from {}: {}{}{}",
+            reply_prefix,
             plugin_ctx.get_metadata().name,
             message,
-            history_info
+            history_info,
+            signature
         );
 
         // 向前端发送响应
@@ -404,12 +545,116 @@ impl PluginHandler for ExamplePlugin {
     }
 }
 
-/// 创建插件实例的导出函数
+// 导出插件构建时使用的 ABI 版本，主程序据此拒绝不兼容的插件
+plugin_abi::export_plugin_abi_version!();
+
+/// 创建插件实例的导出函数，创建失败（包括 panic）时返回 null
 #[no_mangle]
 pub extern "C" fn create_plugin() -> *mut PluginInterface {
-    let plugin = ExamplePlugin::new();
-    let handler: Box<dyn PluginHandler> = Box::new(plugin);
-    create_plugin_interface_from_handler(handler)
+    std::panic::catch_unwind(|| {
+        let plugin = ExamplePlugin::new();
+        let handler: Box<dyn PluginHandler> = Box::new(plugin);
+        create_plugin_interface_from_handler(handler)
+    })
+    .unwrap_or(std::ptr::null_mut())
+}
+
+/// 接收主程序下发设置的导出函数
+///
+/// # Safety
+///
+/// `instance_id` 和 `settings_json` 必须是有效的、以 null 结尾的 C 字符串，
+/// 且在调用期间保持有效
+#[no_mangle]
+pub unsafe extern "C" fn receive_plugin_settings(
+    instance_id: *const c_char,
+    settings_json: *const c_char,
+) -> i32 {
+    if instance_id.is_null() || settings_json.is_null() {
+        return -1;
+    }
+    let (Ok(instance_id), Ok(settings_json)) = (
+        CStr::from_ptr(instance_id).to_str(),
+        CStr::from_ptr(settings_json).to_str(),
+    ) else {
+        return -1;
+    };
+    catch_panic(instance_id, || {
+        match serde_json::from_str::<serde_json::Value>(settings_json) {
+            Ok(settings) => {
+                log_info!("Received settings for instance {}", instance_id);
+                instance_settings()
+                    .lock()
+                    .unwrap()
+                    .insert(instance_id.to_string(), settings);
+                0
+            }
+            Err(e) => {
+                record_error(instance_id, format!("Failed to parse settings: {}", e));
+                -1
+            }
+        }
+    })
+}
+
+/// 注册主程序扩展回调的导出函数
+///
+/// # Safety
+///
+/// `instance_id` 和 `scope_token` 必须是有效的、以 null 结尾的 C 字符串，
+/// 且在调用期间保持有效
+#[no_mangle]
+pub unsafe extern "C" fn register_host_extensions(
+    instance_id: *const c_char,
+    scope_token: *const c_char,
+    callbacks: HostExtensionCallbacks,
+) -> i32 {
+    if instance_id.is_null() || scope_token.is_null() {
+        return -1;
+    }
+    let Ok(instance_id) = CStr::from_ptr(instance_id).to_str() else {
+        return -1;
+    };
+    let scope_token = CStr::from_ptr(scope_token).to_owned();
+    host_extensions()
+        .lock()
+        .unwrap()
+        .insert(instance_id.to_string(), (scope_token, callbacks));
+    0
+}
+
+/// 查询实例最近一次错误信息的导出函数
+///
+/// 返回的字符串由插件持有，在下一次记录该实例的错误之前有效；没有错误时返回 null
+///
+/// # Safety
+///
+/// `instance_id` 必须是有效的、以 null 结尾的 C 字符串
+#[no_mangle]
+pub unsafe extern "C" fn plugin_last_error(instance_id: *const c_char) -> *const c_char {
+    if instance_id.is_null() {
+        return std::ptr::null();
+    }
+    let Ok(instance_id) = CStr::from_ptr(instance_id).to_str() else {
+        return std::ptr::null();
+    };
+    last_errors()
+        .lock()
+        .unwrap()
+        .get(instance_id)
+        .map_or(std::ptr::null(), |message| message.as_ptr())
+}
+
+/// 释放插件返回给主程序的字符串（例如 `handle_message` 的响应）
+///
+/// # Safety
+///
+/// `ptr` 必须是插件通过 `CString::into_raw` 返回的指针，且只能释放一次
+#[no_mangle]
+pub unsafe extern "C" fn free_plugin_string(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(CString::from_raw(ptr));
+    }
 }
 
 /// 销毁插件实例的导出函数
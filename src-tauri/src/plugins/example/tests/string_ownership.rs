@@ -0,0 +1,208 @@
+//! 验证跨动态库边界的字符串所有权：
+//! - `handle_message` 的响应经 `free_plugin_string` 释放后不会泄漏
+//! - 主程序扩展回调返回的字符串由插件通过 `free_string` 交还主程序释放
+//! - 插件实例销毁后清理该实例的设置和错误信息
+
+use example::{
+    create_plugin, destroy_plugin, free_plugin_string, plugin_last_error, receive_plugin_settings,
+    register_host_extensions, HostExtensionCallbacks,
+};
+use plugin_interfaces::{HostCallbacks, PluginInterface, PluginMetadata};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 统计当前线程存活的堆内存字节数
+///
+/// 只统计当前线程，测试并行运行或插件的后台线程分配内存时不影响结果
+struct CountingAllocator;
+
+thread_local! {
+    static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+fn track(delta: isize) {
+    let _ = LIVE_BYTES.try_with(|live| live.set(live.get() + delta));
+}
+
+fn live_bytes() -> isize {
+    LIVE_BYTES.with(Cell::get)
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        track(layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        track(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        track(new_size as isize - layout.size() as isize);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+extern "C" fn send_to_frontend(_event: *const c_char, _payload: *const c_char) -> bool {
+    true
+}
+
+extern "C" fn get_app_config(_key: *const c_char) -> *const c_char {
+    std::ptr::null()
+}
+
+extern "C" fn call_other_plugin(
+    _plugin_id: *const c_char,
+    _message: *const c_char,
+) -> *const c_char {
+    std::ptr::null()
+}
+
+/// 模拟主程序扩展回调分配和释放的字符串数量
+static ISSUED_HOST_STRINGS: AtomicUsize = AtomicUsize::new(0);
+static FREED_HOST_STRINGS: AtomicUsize = AtomicUsize::new(0);
+
+const SCOPE_TOKEN: &str = "test-scope-token";
+
+extern "C" fn get_secret(scope_token: *const c_char, name: *const c_char) -> *const c_char {
+    let scope_token = unsafe { CStr::from_ptr(scope_token) }.to_str().unwrap();
+    let name = unsafe { CStr::from_ptr(name) }.to_str().unwrap();
+    if scope_token != SCOPE_TOKEN || name != "signature" {
+        return std::ptr::null();
+    }
+    ISSUED_HOST_STRINGS.fetch_add(1, Ordering::SeqCst);
+    CString::new("from host").unwrap().into_raw()
+}
+
+extern "C" fn free_host_string(ptr: *mut c_char) {
+    assert!(!ptr.is_null());
+    FREED_HOST_STRINGS.fetch_add(1, Ordering::SeqCst);
+    drop(unsafe { CString::from_raw(ptr) });
+}
+
+/// 创建、初始化并挂载插件实例
+fn mount(instance_id: &str) -> *mut PluginInterface {
+    let interface = create_plugin();
+    assert!(!interface.is_null());
+
+    let metadata = PluginMetadata {
+        id: "example".to_string(),
+        disabled: false,
+        name: "Example".to_string(),
+        description: String::new(),
+        version: "1.0.0".to_string(),
+        author: None,
+        library_path: None,
+        config_path: String::new(),
+        instance_id: Some(instance_id.to_string()),
+        require_history: false,
+    };
+    let callbacks = HostCallbacks {
+        send_to_frontend,
+        get_app_config,
+        call_other_plugin,
+    };
+
+    unsafe {
+        let metadata_ffi = metadata.to_ffi();
+        let result = ((*interface).initialize)((*interface).plugin_ptr, callbacks, metadata_ffi);
+        plugin_interfaces::metadata::free_plugin_metadata_ffi(metadata_ffi);
+        assert_eq!(result, 0);
+        assert_eq!(((*interface).on_mount)((*interface).plugin_ptr), 0);
+    }
+    interface
+}
+
+fn unmount(interface: *mut PluginInterface) {
+    unsafe {
+        assert_eq!(((*interface).on_dispose)((*interface).plugin_ptr), 0);
+        destroy_plugin(interface);
+    }
+}
+
+/// 发送消息并返回响应，响应字符串通过 `free_plugin_string` 交还插件释放
+fn send_message(interface: *mut PluginInterface, message: &CStr) -> String {
+    unsafe {
+        let mut response: *mut c_char = std::ptr::null_mut();
+        let result =
+            ((*interface).handle_message)((*interface).plugin_ptr, message.as_ptr(), &mut response);
+        assert_eq!(result, 0);
+        assert!(!response.is_null());
+        let text = CStr::from_ptr(response).to_str().unwrap().to_string();
+        free_plugin_string(response);
+        text
+    }
+}
+
+#[test]
+fn handle_message_responses_are_released() {
+    let interface = mount("string-ownership-response");
+    let message = CString::new("ping").unwrap();
+
+    // 预热，排除日志缓冲区等一次性分配
+    for _ in 0..16 {
+        drop(send_message(interface, &message));
+    }
+
+    let before = live_bytes();
+    for _ in 0..1000 {
+        drop(send_message(interface, &message));
+    }
+    let leaked = live_bytes() - before;
+
+    unmount(interface);
+
+    // 每条响应至少几十字节，1000 次调用若未释放会远超该阈值
+    assert!(leaked < 1024, "handle_message 泄漏了 {} 字节", leaked);
+}
+
+#[test]
+fn host_callback_strings_are_returned_to_host() {
+    let instance_id = CString::new("string-ownership-host").unwrap();
+    let interface = mount(instance_id.to_str().unwrap());
+
+    let scope_token = CString::new(SCOPE_TOKEN).unwrap();
+    let callbacks = HostExtensionCallbacks {
+        get_secret,
+        free_string: free_host_string,
+    };
+    let result =
+        unsafe { register_host_extensions(instance_id.as_ptr(), scope_token.as_ptr(), callbacks) };
+    assert_eq!(result, 0);
+
+    let message = CString::new("ping").unwrap();
+    for _ in 0..100 {
+        let response = send_message(interface, &message);
+        assert!(response.ends_with(" -- from host"), "{}", response);
+    }
+
+    unmount(interface);
+
+    let issued = ISSUED_HOST_STRINGS.load(Ordering::SeqCst);
+    assert_eq!(issued, 100);
+    assert_eq!(FREED_HOST_STRINGS.load(Ordering::SeqCst), issued);
+}
+
+#[test]
+fn instance_state_is_cleared_on_destroy() {
+    let instance_id = CString::new("string-ownership-destroy").unwrap();
+    let interface = mount(instance_id.to_str().unwrap());
+
+    let invalid_settings = CString::new("not json").unwrap();
+    let result =
+        unsafe { receive_plugin_settings(instance_id.as_ptr(), invalid_settings.as_ptr()) };
+    assert_ne!(result, 0);
+    assert!(!unsafe { plugin_last_error(instance_id.as_ptr()) }.is_null());
+
+    unmount(interface);
+
+    assert!(unsafe { plugin_last_error(instance_id.as_ptr()) }.is_null());
+}
@@ -0,0 +1,180 @@
+//! 主程序提供的插件扩展接口
+//!
+//! 这些符号不属于 `plugin-interfaces` 的 `PluginInterface`，插件可以按需导出。
+//! 主程序在加载插件时按名称查找，插件未导出时相应功能被跳过。
+//!
+//! 跨越动态库边界的字符串遵循“谁分配谁释放”的约定：
+//! - 作为参数传入的字符串只在调用期间有效，接收方需要自行复制
+//! - 主程序回调返回的字符串由主程序分配，插件使用完毕后必须调用 [`HostExtensionCallbacks::free_string`] 释放
+//! - 插件返回的字符串（例如 `handle_message` 的响应）由插件分配，主程序复制后调用插件导出的
+//!   [`FREE_PLUGIN_STRING_SYMBOL`] 释放
+
+use libloading::{Library, Symbol};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+/// 接收插件设置的导出函数名称
+pub const RECEIVE_SETTINGS_SYMBOL: &[u8] = b"receive_plugin_settings";
+
+/// 接收插件设置的函数签名
+///
+/// `settings_json` 为该实例生效设置的 JSON 对象，调用结束后由主程序释放
+pub type ReceiveSettingsFn =
+    unsafe extern "C" fn(instance_id: *const c_char, settings_json: *const c_char) -> i32;
+
+/// 查找插件导出的设置接收函数
+///
+/// 返回的函数指针仅在 `library` 保持加载期间有效
+pub fn resolve_receive_settings(library: &Library) -> Option<ReceiveSettingsFn> {
+    unsafe { library.get::<ReceiveSettingsFn>(RECEIVE_SETTINGS_SYMBOL) }
+        .ok()
+        .map(|symbol| *symbol)
+}
+
+/// 将设置下发给插件实例
+pub fn deliver_settings(
+    receive_settings: ReceiveSettingsFn,
+    instance_id: &str,
+    settings: &HashMap<String, toml::Value>,
+) -> Result<(), String> {
+    let settings_json =
+        serde_json::to_string(settings).map_err(|e| format!("序列化插件设置失败: {}", e))?;
+    let instance_cstr = CString::new(instance_id).map_err(|_| "实例ID转换失败".to_string())?;
+    let settings_cstr = CString::new(settings_json).map_err(|_| "插件设置转换失败".to_string())?;
+
+    let result = unsafe { receive_settings(instance_cstr.as_ptr(), settings_cstr.as_ptr()) };
+    if result != 0 {
+        return Err(format!("插件拒绝了设置，返回码: {}", result));
+    }
+    Ok(())
+}
+
+/// 注册主程序扩展回调的导出函数名称
+pub const REGISTER_HOST_EXTENSIONS_SYMBOL: &[u8] = b"register_host_extensions";
+
+/// 主程序扩展回调集合
+///
+/// 每个插件实例会获得一个作用域令牌，调用回调时必须携带该令牌，
+/// 主程序据此确定调用方插件，插件只能访问自己的资源
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct HostExtensionCallbacks {
+    /// 读取当前插件的密钥，密钥不存在或密钥库未解锁时返回 null
+    pub get_secret: extern "C" fn(scope_token: *const c_char, name: *const c_char) -> *const c_char,
+    /// 释放主程序回调（`get_app_config`、`call_other_plugin`、`get_secret`）返回的字符串
+    pub free_string: extern "C" fn(ptr: *mut c_char),
+}
+
+/// 注册主程序扩展回调的函数签名
+///
+/// `instance_id` 与 `scope_token` 仅在调用期间有效，插件需要自行复制
+pub type RegisterHostExtensionsFn = unsafe extern "C" fn(
+    instance_id: *const c_char,
+    scope_token: *const c_char,
+    callbacks: HostExtensionCallbacks,
+) -> i32;
+
+/// 向插件实例注册主程序扩展回调
+///
+/// 返回 `Ok(false)` 表示插件没有导出 [`REGISTER_HOST_EXTENSIONS_SYMBOL`]
+pub fn register_host_extensions(
+    library: &Library,
+    instance_id: &str,
+    scope_token: &str,
+    callbacks: HostExtensionCallbacks,
+) -> Result<bool, String> {
+    let register: Symbol<RegisterHostExtensionsFn> =
+        match unsafe { library.get(REGISTER_HOST_EXTENSIONS_SYMBOL) } {
+            Ok(symbol) => symbol,
+            Err(_) => return Ok(false),
+        };
+
+    let instance_cstr = CString::new(instance_id).map_err(|_| "实例ID转换失败".to_string())?;
+    let token_cstr = CString::new(scope_token).map_err(|_| "作用域令牌转换失败".to_string())?;
+
+    let result = unsafe { register(instance_cstr.as_ptr(), token_cstr.as_ptr(), callbacks) };
+    if result != 0 {
+        return Err(format!("插件拒绝了扩展回调注册，返回码: {}", result));
+    }
+    Ok(true)
+}
+
+/// 查询插件实例最近一次错误信息的导出函数名称
+pub const LAST_ERROR_SYMBOL: &[u8] = b"plugin_last_error";
+
+/// 查询插件实例最近一次错误信息的函数签名
+///
+/// 返回的字符串由插件持有，在下一次调用该实例之前有效；没有错误信息时返回 null
+pub type LastErrorFn = unsafe extern "C" fn(instance_id: *const c_char) -> *const c_char;
+
+/// 查找插件导出的错误信息查询函数
+///
+/// 返回的函数指针仅在 `library` 保持加载期间有效
+pub fn resolve_last_error(library: &Library) -> Option<LastErrorFn> {
+    unsafe { library.get::<LastErrorFn>(LAST_ERROR_SYMBOL) }
+        .ok()
+        .map(|symbol| *symbol)
+}
+
+/// 读取插件实例最近一次错误信息
+pub fn read_last_error(last_error: LastErrorFn, instance_id: &str) -> Option<String> {
+    let instance_cstr = CString::new(instance_id).ok()?;
+    let message = unsafe { last_error(instance_cstr.as_ptr()) };
+    if message.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(message) }
+        .to_str()
+        .ok()
+        .map(|message| message.to_string())
+}
+
+/// 释放插件分配的字符串的导出函数名称，插件声明的 ABI 版本要求时必须导出
+pub use plugin_abi::FREE_PLUGIN_STRING_SYMBOL;
+
+/// 释放插件分配的字符串的函数签名
+pub type FreePluginStringFn = unsafe extern "C" fn(ptr: *mut c_char);
+
+/// 查找插件导出的字符串释放函数
+///
+/// 返回的函数指针仅在 `library` 保持加载期间有效
+pub fn resolve_free_plugin_string(library: &Library) -> Option<FreePluginStringFn> {
+    unsafe { library.get::<FreePluginStringFn>(FREE_PLUGIN_STRING_SYMBOL) }
+        .ok()
+        .map(|symbol| *symbol)
+}
+
+/// 复制插件返回的字符串，复制完成后立即通过插件导出的释放函数归还内存
+///
+/// 无论字符串是否为有效的 UTF-8 都会释放；`free_string` 为 `None` 时只有旧版 ABI 的插件，
+/// 其返回的字符串无法释放
+///
+/// # Safety
+///
+/// `ptr` 必须是插件返回的非空、以 NUL 结尾的字符串，调用后不得再使用
+pub unsafe fn take_plugin_string(
+    ptr: *mut c_char,
+    free_string: Option<FreePluginStringFn>,
+) -> Result<String, std::str::Utf8Error> {
+    let value = CStr::from_ptr(ptr).to_str().map(|value| value.to_string());
+    if let Some(free_string) = free_string {
+        free_string(ptr);
+    }
+    value
+}
+
+/// 将字符串交给插件，所有权随之转移，插件需通过 `free_string` 回调释放
+pub fn into_host_string(value: String) -> *const c_char {
+    match CString::new(value) {
+        Ok(c_string) => c_string.into_raw(),
+        Err(_) => std::ptr::null(),
+    }
+}
+
+/// 释放通过 [`into_host_string`] 交给插件的字符串
+pub(crate) extern "C" fn free_host_string(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(unsafe { CString::from_raw(ptr) });
+    }
+}
@@ -0,0 +1,122 @@
+//! 插件 FFI 调用边界
+//!
+//! 所有对 `PluginInterface` 的调用都应通过 [`PluginCall`] 进行：它把插件返回的错误码转换为
+//! 带有插件、实例和调用阶段信息的 [`PluginError`]，错误描述来自插件导出的 `plugin_last_error`。
+//!
+//! 主程序通过 `call_unwind*` 以 `extern "C-unwind"` 调用插件函数，[`PluginCall::status`] 捕获展开到
+//! 主程序的 panic，转换为 [`PluginError::Panicked`]，管理器随后将该实例标记为崩溃。
+//! 以 `extern "C"` 编译的插件函数中的 panic 仍会在插件一侧直接终止进程，主程序无法捕获。
+//! 插件应在每个导出函数内部捕获 panic，返回非零错误码并通过 `plugin_last_error` 提供错误信息；
+//! 无法保证这一点的插件应在隔离模式下运行。
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::plugins::{
+    error::{PluginError, PluginPhase},
+    extensions::{self, LastErrorFn},
+};
+
+/// 生成按 `extern "C-unwind"` 调用插件函数指针的函数
+///
+/// `PluginInterface` 中的函数指针声明为 `extern "C"`，panic 展开穿过这类调用是未定义行为，
+/// 改为 C-unwind ABI 调用后，插件中允许展开的 panic 可以安全地传回主程序
+macro_rules! unwind_trampoline {
+    ($name:ident($($arg:ident: $ty:ident),*)) => {
+        /// 以 `extern "C-unwind"` 调用插件函数，展开到主程序的 panic 由 [`PluginCall::status`] 捕获
+        ///
+        /// # Safety
+        ///
+        /// 调用方需保证函数指针和参数满足插件接口的约定
+        pub unsafe fn $name<$($ty,)* R>(f: unsafe extern "C" fn($($ty),*) -> R, $($arg: $ty),*) -> R {
+            let f = std::mem::transmute::<
+                unsafe extern "C" fn($($ty),*) -> R,
+                unsafe extern "C-unwind" fn($($ty),*) -> R,
+            >(f);
+            f($($arg),*)
+        }
+    };
+}
+
+unwind_trampoline!(call_unwind1(a: A));
+unwind_trampoline!(call_unwind2(a: A, b: B));
+unwind_trampoline!(call_unwind3(a: A, b: B, c: C));
+
+/// 一次插件调用的上下文
+#[derive(Clone, Copy)]
+pub struct PluginCall<'a> {
+    plugin_id: &'a str,
+    instance_id: &'a str,
+    last_error: Option<LastErrorFn>,
+}
+
+impl<'a> PluginCall<'a> {
+    pub fn new(plugin_id: &'a str, instance_id: &'a str, last_error: Option<LastErrorFn>) -> Self {
+        Self {
+            plugin_id,
+            instance_id,
+            last_error,
+        }
+    }
+
+    /// 调用返回状态码的插件函数，非零返回值转换为 [`PluginError::Failed`]，
+    /// 展开到主程序的 panic 转换为 [`PluginError::Panicked`]
+    pub fn status<F>(&self, phase: PluginPhase, f: F) -> Result<(), PluginError>
+    where
+        F: FnOnce() -> i32,
+    {
+        let code =
+            panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| PluginError::Panicked {
+                message: panic_message(payload.as_ref()),
+                plugin_id: self.plugin_id.to_string(),
+                instance_id: self.instance_id.to_string(),
+                phase,
+            })?;
+        if code == 0 {
+            return Ok(());
+        }
+
+        let message = self
+            .last_error
+            .and_then(|last_error| extensions::read_last_error(last_error, self.instance_id))
+            .unwrap_or_else(|| format!("插件返回错误码 {}", code));
+        Err(PluginError::Failed {
+            code,
+            message,
+            plugin_id: self.plugin_id.to_string(),
+            instance_id: self.instance_id.to_string(),
+            phase,
+        })
+    }
+
+    /// 构造无效响应错误
+    pub fn invalid_response(&self, phase: PluginPhase, message: impl Into<String>) -> PluginError {
+        PluginError::InvalidResponse {
+            message: message.into(),
+            plugin_id: self.plugin_id.to_string(),
+            instance_id: self.instance_id.to_string(),
+            phase,
+        }
+    }
+
+    /// 构造宿主进程错误
+    pub fn remote(&self, phase: PluginPhase, message: impl Into<String>) -> PluginError {
+        PluginError::Remote {
+            message: message.into(),
+            plugin_id: self.plugin_id.to_string(),
+            instance_id: self.instance_id.to_string(),
+            phase,
+        }
+    }
+}
+
+/// 读取 panic 携带的描述信息
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "未知的 panic".to_string()
+    }
+}
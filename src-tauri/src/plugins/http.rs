@@ -0,0 +1,169 @@
+//! 插件仓库使用的共享 HTTP 客户端
+//!
+//! 超时、代理和额外根证书来自应用设置中的 `network` 分组，代理密码保存在密钥库中。
+//! 客户端在首次使用时创建，网络设置或代理密码变化后下次获取时重建；
+//! 请求遇到连接错误、超时、429 或 5xx 时按指数退避重试。
+
+use plugin_interfaces::{log_info, log_warn};
+use reqwest::{Certificate, Client, Proxy, RequestBuilder, Response, StatusCode};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use zeroize::Zeroizing;
+
+use crate::secrets::get_secret_vault;
+use crate::settings::{get_app_config_store, NetworkSettings};
+
+/// 代理密码在密钥库中的名称
+pub const PROXY_PASSWORD_SECRET: &str = "network.proxy_password";
+
+/// 默认连接超时（秒）
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+
+/// 默认读取超时（秒）
+pub const DEFAULT_READ_TIMEOUT_SECS: u64 = 30;
+
+/// 默认最大重试次数
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// 默认首次重试等待时间（毫秒）
+pub const DEFAULT_RETRY_BACKOFF_MS: u64 = 500;
+
+/// 单次重试等待时间上限
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// 创建客户端时使用的配置，变化时重建客户端
+#[derive(PartialEq, Eq)]
+struct ClientConfig {
+    settings: NetworkSettings,
+    proxy_password: Option<Zeroizing<String>>,
+}
+
+// 共享客户端及创建它时使用的配置
+static HTTP_CLIENT: OnceLock<Mutex<Option<(ClientConfig, Client)>>> = OnceLock::new();
+
+fn network_settings() -> NetworkSettings {
+    get_app_config_store()
+        .lock()
+        .unwrap()
+        .settings()
+        .network
+        .clone()
+}
+
+/// 从密钥库读取代理密码，未配置代理认证时返回 `None`
+fn proxy_password(settings: &NetworkSettings) -> Result<Option<Zeroizing<String>>, String> {
+    if settings.proxy_url.trim().is_empty() || settings.proxy_username.is_empty() {
+        return Ok(None);
+    }
+    get_secret_vault()
+        .lock()
+        .unwrap()
+        .get_host_secret(PROXY_PASSWORD_SECRET)
+        .map(|password| password.map(Zeroizing::new))
+        .map_err(|e| format!("读取代理密码失败，请先解锁密钥库: {}", e))
+}
+
+/// 获取共享 HTTP 客户端，网络设置或代理密码变化时重新创建
+pub fn http_client() -> Result<Client, String> {
+    let settings = network_settings();
+    let config = ClientConfig {
+        proxy_password: proxy_password(&settings)?,
+        settings,
+    };
+    let mut cached = HTTP_CLIENT.get_or_init(|| Mutex::new(None)).lock().unwrap();
+
+    if let Some((cached_config, client)) = cached.as_ref() {
+        if *cached_config == config {
+            return Ok(client.clone());
+        }
+    }
+
+    let client = build_client(
+        &config.settings,
+        config.proxy_password.as_ref().map(|p| p.as_str()),
+    )?;
+    *cached = Some((config, client.clone()));
+    Ok(client)
+}
+
+/// 按网络设置和代理密码创建 HTTP 客户端
+pub fn build_client(
+    settings: &NetworkSettings,
+    proxy_password: Option<&str>,
+) -> Result<Client, String> {
+    let mut builder = Client::builder()
+        .user_agent(concat!("chat-client/", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(Duration::from_secs(settings.connect_timeout_secs))
+        .read_timeout(Duration::from_secs(settings.read_timeout_secs));
+
+    let proxy_url = settings.proxy_url.trim();
+    if !proxy_url.is_empty() {
+        let mut proxy =
+            Proxy::all(proxy_url).map_err(|e| format!("代理地址无效 {}: {}", proxy_url, e))?;
+        if !settings.proxy_username.is_empty() {
+            proxy = proxy.basic_auth(&settings.proxy_username, proxy_password.unwrap_or_default());
+        }
+        builder = builder.proxy(proxy);
+        log_info!("插件仓库请求使用代理: {}", proxy_url);
+    }
+
+    for path in &settings.ca_certificates {
+        let pem = fs::read(path).map_err(|e| format!("读取根证书 {} 失败: {}", path, e))?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("解析根证书 {} 失败: {}", path, e))?;
+        if certificates.is_empty() {
+            return Err(format!("根证书文件 {} 中没有 PEM 证书", path));
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    builder
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))
+}
+
+/// 发送请求，遇到可重试的错误时按设置的次数和退避时间重试
+///
+/// 请求体无法复制（流式请求体）时只发送一次
+pub async fn send_with_retry(request: RequestBuilder) -> Result<Response, reqwest::Error> {
+    let settings = network_settings();
+    let mut backoff = Duration::from_millis(settings.retry_backoff_ms);
+    let mut attempt = 0;
+
+    loop {
+        let Some(retry_request) = request.try_clone() else {
+            return request.send().await;
+        };
+        let result = retry_request.send().await;
+
+        let retryable = match &result {
+            Ok(response) => is_retryable_status(response.status()),
+            Err(error) => error.is_connect() || error.is_timeout(),
+        };
+        if !retryable || attempt >= settings.max_retries {
+            return result;
+        }
+
+        attempt += 1;
+        match &result {
+            Ok(response) => log_warn!(
+                "请求 {} 返回 {}，{:?} 后第 {} 次重试",
+                response.url(),
+                response.status(),
+                backoff,
+                attempt
+            ),
+            Err(error) => log_warn!("请求失败: {}，{:?} 后第 {} 次重试", error, backoff, attempt),
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
@@ -0,0 +1,301 @@
+//! 进程隔离模式下的插件客户端
+//!
+//! 插件动态库由独立的 `plugin-host` 进程加载，主程序通过标准输入输出与其通信，
+//! 协议说明见 `plugin-host/src/main.rs`。插件崩溃只会导致宿主进程退出，主程序将实例标记为崩溃状态；
+//! 请求超时的宿主进程同样被视为崩溃并强制结束。
+//!
+//! `plugin-host` 作为 sidecar（`bundle.externalBin`）随应用打包，安装后与主程序位于同一目录。
+
+use plugin_interfaces::{log_error, log_info, log_warn, PluginMetadata};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::plugins::abi;
+
+/// 协议行前缀，必须与 `plugin-host` 保持一致
+pub const PROTOCOL_PREFIX: &str = "@@chat-client-rpc@@";
+
+/// 宿主进程可执行文件名称
+const PLUGIN_HOST_BINARY: &str = "plugin-host";
+
+/// 等待宿主进程正常退出的时间
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 等待宿主进程响应单个请求的时间
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 宿主进程发往主程序的回调处理器
+pub trait IsolatedPluginHost: Send + Sync + 'static {
+    /// 处理宿主进程的回调请求
+    fn handle_call(&self, plugin_id: &str, method: &str, params: &Value) -> Result<Value, String>;
+
+    /// 宿主进程意外退出
+    fn on_crash(&self, plugin_id: &str, instance_id: &str);
+}
+
+type PendingRequests = Arc<Mutex<HashMap<u64, Sender<Result<Value, String>>>>>;
+
+/// 运行在独立进程中的插件实例
+pub struct IsolatedPlugin {
+    plugin_id: String,
+    instance_id: String,
+    child: Mutex<Child>,
+    stdin: Arc<Mutex<ChildStdin>>,
+    pending: PendingRequests,
+    next_id: AtomicU64,
+    crashed: Arc<AtomicBool>,
+    shutting_down: Arc<AtomicBool>,
+}
+
+impl std::fmt::Debug for IsolatedPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IsolatedPlugin")
+            .field("plugin_id", &self.plugin_id)
+            .field("instance_id", &self.instance_id)
+            .field("crashed", &self.is_crashed())
+            .finish()
+    }
+}
+
+impl IsolatedPlugin {
+    /// 启动宿主进程并在其中挂载插件，返回插件实例与挂载结果（初始UI数据 `ui` 与插件 ABI 版本 `abi_version`）
+    pub fn spawn(
+        metadata: &PluginMetadata,
+        settings: Option<Value>,
+        host: Arc<dyn IsolatedPluginHost>,
+    ) -> Result<(Arc<Self>, Value), String> {
+        let library_path = metadata
+            .library_path
+            .as_ref()
+            .ok_or_else(|| format!("插件 {} 没有找到动态库文件", metadata.id))?;
+        let instance_id = metadata
+            .instance_id
+            .clone()
+            .ok_or_else(|| "缺少插件实例ID".to_string())?;
+
+        let host_path = Self::host_binary_path()?;
+        let mut child = Command::new(&host_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| format!("启动插件宿主进程 {:?} 失败: {}", host_path, e))?;
+
+        let stdin = child.stdin.take().ok_or("无法获取插件宿主进程输入")?;
+        let stdout = child.stdout.take().ok_or("无法获取插件宿主进程输出")?;
+
+        let plugin = Arc::new(Self {
+            plugin_id: metadata.id.clone(),
+            instance_id: instance_id.clone(),
+            child: Mutex::new(child),
+            stdin: Arc::new(Mutex::new(stdin)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            next_id: AtomicU64::new(1),
+            crashed: Arc::new(AtomicBool::new(false)),
+            shutting_down: Arc::new(AtomicBool::new(false)),
+        });
+        plugin.spawn_reader(BufReader::new(stdout), host);
+
+        let params = json!({
+            "library_path": library_path,
+            "metadata": {
+                "id": metadata.id,
+                "name": metadata.name,
+                "description": metadata.description,
+                "version": metadata.version,
+                "author": metadata.author,
+                "config_path": metadata.config_path,
+                "instance_id": instance_id,
+                "require_history": metadata.require_history,
+            },
+            "settings": settings,
+            "allow_legacy_abi": abi::legacy_abi_allowed(),
+        });
+        match plugin.request("mount", params) {
+            Ok(result) => {
+                log_info!("插件 {} 已在独立进程中挂载 ({})", metadata.id, instance_id);
+                Ok((plugin, result))
+            }
+            Err(e) => {
+                plugin.shutdown();
+                Err(e)
+            }
+        }
+    }
+
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    /// 宿主进程是否已崩溃
+    pub fn is_crashed(&self) -> bool {
+        self.crashed.load(Ordering::SeqCst)
+    }
+
+    /// 向宿主进程发送请求并等待结果
+    ///
+    /// 超过 [`REQUEST_TIMEOUT`] 未响应时将实例标记为崩溃并结束宿主进程，
+    /// 调用方不能在请求期间持有插件实例表锁
+    pub fn request(&self, method: &str, params: Value) -> Result<Value, String> {
+        if self.is_crashed() {
+            return Err(format!("插件 {} 已崩溃", self.plugin_id));
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = mpsc::channel();
+        self.pending.lock().unwrap().insert(id, sender);
+
+        let line = json!({ "id": id, "method": method, "params": params });
+        if let Err(e) = Self::write_line(&self.stdin, &line) {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(format!("向插件宿主进程发送请求失败: {}", e));
+        }
+
+        match receiver.recv_timeout(REQUEST_TIMEOUT) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                self.pending.lock().unwrap().remove(&id);
+                log_error!(
+                    "插件 {} ({}) 的请求 {} 超时，结束宿主进程",
+                    self.plugin_id,
+                    self.instance_id,
+                    method
+                );
+                // 宿主进程退出后由读取线程通知崩溃处理器
+                self.crashed.store(true, Ordering::SeqCst);
+                let _ = self.child.lock().unwrap().kill();
+                Err(format!("插件 {} 请求 {} 超时", self.plugin_id, method))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(format!("插件 {} 已崩溃", self.plugin_id)),
+        }
+    }
+
+    /// 卸载插件并结束宿主进程
+    pub fn shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        if !self.is_crashed() {
+            if let Err(e) = self.request("shutdown", Value::Null) {
+                log_warn!("插件 {} 宿主进程关闭请求失败: {}", self.plugin_id, e);
+            }
+        }
+
+        let mut child = self.child.lock().unwrap();
+        let deadline = std::time::Instant::now() + SHUTDOWN_TIMEOUT;
+        loop {
+            match child.try_wait() {
+                Ok(Some(_)) => return,
+                Ok(None) if std::time::Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+                _ => break,
+            }
+        }
+        log_warn!("插件 {} 宿主进程未能正常退出，强制结束", self.plugin_id);
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    /// 宿主进程可执行文件路径
+    ///
+    /// 打包时 Tauri 将 sidecar 去掉目标三元组后缀放在主程序旁边；
+    /// 开发时由 `tauri-build` 复制到 `target/<profile>` 目录，同样与主程序位于同一目录
+    fn host_binary_path() -> Result<PathBuf, String> {
+        let current_exe =
+            std::env::current_exe().map_err(|e| format!("无法获取主程序路径: {}", e))?;
+        let host_path = current_exe
+            .parent()
+            .ok_or("无法获取主程序目录")?
+            .join(format!(
+                "{}{}",
+                PLUGIN_HOST_BINARY,
+                std::env::consts::EXE_SUFFIX
+            ));
+        if !host_path.exists() {
+            return Err(format!("插件宿主程序不存在: {:?}", host_path));
+        }
+        Ok(host_path)
+    }
+
+    fn write_line(stdin: &Mutex<ChildStdin>, message: &Value) -> std::io::Result<()> {
+        let mut stdin = stdin.lock().unwrap();
+        writeln!(stdin, "{}", message)?;
+        stdin.flush()
+    }
+
+    /// 读取宿主进程输出：响应交给等待方，回调交给处理器，其余输出记录为插件日志
+    fn spawn_reader<R: BufRead + Send + 'static>(
+        &self,
+        reader: R,
+        host: Arc<dyn IsolatedPluginHost>,
+    ) {
+        let plugin_id = self.plugin_id.clone();
+        let instance_id = self.instance_id.clone();
+        let stdin = Arc::clone(&self.stdin);
+        let pending = Arc::clone(&self.pending);
+        let crashed = Arc::clone(&self.crashed);
+        let shutting_down = Arc::clone(&self.shutting_down);
+
+        std::thread::spawn(move || {
+            for line in reader.lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                let Some(payload) = line.strip_prefix(PROTOCOL_PREFIX) else {
+                    log_info!("[plugin-host:{}] {}", plugin_id, line);
+                    continue;
+                };
+                let Ok(message) = serde_json::from_str::<Value>(payload) else {
+                    log_warn!(
+                        "[plugin-host:{}] 无法解析的协议消息: {}",
+                        plugin_id,
+                        payload
+                    );
+                    continue;
+                };
+
+                if let Some(call_id) = message["call_id"].as_u64() {
+                    // 回调可能再次调用其他插件，在独立线程中处理以免阻塞读取
+                    let host = Arc::clone(&host);
+                    let stdin = Arc::clone(&stdin);
+                    let plugin_id = plugin_id.clone();
+                    std::thread::spawn(move || {
+                        let method = message["method"].as_str().unwrap_or_default();
+                        let reply = match host.handle_call(&plugin_id, method, &message["params"]) {
+                            Ok(result) => json!({ "call_id": call_id, "result": result }),
+                            Err(error) => json!({ "call_id": call_id, "error": error }),
+                        };
+                        let _ = Self::write_line(&stdin, &reply);
+                    });
+                } else if let Some(id) = message["id"].as_u64() {
+                    if let Some(sender) = pending.lock().unwrap().remove(&id) {
+                        let result = match message["error"].as_str() {
+                            Some(error) => Err(error.to_string()),
+                            None => Ok(message["result"].clone()),
+                        };
+                        let _ = sender.send(result);
+                    }
+                }
+            }
+
+            // 宿主进程输出已关闭，视为进程退出
+            let unexpected = !shutting_down.load(Ordering::SeqCst);
+            if unexpected {
+                crashed.store(true, Ordering::SeqCst);
+            }
+            for (_, sender) in pending.lock().unwrap().drain() {
+                let _ = sender.send(Err(format!("插件 {} 已崩溃", plugin_id)));
+            }
+            if unexpected {
+                log_error!("插件 {} ({}) 的宿主进程意外退出", plugin_id, instance_id);
+                host.on_crash(&plugin_id, &instance_id);
+            }
+        });
+    }
+}
@@ -1,7 +1,35 @@
 use plugin_interfaces::{log_warn, PluginMetadata};
+use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
-use crate::plugins::{config::PluginConfig, directories::get_plugins_directories};
+use crate::plugins::{
+    abi::read_library_abi_version,
+    compatibility::{check_compatibility, check_library_arch},
+    config::PluginConfig,
+    directories::{get_plugins_directories, get_root_plugin_installed_directory},
+    lockfile::{verify_installed_library, InstalledLock},
+    package::select_target_library,
+};
+
+/// 无法加载的本地插件（与当前客户端不兼容，或动态库与安装记录不一致）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncompatiblePluginInfo {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub config_path: String,
+    /// 不兼容原因
+    pub reason: String,
+}
+
+/// 扫描到的可加载插件
+#[derive(Debug, Clone, Serialize)]
+pub struct ScannedPlugin {
+    #[serde(flatten)]
+    pub metadata: PluginMetadata,
+    /// 从动态库读取并协商得到的 ABI 版本，没有找到动态库时为 `None`
+    pub abi_version: Option<u32>,
+}
 
 #[derive(Debug)]
 pub struct PluginLoader;
@@ -13,8 +41,57 @@ impl PluginLoader {
 
     /// 扫描并返回插件列表
     /// 同时扫描 (pwd)/plugins 和 (pwd)/src-tauri/src/plugins 目录下的所有包含 config.toml 的子目录
+    /// 与当前客户端版本或平台不兼容、或动态库校验失败的插件会被跳过，可通过 [`Self::scan_incompatible_plugins`] 查看原因
     pub fn scan_plugins(&self) -> Vec<PluginMetadata> {
-        let mut plugins = Vec::new();
+        self.scan_plugins_with_abi()
+            .into_iter()
+            .map(|plugin| plugin.metadata)
+            .collect()
+    }
+
+    /// 扫描并返回插件列表，附带每个插件协商得到的 ABI 版本
+    pub fn scan_plugins_with_abi(&self) -> Vec<ScannedPlugin> {
+        self.scan_all_plugins()
+            .into_iter()
+            .filter_map(|(plugin, incompatible_reason)| match incompatible_reason {
+                Some(reason) => {
+                    log_warn!(
+                        "Plugin '{}' is incompatible and will not be loaded: {}",
+                        plugin.metadata.id,
+                        reason
+                    );
+                    None
+                }
+                None => Some(plugin),
+            })
+            .collect()
+    }
+
+    /// 扫描与当前客户端不兼容的插件及其原因
+    pub fn scan_incompatible_plugins(&self) -> Vec<IncompatiblePluginInfo> {
+        self.scan_all_plugins()
+            .into_iter()
+            .filter_map(|(plugin, incompatible_reason)| {
+                incompatible_reason.map(|reason| IncompatiblePluginInfo {
+                    id: plugin.metadata.id,
+                    name: plugin.metadata.name,
+                    version: plugin.metadata.version,
+                    config_path: plugin.metadata.config_path,
+                    reason,
+                })
+            })
+            .collect()
+    }
+
+    /// 扫描所有启用的插件，附带不兼容原因
+    fn scan_all_plugins(&self) -> Vec<(ScannedPlugin, Option<String>)> {
+        let mut plugins: Vec<(ScannedPlugin, Option<String>)> = Vec::new();
+
+        // 读取安装记录，用于校验已安装插件的动态库
+        let lock = InstalledLock::load().unwrap_or_else(|e| {
+            log_warn!("Failed to load installed plugin lock file: {}", e);
+            InstalledLock::default()
+        });
 
         // 获取要扫描的插件目录列表
         let plugin_directories = get_plugins_directories();
@@ -34,21 +111,23 @@ impl PluginLoader {
                 .filter_map(|e| e.ok())
             {
                 if entry.file_type().is_dir() {
-                    if let Some(plugin_metadata) = self.load_plugin_from_directory(entry.path()) {
-                        if plugin_metadata.disabled {
+                    if let Some((plugin, incompatible_reason)) =
+                        self.load_plugin_from_directory(entry.path(), &lock)
+                    {
+                        if plugin.metadata.disabled {
                             continue;
                         }
 
                         // 检查是否已经存在相同ID的插件，避免重复加载
                         if !plugins
                             .iter()
-                            .any(|p: &PluginMetadata| p.id == plugin_metadata.id)
+                            .any(|(p, _)| p.metadata.id == plugin.metadata.id)
                         {
-                            plugins.push(plugin_metadata);
+                            plugins.push((plugin, incompatible_reason));
                         } else {
                             log_warn!(
                                 "Plugin with ID '{}' already loaded, skipping duplicate",
-                                plugin_metadata.id
+                                plugin.metadata.id
                             );
                         }
                     }
@@ -60,7 +139,12 @@ impl PluginLoader {
     }
 
     /// 从目录加载插件元数据
-    fn load_plugin_from_directory(&self, plugin_dir: &std::path::Path) -> Option<PluginMetadata> {
+    /// 返回插件元数据以及不兼容原因（兼容时为 `None`）
+    fn load_plugin_from_directory(
+        &self,
+        plugin_dir: &std::path::Path,
+        lock: &InstalledLock,
+    ) -> Option<(ScannedPlugin, Option<String>)> {
         let config_path = plugin_dir.join("config.toml");
 
         if !config_path.exists() {
@@ -68,6 +152,12 @@ impl PluginLoader {
         }
         match PluginConfig::from_file(&config_path) {
             Ok(config) => {
+                let incompatible_reason = check_compatibility(
+                    config.plugin.min_client_version.as_deref(),
+                    config.plugin.max_client_version.as_deref(),
+                    &config.plugin.platform,
+                )
+                .err();
                 let library_path = if let Some(library_name) = config.plugin.library {
                     self.find_library_file(plugin_dir, library_name.as_str())
                 } else {
@@ -79,7 +169,35 @@ impl PluginLoader {
                 if library_path.is_none() {
                     log_warn!("Failed to find library file for plugin: {:?}", config_path);
                 }
-                Some(PluginMetadata {
+
+                // 动态库架构与当前系统不一致时直接报告，而不是在加载时得到 dlopen 错误
+                let incompatible_reason = incompatible_reason.or_else(|| {
+                    check_library_arch(std::path::Path::new(library_path.as_deref()?)).err()
+                });
+
+                // 通过插件仓库安装的插件需与安装记录中的校验值一致
+                let incompatible_reason = incompatible_reason.or_else(|| {
+                    let entry = lock.get(&config.plugin.id)?;
+                    let path = library_path.as_deref()?;
+                    if !plugin_dir.starts_with(get_root_plugin_installed_directory()) {
+                        return None;
+                    }
+                    verify_installed_library(entry, std::path::Path::new(path)).err()
+                });
+
+                // 最后读取 ABI 版本，只加载通过前述检查的动态库
+                let mut abi_version = None;
+                let incompatible_reason = incompatible_reason.or_else(|| {
+                    let path = library_path.as_deref()?;
+                    match read_library_abi_version(std::path::Path::new(path), &config.plugin.id) {
+                        Ok(version) => {
+                            abi_version = Some(version);
+                            None
+                        }
+                        Err(reason) => Some(reason),
+                    }
+                });
+                let plugin_metadata = PluginMetadata {
                     id: config.plugin.id,
                     disabled: config.plugin.disabled, // 默认启用，后续可以从配置中读取
                     name: config.plugin.name,
@@ -90,7 +208,14 @@ impl PluginLoader {
                     config_path: config_path.to_string_lossy().to_string(),
                     instance_id: None,
                     require_history: config.plugin.require_history,
-                })
+                };
+                Some((
+                    ScannedPlugin {
+                        metadata: plugin_metadata,
+                        abi_version,
+                    },
+                    incompatible_reason,
+                ))
             }
             Err(e) => {
                 log_warn!("Failed to load plugin config from {:?}: {}", config_path, e);
@@ -105,14 +230,7 @@ impl PluginLoader {
         plugin_dir: &std::path::Path,
         library_name: &str,
     ) -> Option<String> {
-        // 判断是哪个平台 windows / macos / linux
-        let library_name_dylib = if cfg!(target_os = "windows") {
-            format!("{}.dll", library_name)
-        } else if cfg!(target_os = "macos") {
-            format!("lib{}.dylib", library_name)
-        } else {
-            format!("lib{}.so", library_name)
-        };
+        let library_name_dylib = library_filename(library_name);
 
         // 直接在插件目录中查找
         let direct_path = plugin_dir.join(&library_name_dylib);
@@ -120,6 +238,11 @@ impl PluginLoader {
             return Some(direct_path.to_string_lossy().to_string());
         }
 
+        // 在 .ccplugin 插件包的 lib/<目标三元组>/ 目录中查找
+        if let Some(target_path) = select_target_library(plugin_dir, &library_name_dylib) {
+            return Some(target_path.to_string_lossy().to_string());
+        }
+
         // 在插件目录的 target/debug 目录中查找（开发环境）
         let debug_path = plugin_dir
             .join("target")
@@ -162,6 +285,18 @@ impl PluginLoader {
     }
 }
 
+/// 当前平台的动态库文件名，如 Linux 下 `example-1.0.0` 对应 `libexample-1.0.0.so`
+pub fn library_filename(library_name: &str) -> String {
+    // 判断是哪个平台 windows / macos / linux
+    if cfg!(target_os = "windows") {
+        format!("{}.dll", library_name)
+    } else if cfg!(target_os = "macos") {
+        format!("lib{}.dylib", library_name)
+    } else {
+        format!("lib{}.so", library_name)
+    }
+}
+
 impl Default for PluginLoader {
     fn default() -> Self {
         Self::new()
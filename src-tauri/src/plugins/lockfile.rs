@@ -0,0 +1,212 @@
+//! 已安装插件锁文件（~/.chat_client/installed.lock）
+//!
+//! 记录每个通过插件仓库安装的插件的来源、版本和校验值。扫描插件时用记录的校验值检查动态库，
+//! 发现被替换或篡改的动态库后拒绝加载。
+//!
+//! ```toml
+//! [[plugins]]
+//! id = "example"
+//! version = "1.0.0"
+//! source = "https://github.com/luodeb/chat-client-plugin"
+//! download_url = "https://example.com/libexample.so"
+//! checksum = "sha256:..."
+//! library = "libexample-1.0.0.so"
+//! installed_at = 1760572800
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::plugins::{checksum::verify_checksum, directories::get_installed_lock_file};
+use crate::settings::store::write_file_atomic;
+
+/// 锁文件中的一条安装记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledPluginEntry {
+    pub id: String,
+    pub version: String,
+    /// 插件来源仓库
+    pub source: String,
+    pub download_url: String,
+    /// 下载时校验通过的校验值，格式同 `PlatformDownload.checksum`
+    pub checksum: String,
+    /// 动态库文件名
+    pub library: String,
+    /// 安装时间（Unix 时间戳，秒）
+    pub installed_at: u64,
+}
+
+impl InstalledPluginEntry {
+    pub fn new(
+        id: &str,
+        version: &str,
+        source: &str,
+        download_url: &str,
+        checksum: &str,
+        library: &str,
+    ) -> Self {
+        Self {
+            id: id.to_string(),
+            version: version.to_string(),
+            source: source.to_string(),
+            download_url: download_url.to_string(),
+            checksum: checksum.to_string(),
+            library: library.to_string(),
+            installed_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// 锁文件结构
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstalledLock {
+    #[serde(default)]
+    pub plugins: Vec<InstalledPluginEntry>,
+    /// 被升级替换的上一个版本，供回滚时恢复记录
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub previous: Vec<InstalledPluginEntry>,
+}
+
+impl InstalledLock {
+    /// 读取锁文件，文件不存在时返回空记录
+    pub fn load() -> Result<Self, String> {
+        let path = get_installed_lock_file();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).map_err(|e| format!("读取锁文件失败: {}", e))?;
+        toml::from_str(&content).map_err(|e| format!("解析锁文件失败: {}", e))
+    }
+
+    /// 写回锁文件，先写入临时文件再替换，写入中断时不会留下损坏的锁文件
+    pub fn save(&self) -> Result<(), String> {
+        let path = get_installed_lock_file();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        let content =
+            toml::to_string_pretty(self).map_err(|e| format!("序列化锁文件失败: {}", e))?;
+        write_file_atomic(&path, content.as_bytes()).map_err(|e| format!("写入锁文件失败: {}", e))
+    }
+
+    pub fn get(&self, plugin_id: &str) -> Option<&InstalledPluginEntry> {
+        self.plugins.iter().find(|entry| entry.id == plugin_id)
+    }
+
+    /// 记录新安装的插件，原有记录保存为上一个版本
+    pub fn record_install(&mut self, entry: InstalledPluginEntry) {
+        if let Some(index) = self.plugins.iter().position(|e| e.id == entry.id) {
+            let replaced = self.plugins.remove(index);
+            self.previous.retain(|e| e.id != replaced.id);
+            self.previous.push(replaced);
+        }
+        self.plugins.push(entry);
+    }
+
+    /// 回滚后交换当前记录与上一个版本的记录
+    pub fn record_rollback(&mut self, plugin_id: &str) {
+        let current = self
+            .plugins
+            .iter()
+            .position(|e| e.id == plugin_id)
+            .map(|index| self.plugins.remove(index));
+        let previous = self
+            .previous
+            .iter()
+            .position(|e| e.id == plugin_id)
+            .map(|index| self.previous.remove(index));
+        if let Some(previous) = previous {
+            self.plugins.push(previous);
+        }
+        if let Some(current) = current {
+            self.previous.push(current);
+        }
+    }
+
+    /// 删除插件的全部记录，返回是否存在记录
+    pub fn remove(&mut self, plugin_id: &str) -> bool {
+        let before = self.plugins.len() + self.previous.len();
+        self.plugins.retain(|e| e.id != plugin_id);
+        self.previous.retain(|e| e.id != plugin_id);
+        before != self.plugins.len() + self.previous.len()
+    }
+}
+
+/// 判断动态库文件是否变化的依据
+///
+/// 修改时间和文件大小可以被随意设置，Unix 上额外比较设备号、inode 和 ctime：替换文件会改变 inode，
+/// 修改内容或元数据都会更新 ctime，且 ctime 无法由用户设置
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(not(unix), allow(dead_code))]
+struct FileIdentity {
+    len: u64,
+    modified: SystemTime,
+    device: u64,
+    inode: u64,
+    changed: (i64, i64),
+}
+
+#[cfg(unix)]
+fn file_identity(metadata: &fs::Metadata) -> Option<FileIdentity> {
+    use std::os::unix::fs::MetadataExt;
+    Some(FileIdentity {
+        len: metadata.len(),
+        modified: metadata.modified().ok()?,
+        device: metadata.dev(),
+        inode: metadata.ino(),
+        changed: (metadata.ctime(), metadata.ctime_nsec()),
+    })
+}
+
+/// 其他平台无法可靠判断文件是否变化，每次都重新计算校验值
+#[cfg(not(unix))]
+fn file_identity(_metadata: &fs::Metadata) -> Option<FileIdentity> {
+    None
+}
+
+/// 动态库校验结果缓存，键为路径，值为（文件标识、校验值、是否通过）
+type VerificationCache = HashMap<PathBuf, (FileIdentity, String, bool)>;
+
+static VERIFICATION_CACHE: OnceLock<Mutex<VerificationCache>> = OnceLock::new();
+
+/// 校验动态库是否与安装记录一致；能确认文件未变化时复用上次的校验结果
+pub fn verify_installed_library(
+    entry: &InstalledPluginEntry,
+    library_path: &Path,
+) -> Result<(), String> {
+    let metadata = fs::metadata(library_path).map_err(|e| format!("读取动态库信息失败: {}", e))?;
+    let identity = file_identity(&metadata);
+
+    let cache = VERIFICATION_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(identity) = &identity {
+        if let Some((cached_identity, cached_checksum, passed)) =
+            cache.lock().unwrap().get(library_path)
+        {
+            if cached_identity == identity && cached_checksum == &entry.checksum {
+                return if *passed {
+                    Ok(())
+                } else {
+                    Err("动态库与安装记录的校验值不一致，可能已被篡改".to_string())
+                };
+            }
+        }
+    }
+
+    let data = fs::read(library_path).map_err(|e| format!("读取动态库失败: {}", e))?;
+    let result = verify_checksum(&data, &entry.checksum)
+        .map_err(|e| format!("动态库与安装记录不一致，可能已被篡改: {}", e));
+    if let Some(identity) = identity {
+        cache.lock().unwrap().insert(
+            library_path.to_path_buf(),
+            (identity, entry.checksum.clone(), result.is_ok()),
+        );
+    }
+    result
+}
@@ -1,16 +1,36 @@
-use crate::plugins::PluginLoader;
+use crate::plugins::{
+    abi,
+    config::PluginConfig,
+    dependencies,
+    directories::get_root_plugin_installed_directory,
+    download::DownloadTask,
+    error::{PluginError, PluginPhase},
+    extensions::{
+        self, FreePluginStringFn, HostExtensionCallbacks, LastErrorFn, ReceiveSettingsFn,
+    },
+    ffi::{call_unwind1, call_unwind2, call_unwind3, PluginCall},
+    isolation::{IsolatedPlugin, IsolatedPluginHost},
+    staging::validate_plugin_id,
+    IncompatiblePluginInfo, PluginDownloadResult, PluginLoader, PluginRepository,
+    PluginSettingField, PluginSettingsStore, PluginSettingsView, ScannedPlugin,
+};
+use crate::secrets::get_secret_vault;
+use crate::settings::get_app_config_store;
 use libloading::{Library, Symbol};
 use plugin_interfaces::metadata::HistoryMessage;
 use plugin_interfaces::{
-    log_error, log_info,
+    log_error, log_info, log_warn,
     pluginui::{Context, Ui},
     CreatePluginFn, DestroyPluginFn, HostCallbacks, PluginInterface, PluginMetadata, StreamStatus,
     CREATE_PLUGIN_SYMBOL, DESTROY_PLUGIN_SYMBOL,
 };
-use serde_json;
+use serde::{Deserialize, Serialize};
+use serde_json::{self, json, Value};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
+use std::path::Path;
 use std::sync::{Arc, Mutex, OnceLock};
 use tauri::{AppHandle, Emitter};
 use uuid::Uuid;
@@ -18,6 +38,35 @@ use uuid::Uuid;
 // 全局AppHandle存储，用于在回调函数中访问
 static GLOBAL_APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
 
+// 全局插件管理器存储，用于在插件间调用的回调函数中访问
+static GLOBAL_PLUGIN_MANAGER: OnceLock<Arc<PluginManager>> = OnceLock::new();
+
+// 插件作用域令牌到插件ID的映射，用于限定扩展回调的访问范围
+static PLUGIN_SCOPES: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+fn get_plugin_scopes() -> &'static Mutex<HashMap<String, String>> {
+    PLUGIN_SCOPES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+thread_local! {
+    // 当前线程上正在处理消息的插件ID调用链，用于检测插件间的循环调用
+    // 隔离模式下调用链随 JSON-RPC 请求传给宿主进程，回调时再由宿主进程带回
+    static PLUGIN_CALL_STACK: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// 当前线程上的插件调用链
+fn current_call_chain() -> Vec<String> {
+    PLUGIN_CALL_STACK.with(|stack| stack.borrow().clone())
+}
+
+/// 在指定的插件调用链上执行 `f`，用于在处理宿主进程回调的线程上恢复调用链
+fn with_call_chain<T>(call_chain: Vec<String>, f: impl FnOnce() -> T) -> T {
+    let previous = PLUGIN_CALL_STACK.with(|stack| stack.replace(call_chain));
+    let result = f();
+    PLUGIN_CALL_STACK.with(|stack| stack.replace(previous));
+    result
+}
+
 /// 后端流状态信息
 #[derive(Debug, Clone)]
 pub struct BackendStreamInfo {
@@ -34,15 +83,23 @@ static BACKEND_STREAM_MANAGER: OnceLock<Arc<Mutex<HashMap<String, BackendStreamI
 
 /// 插件实例信息
 pub struct PluginInstance {
-    pub metadata: PluginMetadata,            // 插件元数据，供插件使用
-    pub instance_id: String,                 // 插件实例ID，用于多实例支持
-    pub plugin_id: String,                   // 插件ID，用于标识插件类型
-    pub handler: *mut PluginInterface,       // 插件处理函数集合
-    pub library: Library,                    // 插件库句柄，用于卸载
-    pub is_mounted: bool,                    // 是否已经挂载
-    pub is_connected: bool,                  // 是否已经连接
-    pub ui_data: Option<String>,             // 保存序列化的UI数据
+    pub metadata: PluginMetadata,                // 插件元数据，供插件使用
+    pub instance_id: String,                     // 插件实例ID，用于多实例支持
+    pub plugin_id: String,                       // 插件ID，用于标识插件类型
+    pub handler: *mut PluginInterface,           // 插件处理函数集合（隔离模式下为空）
+    pub library: Option<Library>,                // 插件库句柄，用于卸载（隔离模式下为空）
+    pub isolated: Option<Arc<IsolatedPlugin>>,   // 隔离模式下的宿主进程客户端
+    pub is_mounted: bool,                        // 是否已经挂载
+    pub is_connected: bool,                      // 是否已经连接
+    pub wants_connected: bool, // 用户期望的连接状态，崩溃后保留，重启时据此恢复连接
+    pub is_crashed: bool,      // 宿主进程是否已崩溃（仅隔离模式）
+    pub ui_data: Option<String>, // 保存序列化的UI数据
     pub ui_instance: Option<Arc<Mutex<Ui>>>, // 保存UI实例以处理事件
+    pub call_lock: Arc<Mutex<()>>, // 调用锁，保证同一实例的消息处理与销毁互斥
+    pub scope_token: String,   // 作用域令牌，用于扩展回调识别调用方插件
+    pub last_error: Option<LastErrorFn>, // 插件导出的错误信息查询函数
+    pub free_string: Option<FreePluginStringFn>, // 插件导出的字符串释放函数
+    pub abi_version: u32,      // 加载时协商得到的插件 ABI 版本
 }
 
 impl std::fmt::Debug for PluginInstance {
@@ -53,6 +110,10 @@ impl std::fmt::Debug for PluginInstance {
             .field("plugin_id", &self.plugin_id)
             .field("is_mounted", &self.is_mounted)
             .field("is_connected", &self.is_connected)
+            .field("wants_connected", &self.wants_connected)
+            .field("is_isolated", &self.isolated.is_some())
+            .field("is_crashed", &self.is_crashed)
+            .field("abi_version", &self.abi_version)
             .field("has_ui_data", &self.ui_data.is_some())
             .field("has_ui_instance", &self.ui_instance.is_some())
             .finish()
@@ -62,13 +123,122 @@ impl std::fmt::Debug for PluginInstance {
 unsafe impl Send for PluginInstance {}
 unsafe impl Sync for PluginInstance {}
 
+/// 插件实例的UI状态，处理UI事件和调用 update_ui 期间持有其锁
+type SharedUi = Arc<Mutex<Ui>>;
+
+/// 调用插件所需的实例信息，从实例表中复制后即可释放实例表锁
+struct InstanceCallTarget {
+    plugin_id: String,
+    name: String,
+    handler: *mut PluginInterface,
+    isolated: Option<Arc<IsolatedPlugin>>,
+    call_lock: Arc<Mutex<()>>,
+    last_error: Option<LastErrorFn>,
+}
+
+impl InstanceCallTarget {
+    fn call<'a>(&'a self, instance_id: &'a str) -> PluginCall<'a> {
+        PluginCall::new(&self.plugin_id, instance_id, self.last_error)
+    }
+}
+
+impl From<&PluginInstance> for InstanceCallTarget {
+    fn from(instance: &PluginInstance) -> Self {
+        Self {
+            plugin_id: instance.plugin_id.clone(),
+            name: instance.metadata.name.clone(),
+            handler: instance.handler,
+            isolated: instance.isolated.clone(),
+            call_lock: Arc::clone(&instance.call_lock),
+            last_error: instance.last_error,
+        }
+    }
+}
+
+/// 插件实例状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginInstanceState {
+    Disposed,
+    Mounted,
+    Connected,
+    Crashed,
+}
+
+/// 处理隔离模式下宿主进程的回调
+struct ManagerIsolationHost;
+
+impl IsolatedPluginHost for ManagerIsolationHost {
+    fn handle_call(&self, plugin_id: &str, method: &str, params: &Value) -> Result<Value, String> {
+        let param = |name: &str| {
+            params[name]
+                .as_str()
+                .ok_or_else(|| format!("缺少参数: {}", name))
+        };
+
+        match method {
+            "send_to_frontend" => Ok(json!(PluginManager::forward_to_frontend(
+                param("event")?,
+                param("payload")?
+            ))),
+            "get_app_config" => Ok(json!(get_app_config_store()
+                .lock()
+                .unwrap()
+                .get_string_for_plugin(param("key")?))),
+            "call_other_plugin" => {
+                let manager = GLOBAL_PLUGIN_MANAGER
+                    .get()
+                    .ok_or("PluginManager not available")?;
+                // 回调在新线程中处理，调用链由宿主进程随回调带回，调用方插件本身也在链上
+                let mut call_chain: Vec<String> =
+                    serde_json::from_value(params["call_chain"].clone()).unwrap_or_default();
+                if !call_chain.iter().any(|id| id == plugin_id) {
+                    call_chain.push(plugin_id.to_string());
+                }
+                with_call_chain(call_chain, || {
+                    manager.call_other_plugin(param("plugin_id")?, param("message")?)
+                })
+                .map(Value::String)
+            }
+            // 宿主进程只运行一个插件，调用方插件由进程确定
+            "get_secret" => get_secret_vault()
+                .lock()
+                .unwrap()
+                .get(plugin_id, param("name")?)
+                .map(|secret| json!(secret)),
+            _ => Err(format!("未知的回调方法: {}", method)),
+        }
+    }
+
+    fn on_crash(&self, plugin_id: &str, instance_id: &str) {
+        if let Some(manager) = GLOBAL_PLUGIN_MANAGER.get() {
+            manager.mark_instance_crashed(plugin_id, instance_id);
+        }
+    }
+}
+
 /// 插件管理器
 #[derive(Debug)]
 pub struct PluginManager {
     loader: PluginLoader,
     instances: Arc<Mutex<HashMap<String, PluginInstance>>>, // 键为 instance_id
     plugin_instances: Arc<Mutex<HashMap<String, Vec<String>>>>, // 键为 plugin_id，值为 instance_id 列表
-    app_handle: AppHandle,
+    app_handle: Option<AppHandle>,
+}
+
+/// 记录插件错误并通过 `plugin-error` 事件通知前端，返回错误描述
+fn report_plugin_error(error: PluginError) -> String {
+    log_error!("{}", error);
+    // panic 后插件内部状态不再可信，将实例标记为崩溃，之后只能重启或关闭
+    if let (PluginError::Panicked { .. }, Some(manager)) = (&error, GLOBAL_PLUGIN_MANAGER.get()) {
+        manager.mark_instance_crashed(error.plugin_id(), error.instance_id());
+    }
+    if let Some(app_handle) = GLOBAL_APP_HANDLE.get() {
+        if let Err(e) = app_handle.emit("plugin-error", &error) {
+            log_error!("发送插件错误事件失败: {}", e);
+        }
+    }
+    error.to_string()
 }
 
 /// 获取后端流管理器实例
@@ -77,7 +247,8 @@ fn get_backend_stream_manager() -> &'static Arc<Mutex<HashMap<String, BackendStr
 }
 
 impl PluginManager {
-    pub fn new(app_handle: AppHandle) -> Self {
+    /// 创建插件管理器，没有 `AppHandle` 时（例如在测试中）不向前端发送事件
+    pub fn new(app_handle: Option<AppHandle>) -> Self {
         Self {
             loader: PluginLoader::new(),
             instances: Arc::new(Mutex::new(HashMap::new())),
@@ -86,10 +257,17 @@ impl PluginManager {
         }
     }
 
+    /// 注册全局插件管理器，供插件间调用的回调函数使用
+    pub fn register_global(manager: &Arc<PluginManager>) {
+        GLOBAL_PLUGIN_MANAGER.set(Arc::clone(manager)).ok();
+    }
+
     /// 创建主程序回调函数集合
     fn create_host_callbacks(&self) -> HostCallbacks {
         // 将AppHandle克隆并存储在静态变量中，供回调函数使用
-        GLOBAL_APP_HANDLE.set(self.app_handle.clone()).ok();
+        if let Some(app_handle) = &self.app_handle {
+            GLOBAL_APP_HANDLE.set(app_handle.clone()).ok();
+        }
 
         HostCallbacks {
             send_to_frontend: Self::host_send_to_frontend,
@@ -106,52 +284,99 @@ impl PluginManager {
                     CStr::from_ptr(event).to_str(),
                     CStr::from_ptr(payload).to_str(),
                 ) {
-                    // 如果是流式消息事件，检查和更新后端流状态
-                    if event_str == "plugin-stream" {
-                        // 检查流是否被取消，如果被取消则拒绝发送
-                        if let Some(stream_id) = Self::extract_stream_id(payload_str) {
-                            if Self::is_stream_cancelled(&stream_id) {
-                                log_info!("流 {} 已被取消，拒绝发送消息", stream_id);
-                                return false; // 返回false表示发送失败
-                            }
-                        }
+                    return Self::forward_to_frontend(event_str, payload_str);
+                }
+            }
+        }
+        false
+    }
 
-                        // 更新后端流状态
-                        Self::handle_stream_event(payload_str);
-                    }
+    /// 将插件事件发送到前端，流已被取消时返回 false
+    fn forward_to_frontend(event_str: &str, payload_str: &str) -> bool {
+        // 如果是流式消息事件，检查和更新后端流状态
+        if event_str == "plugin-stream" {
+            // 检查流是否被取消，如果被取消则拒绝发送
+            if let Some(stream_id) = Self::extract_stream_id(payload_str) {
+                if Self::is_stream_cancelled(&stream_id) {
+                    log_info!("流 {} 已被取消，拒绝发送消息", stream_id);
+                    return false; // 返回false表示发送失败
+                }
+            }
 
-                    // 实现实际的Tauri事件发送
-                    if let Some(app_handle) = GLOBAL_APP_HANDLE.get() {
-                        match app_handle.emit(event_str, payload_str) {
-                            Ok(_) => {
-                                return true;
-                            }
-                            Err(e) => {
-                                log_error!(
-                                    "[PLUGIN->FRONTEND] Failed to send event {}: {}",
-                                    event_str,
-                                    e
-                                );
-                            }
-                        }
-                    } else {
-                        log_error!("[PLUGIN->FRONTEND] AppHandle not available");
-                    }
+            // 更新后端流状态
+            Self::handle_stream_event(payload_str);
+        }
+
+        // 实现实际的Tauri事件发送
+        if let Some(app_handle) = GLOBAL_APP_HANDLE.get() {
+            match app_handle.emit(event_str, payload_str) {
+                Ok(_) => {
+                    return true;
+                }
+                Err(e) => {
+                    log_error!(
+                        "[PLUGIN->FRONTEND] Failed to send event {}: {}",
+                        event_str,
+                        e
+                    );
                 }
             }
+        } else {
+            log_error!("[PLUGIN->FRONTEND] AppHandle not available");
         }
         false
     }
 
-    /// 获取应用配置
+    /// 获取应用配置，返回的字符串由插件通过 `free_string` 扩展回调释放
     extern "C" fn host_get_app_config(key: *const c_char) -> *const c_char {
         if !key.is_null() {
             unsafe {
                 if let Ok(key_str) = CStr::from_ptr(key).to_str() {
-                    // TODO: 实现实际的配置获取逻辑
-                    let config_value = format!("config_value_for_{}", key_str);
-                    if let Ok(c_string) = CString::new(config_value) {
-                        return c_string.into_raw();
+                    let config_value = get_app_config_store()
+                        .lock()
+                        .unwrap()
+                        .get_string_for_plugin(key_str);
+                    match config_value {
+                        Some(value) => return extensions::into_host_string(value),
+                        None => log_warn!("[PLUGIN->HOST] 未知的配置项: {}", key_str),
+                    }
+                }
+            }
+        }
+        std::ptr::null()
+    }
+
+    /// 创建主程序扩展回调集合
+    fn create_host_extension_callbacks() -> HostExtensionCallbacks {
+        HostExtensionCallbacks {
+            get_secret: Self::host_get_secret,
+            free_string: extensions::free_host_string,
+        }
+    }
+
+    /// 读取调用方插件自己的密钥，返回的字符串由插件通过 `free_string` 扩展回调释放
+    extern "C" fn host_get_secret(
+        scope_token: *const c_char,
+        name: *const c_char,
+    ) -> *const c_char {
+        if !scope_token.is_null() && !name.is_null() {
+            unsafe {
+                if let (Ok(token_str), Ok(name_str)) = (
+                    CStr::from_ptr(scope_token).to_str(),
+                    CStr::from_ptr(name).to_str(),
+                ) {
+                    let plugin_id = get_plugin_scopes().lock().unwrap().get(token_str).cloned();
+                    let Some(plugin_id) = plugin_id else {
+                        log_warn!("[PLUGIN->HOST] 无效的作用域令牌");
+                        return std::ptr::null();
+                    };
+
+                    match get_secret_vault().lock().unwrap().get(&plugin_id, name_str) {
+                        Ok(Some(secret)) => return extensions::into_host_string(secret),
+                        Ok(None) => {}
+                        Err(e) => {
+                            log_warn!("[PLUGIN->HOST] 插件 {} 读取密钥失败: {}", plugin_id, e)
+                        }
                     }
                 }
             }
@@ -159,21 +384,33 @@ impl PluginManager {
         std::ptr::null()
     }
 
-    /// 调用其他插件
+    /// 调用其他插件，返回的字符串由插件通过 `free_string` 扩展回调释放
     extern "C" fn host_call_other_plugin(
         plugin_id: *const c_char,
         message: *const c_char,
     ) -> *const c_char {
         if !plugin_id.is_null() && !message.is_null() {
             unsafe {
-                if let (Ok(id_str), Ok(_msg_str)) = (
+                if let (Ok(id_str), Ok(msg_str)) = (
                     CStr::from_ptr(plugin_id).to_str(),
                     CStr::from_ptr(message).to_str(),
                 ) {
-                    // TODO: 实现实际的插件间通信逻辑
-                    let response = format!("response_from_{}", id_str);
-                    if let Ok(c_string) = CString::new(response) {
-                        return c_string.into_raw();
+                    let Some(manager) = GLOBAL_PLUGIN_MANAGER.get() else {
+                        log_error!("[PLUGIN->PLUGIN] PluginManager not available");
+                        return std::ptr::null();
+                    };
+
+                    match manager.call_other_plugin(id_str, msg_str) {
+                        Ok(response) => {
+                            let response = extensions::into_host_string(response);
+                            if response.is_null() {
+                                log_error!("[PLUGIN->PLUGIN] 插件 {} 的响应包含非法字符", id_str);
+                            }
+                            return response;
+                        }
+                        Err(e) => {
+                            log_error!("[PLUGIN->PLUGIN] 调用插件 {} 失败: {}", id_str, e);
+                        }
                     }
                 }
             }
@@ -181,11 +418,64 @@ impl PluginManager {
         std::ptr::null()
     }
 
+    /// 调用其他插件的已挂载且已连接的实例
+    ///
+    /// 主程序不会为插件间调用自动挂载目标插件，目标插件需由用户预先挂载并连接
+    pub fn call_other_plugin(&self, plugin_id: &str, message: &str) -> Result<String, String> {
+        let in_call_chain =
+            PLUGIN_CALL_STACK.with(|stack| stack.borrow().iter().any(|id| id == plugin_id));
+        if in_call_chain {
+            return Err(format!("检测到插件循环调用: {}", plugin_id));
+        }
+
+        let instance_id = self.find_connected_instance(plugin_id)?;
+        self.send_message_to_plugin_instance(plugin_id, &instance_id, message, None)
+    }
+
+    /// 查找插件已挂载且已连接的实例
+    fn find_connected_instance(&self, plugin_id: &str) -> Result<String, String> {
+        let instances = self.instances.lock().unwrap();
+        let plugin_instances = self.plugin_instances.lock().unwrap();
+
+        let mounted: Vec<&PluginInstance> = plugin_instances
+            .get(plugin_id)
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| instances.get(id))
+                    .filter(|instance| instance.is_mounted)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if mounted.is_empty() {
+            return Err(format!(
+                "插件 {} 没有已挂载的实例，请先挂载并连接该插件",
+                plugin_id
+            ));
+        }
+
+        mounted
+            .iter()
+            .find(|instance| instance.is_connected)
+            .map(|instance| instance.instance_id.clone())
+            .ok_or_else(|| format!("插件 {} 的所有实例均未连接", plugin_id))
+    }
+
     /// 扫描插件列表
     pub fn scan_plugins(&self) -> Vec<PluginMetadata> {
         self.loader.scan_plugins()
     }
 
+    /// 扫描插件列表，附带每个插件协商得到的 ABI 版本
+    pub fn scan_plugins_with_abi(&self) -> Vec<ScannedPlugin> {
+        self.loader.scan_plugins_with_abi()
+    }
+
+    /// 扫描与当前客户端不兼容的插件
+    pub fn scan_incompatible_plugins(&self) -> Vec<IncompatiblePluginInfo> {
+        self.loader.scan_incompatible_plugins()
+    }
+
     /// 挂载插件实例
     pub fn mount_plugin(
         &self,
@@ -195,18 +485,31 @@ impl PluginManager {
         // 生成或使用提供的实例ID
         let instance_id = instance_id.unwrap_or_else(|| Uuid::new_v4().to_string());
 
-        let mut instances = self.instances.lock().unwrap();
-
         // 如果实例已经存在且已挂载，直接返回成功
-        if let Some(instance) = instances.get(&instance_id) {
+        if let Some(instance) = self.instances.lock().unwrap().get(&instance_id) {
             if instance.is_mounted {
                 return Ok(format!("插件实例 {} 已经挂载", instance.metadata.name));
             }
         }
 
+        // 初始化和挂载期间插件可能回调主程序（例如调用其他插件），调用插件时不持有实例表锁
+
         // 加载插件
         let mut plugin_metadata = self.find_plugin_metadata(plugin_id)?;
         plugin_metadata.instance_id = Some(instance_id.clone());
+        Self::check_plugin_dependencies(&plugin_metadata)?;
+
+        // 隔离模式下插件在独立的宿主进程中运行
+        if get_app_config_store()
+            .lock()
+            .unwrap()
+            .settings()
+            .plugin
+            .isolation
+        {
+            return self.mount_isolated_plugin(plugin_metadata, instance_id);
+        }
+
         let library_path = plugin_metadata
             .library_path
             .as_ref()
@@ -217,6 +520,9 @@ impl PluginManager {
             Library::new(library_path).map_err(|e| format!("加载动态库失败: {}", e))?
         };
 
+        // 在调用任何插件函数之前检查 ABI 版本，拒绝不兼容的插件
+        let abi_version = abi::negotiate_abi_version(&library, plugin_id)?;
+
         // 获取创建函数
         let create_plugin: Symbol<CreatePluginFn> = unsafe {
             library
@@ -224,24 +530,44 @@ impl PluginManager {
                 .map_err(|e| format!("找不到插件创建函数: {}", e))?
         };
 
+        let last_error = extensions::resolve_last_error(&library);
+        let free_string = extensions::resolve_free_plugin_string(&library);
+        abi::check_free_plugin_string(plugin_id, abi_version, free_string.is_some())?;
+        if free_string.is_none() {
+            log_warn!(
+                "插件 {} 使用 ABI 版本 {} 且未导出 free_plugin_string，其返回的字符串将无法释放",
+                plugin_id,
+                abi_version
+            );
+        }
+        let call = PluginCall::new(plugin_id, &instance_id, last_error);
+
         // 创建插件实例
         let handler = unsafe { create_plugin() };
         if handler.is_null() {
-            return Err("插件创建失败".to_string());
+            return Err(report_plugin_error(
+                call.invalid_response(PluginPhase::Create, "插件创建函数返回了空指针"),
+            ));
         }
 
         // 初始化插件（设置回调函数和元数据）
         let callbacks = self.create_host_callbacks();
         let metadata_ffi = plugin_metadata.to_ffi();
-        let init_result =
-            unsafe { ((*handler).initialize)((*handler).plugin_ptr, callbacks, metadata_ffi) };
+        let init_result = call.status(PluginPhase::Initialize, || unsafe {
+            call_unwind3(
+                (*handler).initialize,
+                (*handler).plugin_ptr,
+                callbacks,
+                metadata_ffi,
+            )
+        });
 
         // 清理FFI元数据内存
         unsafe {
             plugin_interfaces::metadata::free_plugin_metadata_ffi(metadata_ffi);
         }
 
-        if init_result != 0 {
+        if let Err(e) = init_result {
             // 清理失败的插件实例
             unsafe {
                 let destroy_plugin: Result<Symbol<DestroyPluginFn>, _> =
@@ -250,17 +576,35 @@ impl PluginManager {
                     destroy_fn(handler);
                 }
             }
-            return Err("插件初始化失败".to_string());
+            return Err(report_plugin_error(e));
         }
 
-        // 调用 on_mount
-        let mount_result = unsafe { ((*handler).on_mount)((*handler).plugin_ptr) };
+        // 注册扩展回调，作用域令牌限定插件只能访问自己的资源
+        let scope_token = Uuid::new_v4().to_string();
+        get_plugin_scopes()
+            .lock()
+            .unwrap()
+            .insert(scope_token.clone(), plugin_id.to_string());
+        if let Err(e) = extensions::register_host_extensions(
+            &library,
+            &instance_id,
+            &scope_token,
+            Self::create_host_extension_callbacks(),
+        ) {
+            log_warn!("向插件实例 {} 注册扩展回调失败: {}", instance_id, e);
+        }
 
-        let result: Result<(), Box<dyn std::error::Error>> = if mount_result == 0 {
-            Ok(())
-        } else {
-            Err("插件挂载失败".into())
-        };
+        // 在 on_mount 之前下发插件设置
+        Self::deliver_plugin_settings(
+            extensions::resolve_receive_settings(&library),
+            &plugin_metadata,
+            &instance_id,
+        );
+
+        // 调用 on_mount
+        let result = call.status(PluginPhase::Mount, || unsafe {
+            call_unwind1((*handler).on_mount, (*handler).plugin_ptr)
+        });
 
         // 初始化UI
         let context = Context::new(instance_id.clone());
@@ -270,13 +614,17 @@ impl PluginManager {
         // 保存UI实例的引用以便后续事件处理
         let ui_instance_ref = Arc::clone(&ui_arc);
 
-        unsafe {
-            ((*handler).update_ui)(
+        let update_ui_result = call.status(PluginPhase::UpdateUi, || unsafe {
+            call_unwind3(
+                (*handler).update_ui,
                 (*handler).plugin_ptr,
                 &context as *const Context as *const std::ffi::c_void,
                 &mut *ui as *mut Ui as *mut std::ffi::c_void,
             )
-        };
+        });
+        if let Err(e) = update_ui_result {
+            report_plugin_error(e);
+        }
 
         let ui_data = match serde_json::to_string(&ui.get_components()) {
             Ok(json) => json,
@@ -294,14 +642,25 @@ impl PluginManager {
                     instance_id: instance_id.clone(),
                     plugin_id: plugin_id.to_string(),
                     handler,
-                    library,
+                    library: Some(library),
+                    isolated: None,
                     is_mounted: true,
                     is_connected: false,
+                    wants_connected: false,
+                    is_crashed: false,
                     ui_data: Some(ui_data),
                     ui_instance: Some(ui_instance_ref),
+                    call_lock: Arc::new(Mutex::new(())),
+                    scope_token,
+                    last_error,
+                    free_string,
+                    abi_version,
                 };
 
-                instances.insert(instance_id.clone(), instance);
+                self.instances
+                    .lock()
+                    .unwrap()
+                    .insert(instance_id.clone(), instance);
 
                 // 更新插件实例映射
                 let mut plugin_instances = self.plugin_instances.lock().unwrap();
@@ -322,148 +681,301 @@ impl PluginManager {
                         destroy_fn(handler);
                     }
                 }
-                Err(format!("插件挂载失败: {}", e))
+                get_plugin_scopes().lock().unwrap().remove(&scope_token);
+                Err(report_plugin_error(e))
             }
         }
     }
 
+    /// 在独立的宿主进程中挂载插件实例
+    fn mount_isolated_plugin(
+        &self,
+        plugin_metadata: PluginMetadata,
+        instance_id: String,
+    ) -> Result<String, String> {
+        let settings = Self::resolve_plugin_settings(&plugin_metadata, &instance_id)
+            .and_then(|settings| serde_json::to_value(settings).ok());
+        let (isolated, mount_result) =
+            IsolatedPlugin::spawn(&plugin_metadata, settings, Arc::new(ManagerIsolationHost))
+                .map_err(|e| {
+                    let call = PluginCall::new(&plugin_metadata.id, &instance_id, None);
+                    report_plugin_error(call.remote(PluginPhase::Mount, e))
+                })?;
+        let ui_data = mount_result["ui"].as_str().unwrap_or("[]").to_string();
+
+        let plugin_id = plugin_metadata.id.clone();
+        let abi_version = mount_result["abi_version"]
+            .as_u64()
+            .map_or(abi::LEGACY_ABI_VERSION, |version| version as u32);
+        let instance = PluginInstance {
+            metadata: plugin_metadata.clone(),
+            instance_id: instance_id.clone(),
+            plugin_id: plugin_id.clone(),
+            handler: std::ptr::null_mut(),
+            library: None,
+            isolated: Some(isolated),
+            is_mounted: true,
+            is_connected: false,
+            wants_connected: false,
+            is_crashed: false,
+            ui_data: Some(ui_data),
+            ui_instance: None,
+            call_lock: Arc::new(Mutex::new(())),
+            scope_token: String::new(),
+            last_error: None,
+            free_string: None,
+            abi_version,
+        };
+
+        self.instances
+            .lock()
+            .unwrap()
+            .insert(instance_id.clone(), instance);
+        self.plugin_instances
+            .lock()
+            .unwrap()
+            .entry(plugin_id)
+            .or_default()
+            .push(instance_id);
+
+        Ok(format!(
+            "插件 {} 实例已在独立进程中挂载成功",
+            plugin_metadata.name
+        ))
+    }
+
     /// 卸载插件实例
+    ///
+    /// 实例级别设置保留在磁盘上，重启应用或重启插件后使用相同实例ID挂载时继续生效；
+    /// 用户关闭实例时使用 [`Self::remove_plugin_instance`]
     pub fn dispose_plugin(&self, instance_id: &str) -> Result<String, String> {
-        let mut instances = self.instances.lock().unwrap();
+        // 等待正在进行的消息处理结束后再销毁插件实例
+        let call_lock = self
+            .instances
+            .lock()
+            .unwrap()
+            .get(instance_id)
+            .map(|instance| Arc::clone(&instance.call_lock));
+        let _call_guard = call_lock.as_ref().map(|lock| lock.lock().unwrap());
+
+        // 先将实例标记为已卸载并取出需要的信息，调用插件期间不持有实例表锁
+        let (target, ui_instance, was_connected, destroy_plugin, scope_token) = {
+            let mut instances = self.instances.lock().unwrap();
+            let instance = instances
+                .get_mut(instance_id)
+                .ok_or_else(|| format!("插件实例 {} 未找到", instance_id))?;
+
+            if instance.is_crashed {
+                // 崩溃的实例没有可卸载的插件，直接移除记录
+                instances.remove(instance_id);
+                return Ok(format!("已移除崩溃的插件实例 {}", instance_id));
+            }
 
-        if let Some(instance) = instances.get_mut(instance_id) {
             if !instance.is_mounted {
                 return Ok(format!("插件 {} 已经卸载", instance.metadata.name));
             }
 
+            // 动态库保留在实例记录中，函数指针在记录移除前一直有效
+            let destroy_plugin = instance.library.as_ref().and_then(|library| unsafe {
+                library
+                    .get::<DestroyPluginFn>(DESTROY_PLUGIN_SYMBOL)
+                    .ok()
+                    .map(|symbol| *symbol)
+            });
+            let target = InstanceCallTarget::from(&*instance);
+            let ui_instance = instance.ui_instance.clone();
+            let was_connected = instance.is_connected;
+            instance.is_mounted = false;
+            instance.is_connected = false;
+            instance.isolated = None;
+            (
+                target,
+                ui_instance,
+                was_connected,
+                destroy_plugin,
+                instance.scope_token.clone(),
+            )
+        };
+
+        let result: Result<(), String> = if let Some(isolated) = &target.isolated {
+            // 宿主进程负责断开连接并销毁插件
+            isolated.shutdown();
+            Ok(())
+        } else {
+            let handler = target.handler;
+            let call = target.call(instance_id);
+            // 等待正在进行的UI更新结束
+            let _ui_guard = ui_instance.as_ref().map(|ui| ui.lock().unwrap());
+
             // 先断开连接
-            if instance.is_connected {
-                let _ =
-                    unsafe { ((*instance.handler).on_disconnect)((*instance.handler).plugin_ptr) };
-                instance.is_connected = false;
+            if was_connected {
+                let disconnect_result = call.status(PluginPhase::Disconnect, || unsafe {
+                    call_unwind1((*handler).on_disconnect, (*handler).plugin_ptr)
+                });
+                if let Err(e) = disconnect_result {
+                    report_plugin_error(e);
+                }
             }
 
             // 调用 on_dispose
-            let dispose_result =
-                unsafe { ((*instance.handler).on_dispose)((*instance.handler).plugin_ptr) };
-            let result: Result<(), Box<dyn std::error::Error>> = if dispose_result == 0 {
-                Ok(())
-            } else {
-                Err("插件卸载失败".into())
-            };
+            let dispose_result = call.status(PluginPhase::Dispose, || unsafe {
+                call_unwind1((*handler).on_dispose, (*handler).plugin_ptr)
+            });
 
             // 销毁插件实例
-            unsafe {
-                let destroy_plugin: Result<Symbol<DestroyPluginFn>, _> =
-                    instance.library.get(DESTROY_PLUGIN_SYMBOL);
-                if let Ok(destroy_fn) = destroy_plugin {
-                    destroy_fn(instance.handler);
-                }
+            if let Some(destroy_fn) = destroy_plugin {
+                unsafe { destroy_fn(handler) };
             }
 
-            let plugin_id = instance.plugin_id.clone();
-            let instance_name = instance.metadata.name.clone();
+            dispose_result.map_err(report_plugin_error)
+        };
 
-            instance.is_mounted = false;
+        // 吊销作用域令牌
+        get_plugin_scopes().lock().unwrap().remove(&scope_token);
 
-            // TODO: 清理插件元数据 - 需要重新实现以支持实例级别管理
+        // TODO: 清理插件元数据 - 需要重新实现以支持实例级别管理
 
-            // 从插件实例映射中移除
-            let mut plugin_instances = self.plugin_instances.lock().unwrap();
-            if let Some(instance_list) = plugin_instances.get_mut(&plugin_id) {
-                instance_list.retain(|id| id != instance_id);
-                if instance_list.is_empty() {
-                    plugin_instances.remove(&plugin_id);
-                }
+        // 从插件实例映射中移除
+        let mut plugin_instances = self.plugin_instances.lock().unwrap();
+        if let Some(instance_list) = plugin_instances.get_mut(&target.plugin_id) {
+            instance_list.retain(|id| id != instance_id);
+            if instance_list.is_empty() {
+                plugin_instances.remove(&target.plugin_id);
             }
-            drop(plugin_instances);
-
-            match result {
-                Ok(_) => Ok(format!(
-                    "插件实例 {} ({}) 卸载成功",
-                    instance_name, instance_id
-                )),
-                Err(e) => Ok(format!(
-                    "插件实例 {} ({}) 卸载完成，但有警告: {}",
-                    instance_name, instance_id, e
-                )),
-            }
-        } else {
-            Err(format!("插件实例 {} 未找到", instance_id))
+        }
+        drop(plugin_instances);
+
+        match result {
+            Ok(_) => Ok(format!(
+                "插件实例 {} ({}) 卸载成功",
+                target.name, instance_id
+            )),
+            Err(e) => Ok(format!(
+                "插件实例 {} ({}) 卸载完成，但有警告: {}",
+                target.name, instance_id, e
+            )),
         }
     }
 
+    /// 关闭插件实例：卸载实例并删除其实例级别设置，该实例ID此后不再使用
+    pub fn remove_plugin_instance(&self, instance_id: &str) -> Result<String, String> {
+        let plugin_id = self
+            .instances
+            .lock()
+            .unwrap()
+            .get(instance_id)
+            .map(|instance| instance.plugin_id.clone())
+            .ok_or_else(|| format!("插件实例 {} 未找到", instance_id))?;
+
+        let message = self.dispose_plugin(instance_id)?;
+        self.instances.lock().unwrap().remove(instance_id);
+        Self::remove_instance_settings(&plugin_id, instance_id);
+        Ok(message)
+    }
+
     /// 连接插件实例
     pub fn connect_plugin(&self, instance_id: &str) -> Result<String, String> {
-        let mut instances = self.instances.lock().unwrap();
-
-        if let Some(instance) = instances.get_mut(instance_id) {
+        let target = {
+            let instances = self.instances.lock().unwrap();
+            let instance = instances
+                .get(instance_id)
+                .ok_or_else(|| format!("插件实例 {} 未找到", instance_id))?;
             if !instance.is_mounted {
                 return Err(format!("插件 {} 未挂载", instance.metadata.name));
             }
-
             if instance.is_connected {
                 return Ok(format!("插件 {} 已经连接", instance.metadata.name));
             }
+            InstanceCallTarget::from(instance)
+        };
 
-            let connect_result =
-                unsafe { ((*instance.handler).on_connect)((*instance.handler).plugin_ptr) };
-            let result: Result<(), Box<dyn std::error::Error>> = if connect_result == 0 {
-                Ok(())
-            } else {
-                Err("插件连接失败".into())
-            };
+        // 持有调用锁防止实例被销毁，调用插件期间不持有实例表锁
+        let _call_guard = target.call_lock.lock().unwrap();
+        if !matches!(self.get_plugin_status(instance_id), Some((true, false))) {
+            return Err(format!("插件实例 {} 已卸载或状态已改变", instance_id));
+        }
 
-            match result {
-                Ok(_) => {
-                    instance.is_connected = true;
-                    Ok(format!(
-                        "插件实例 {} ({}) 连接成功",
-                        instance.metadata.name, instance_id
-                    ))
-                }
-                Err(e) => Err(format!("插件实例连接失败: {}", e)),
+        let call = target.call(instance_id);
+        let handler = target.handler;
+        let result = match &target.isolated {
+            Some(isolated) => isolated
+                .request("connect", Value::Null)
+                .map(|_| ())
+                .map_err(|e| call.remote(PluginPhase::Connect, e)),
+            None => call.status(PluginPhase::Connect, || unsafe {
+                call_unwind1((*handler).on_connect, (*handler).plugin_ptr)
+            }),
+        }
+        .map_err(report_plugin_error);
+
+        match result {
+            Ok(_) => {
+                self.set_instance_connected(instance_id, true);
+                Ok(format!(
+                    "插件实例 {} ({}) 连接成功",
+                    target.name, instance_id
+                ))
             }
-        } else {
-            Err(format!("插件实例 {} 未找到", instance_id))
+            Err(e) => Err(format!("插件实例连接失败: {}", e)),
         }
     }
 
     /// 断开插件实例连接
     pub fn disconnect_plugin(&self, instance_id: &str) -> Result<String, String> {
-        let mut instances = self.instances.lock().unwrap();
-
-        if let Some(instance) = instances.get_mut(instance_id) {
+        let target = {
+            let instances = self.instances.lock().unwrap();
+            let instance = instances
+                .get(instance_id)
+                .ok_or_else(|| format!("插件实例 {} 未找到", instance_id))?;
             if !instance.is_mounted {
                 return Err(format!("插件 {} 未挂载", instance.metadata.name));
             }
-
             if !instance.is_connected {
                 return Ok(format!("插件 {} 已经断开连接", instance.metadata.name));
             }
+            InstanceCallTarget::from(instance)
+        };
 
-            let disconnect_result =
-                unsafe { ((*instance.handler).on_disconnect)((*instance.handler).plugin_ptr) };
-            let result: Result<(), Box<dyn std::error::Error>> = if disconnect_result == 0 {
-                Ok(())
-            } else {
-                Err("插件断开连接失败".into())
-            };
+        // 持有调用锁防止实例被销毁，调用插件期间不持有实例表锁
+        let _call_guard = target.call_lock.lock().unwrap();
+        if !matches!(self.get_plugin_status(instance_id), Some((true, true))) {
+            return Err(format!("插件实例 {} 已卸载或状态已改变", instance_id));
+        }
 
-            instance.is_connected = false;
+        let call = target.call(instance_id);
+        let handler = target.handler;
+        let result = match &target.isolated {
+            Some(isolated) => isolated
+                .request("disconnect", Value::Null)
+                .map(|_| ())
+                .map_err(|e| call.remote(PluginPhase::Disconnect, e)),
+            None => call.status(PluginPhase::Disconnect, || unsafe {
+                call_unwind1((*handler).on_disconnect, (*handler).plugin_ptr)
+            }),
+        }
+        .map_err(report_plugin_error);
+
+        self.set_instance_connected(instance_id, false);
+
+        match result {
+            Ok(_) => Ok(format!(
+                "插件实例 {} ({}) 断开连接成功",
+                target.name, instance_id
+            )),
+            Err(e) => Ok(format!(
+                "插件实例 {} ({}) 断开连接完成，但有警告: {}",
+                target.name, instance_id, e
+            )),
+        }
+    }
 
-            match result {
-                Ok(_) => Ok(format!(
-                    "插件实例 {} ({}) 断开连接成功",
-                    instance.metadata.name, instance_id
-                )),
-                Err(e) => Ok(format!(
-                    "插件实例 {} ({}) 断开连接完成，但有警告: {}",
-                    instance.metadata.name, instance_id, e
-                )),
+    /// 更新实例的连接状态，实例已不存在或已卸载时忽略
+    fn set_instance_connected(&self, instance_id: &str, connected: bool) {
+        if let Some(instance) = self.instances.lock().unwrap().get_mut(instance_id) {
+            if instance.is_mounted {
+                instance.is_connected = connected;
+                instance.wants_connected = connected;
             }
-        } else {
-            Err(format!("插件实例 {} 未找到", instance_id))
         }
     }
 
@@ -475,12 +987,106 @@ impl PluginManager {
             .map(|instance| (instance.is_mounted, instance.is_connected))
     }
 
+    /// 获取插件加载时协商得到的 ABI 版本，优先取已挂载的实例，插件尚未加载过时返回 `None`
+    pub fn get_plugin_abi_version(&self, plugin_id: &str) -> Option<u32> {
+        let instances = self.instances.lock().unwrap();
+        let plugin_instances: Vec<&PluginInstance> = instances
+            .values()
+            .filter(|instance| instance.plugin_id == plugin_id)
+            .collect();
+        plugin_instances
+            .iter()
+            .find(|instance| instance.is_mounted)
+            .or(plugin_instances.first())
+            .map(|instance| instance.abi_version)
+    }
+
+    /// 获取插件实例的生命周期状态
+    pub fn get_plugin_instance_state(&self, instance_id: &str) -> Option<PluginInstanceState> {
+        let instances = self.instances.lock().unwrap();
+        instances.get(instance_id).map(|instance| {
+            if instance.is_crashed {
+                PluginInstanceState::Crashed
+            } else if instance.is_connected {
+                PluginInstanceState::Connected
+            } else if instance.is_mounted {
+                PluginInstanceState::Mounted
+            } else {
+                PluginInstanceState::Disposed
+            }
+        })
+    }
+
+    /// 将宿主进程已退出的实例标记为崩溃，并通知前端
+    fn mark_instance_crashed(&self, plugin_id: &str, instance_id: &str) {
+        let mut instances = self.instances.lock().unwrap();
+        let Some(instance) = instances.get_mut(instance_id) else {
+            return;
+        };
+        instance.is_crashed = true;
+        instance.is_mounted = false;
+        instance.is_connected = false;
+        instance.isolated = None;
+        drop(instances);
+
+        let mut plugin_instances = self.plugin_instances.lock().unwrap();
+        if let Some(instance_list) = plugin_instances.get_mut(plugin_id) {
+            instance_list.retain(|id| id != instance_id);
+            if instance_list.is_empty() {
+                plugin_instances.remove(plugin_id);
+            }
+        }
+        drop(plugin_instances);
+
+        let payload = json!({ "plugin": plugin_id, "instance": instance_id });
+        if let Some(app_handle) = &self.app_handle {
+            if let Err(e) = app_handle.emit("plugin-crashed", payload) {
+                log_error!("发送插件崩溃事件失败: {}", e);
+            }
+        }
+    }
+
+    /// 重启插件实例：卸载后使用相同的实例ID重新挂载，并恢复连接状态
+    ///
+    /// 崩溃的实例已被标记为断开连接，按崩溃前用户期望的连接状态恢复
+    pub fn restart_plugin(&self, instance_id: &str) -> Result<String, String> {
+        let (plugin_id, was_connected) = {
+            let instances = self.instances.lock().unwrap();
+            let instance = instances
+                .get(instance_id)
+                .ok_or_else(|| format!("插件实例 {} 未找到", instance_id))?;
+            (instance.plugin_id.clone(), instance.wants_connected)
+        };
+
+        self.dispose_plugin(instance_id)?;
+        self.instances.lock().unwrap().remove(instance_id);
+
+        let message = self.mount_plugin(&plugin_id, Some(instance_id.to_string()))?;
+        if was_connected {
+            self.connect_plugin(instance_id)?;
+        }
+        log_info!("插件实例 {} ({}) 已重启", plugin_id, instance_id);
+        Ok(message)
+    }
+
     /// 获取插件实例UI定义
     pub fn get_plugin_ui(&self, instance_id: &str) -> Result<String, String> {
         let mut instances = self.instances.lock().unwrap();
 
         if let Some(instance) = instances.get_mut(instance_id) {
-            if instance.is_mounted {
+            if let (true, Some(isolated)) = (instance.is_mounted, instance.isolated.clone()) {
+                // 请求宿主进程期间不持有实例表锁
+                drop(instances);
+                let result = isolated.request("get_ui", Value::Null).map_err(|e| {
+                    let call = PluginCall::new(isolated.plugin_id(), instance_id, None);
+                    report_plugin_error(call.remote(PluginPhase::UpdateUi, e))
+                })?;
+                let ui_data = result["ui"].as_str().unwrap_or("[]").to_string();
+                if let Some(instance) = self.instances.lock().unwrap().get_mut(instance_id) {
+                    instance.ui_data = Some(ui_data.clone());
+                }
+                Ok(ui_data)
+            } else if instance.is_mounted {
                 let ui_arc = instance.ui_instance.as_ref().ok_or("UI实例未找到")?;
                 let ui = ui_arc.lock().unwrap();
 
@@ -508,146 +1114,147 @@ impl PluginManager {
         component_id: &str,
         value: &str,
     ) -> Result<bool, String> {
-        let mut instances = self.instances.lock().unwrap();
-
-        if let Some(instance) = instances.get_mut(instance_id) {
-            if !instance.is_mounted {
-                return Err("插件实例未挂载".to_string());
-            }
+        let (target, ui_instance) = self.ui_call_target(instance_id)?;
 
-            if let Some(ui_instance) = &instance.ui_instance {
-                // 创建包含UI事件数据的Context
-                let mut ui_event_data = std::collections::HashMap::new();
-                ui_event_data.insert(component_id.to_string(), value.to_string());
-                let context = Context::with_ui_event_data(instance_id.to_string(), ui_event_data);
-                let mut ui = ui_instance.lock().unwrap();
-
-                // 只清除组件，保留事件状态用于本次update_ui
-                ui.clear_components_only();
-
-                let update_ui_result = unsafe {
-                    ((*instance.handler).update_ui)(
-                        (*instance.handler).plugin_ptr,
-                        &context as *const Context as *const std::ffi::c_void,
-                        &mut *ui as *mut Ui as *mut std::ffi::c_void,
-                    )
-                };
+        if let Some(isolated) = &target.isolated {
+            self.handle_isolated_ui(isolated, instance_id, component_id, value, "ui_update")?;
+            return Ok(true);
+        }
 
-                if update_ui_result == 0 {
-                    // 更新UI数据
-                    let ui_data = match serde_json::to_string(&ui.get_components()) {
-                        Ok(json) => json,
-                        Err(e) => {
-                            log_error!("序列化UI数据失败: {}", e);
-                            "[]".to_string()
-                        }
-                    };
-                    instance.ui_data = Some(ui_data.clone());
+        let ui_instance = ui_instance.ok_or("插件实例未找到 2")?;
+        self.update_native_ui(
+            &target,
+            &ui_instance,
+            instance_id,
+            component_id,
+            value,
+            false,
+        )?;
+        Ok(true)
+    }
 
-                    // 清除事件状态，为下次事件做准备
-                    ui.clear_events();
+    /// 处理插件实例UI事件
+    pub fn handle_plugin_ui_event(
+        &self,
+        instance_id: &str,
+        component_id: &str,
+        value: &str,
+    ) -> Result<bool, String> {
+        let (target, ui_instance) = self.ui_call_target(instance_id)?;
 
-                    drop(ui); // 释放锁
+        if let Some(isolated) = &target.isolated {
+            return self.handle_isolated_ui(isolated, instance_id, component_id, value, "ui_event");
+        }
 
-                    // 获取plugin_id用于后续通知
-                    let plugin_id = instance.plugin_id.clone();
-                    drop(instances); // 释放instances锁
+        match ui_instance {
+            Some(ui_instance) => self.update_native_ui(
+                &target,
+                &ui_instance,
+                instance_id,
+                component_id,
+                value,
+                true,
+            ),
+            None => Ok(false),
+        }
+    }
 
-                    // 发送UI更新事件到前端
-                    let _ = self.notify_plugin_ui_update(&plugin_id, instance_id);
-                }
-                Ok(true)
-            } else {
-                Err("插件实例未找到 2".to_string())
-            }
-        } else {
-            Err("插件实例未找到 3".to_string())
+    /// 获取处理UI所需的实例信息，实例未挂载时返回错误
+    fn ui_call_target(
+        &self,
+        instance_id: &str,
+    ) -> Result<(InstanceCallTarget, Option<SharedUi>), String> {
+        let instances = self.instances.lock().unwrap();
+        let instance = instances
+            .get(instance_id)
+            .ok_or_else(|| "插件实例未找到 3".to_string())?;
+        if !instance.is_mounted {
+            return Err("插件实例未挂载".to_string());
         }
+        Ok((
+            InstanceCallTarget::from(instance),
+            instance.ui_instance.clone(),
+        ))
     }
 
-    /// 处理插件实例UI事件
-    pub fn handle_plugin_ui_event(
+    /// 通过FFI调用插件的 update_ui，UI更新后通知前端
+    ///
+    /// 调用期间持有UI实例锁而不持有实例表锁，销毁实例前同样需要获取UI实例锁。
+    /// `is_event` 为 true 时先交给UI实例处理事件，未处理时不调用插件
+    fn update_native_ui(
         &self,
+        target: &InstanceCallTarget,
+        ui_instance: &Mutex<Ui>,
         instance_id: &str,
         component_id: &str,
         value: &str,
+        is_event: bool,
     ) -> Result<bool, String> {
-        let mut instances = self.instances.lock().unwrap();
+        let mut ui = ui_instance.lock().unwrap();
 
-        if let Some(instance) = instances.get_mut(instance_id) {
-            if instance.is_mounted {
-                let mut event_handled = false;
-
-                // 首先尝试使用UI实例处理事件
-                if let Some(ui_instance) = &instance.ui_instance {
-                    if let Ok(mut ui) = ui_instance.lock() {
-                        let handled = ui.handle_ui_event(component_id, value);
-                        if handled {
-                            event_handled = true;
-                        }
-                    }
-                }
+        // 获取UI实例锁期间实例可能已被卸载，需要重新确认
+        if !matches!(self.get_plugin_status(instance_id), Some((true, _))) {
+            return Err("插件实例未挂载".to_string());
+        }
 
-                // 如果事件被处理，调用 update_ui 并发送更新事件
-                if event_handled {
-                    if let Some(ui_instance) = &instance.ui_instance {
-                        // 创建包含UI事件数据的Context
-                        let mut ui_event_data = std::collections::HashMap::new();
-                        ui_event_data.insert(component_id.to_string(), value.to_string());
-                        let context =
-                            Context::with_ui_event_data(instance_id.to_string(), ui_event_data);
-                        let mut ui = ui_instance.lock().unwrap();
-
-                        // 确保UI实例也有事件数据（这是关键！）
-                        ui.handle_ui_event(component_id, value);
-
-                        // 只清除组件，保留事件状态用于本次update_ui
-                        ui.clear_components_only();
-
-                        let update_ui_result = unsafe {
-                            ((*instance.handler).update_ui)(
-                                (*instance.handler).plugin_ptr,
-                                &context as *const Context as *const std::ffi::c_void,
-                                &mut *ui as *mut Ui as *mut std::ffi::c_void,
-                            )
-                        };
-
-                        if update_ui_result == 0 {
-                            // 更新UI数据
-                            let ui_data = match serde_json::to_string(&ui.get_components()) {
-                                Ok(json) => json,
-                                Err(e) => {
-                                    log_error!("序列化UI数据失败: {}", e);
-                                    "[]".to_string()
-                                }
-                            };
-                            instance.ui_data = Some(ui_data.clone());
+        if is_event {
+            if !ui.handle_ui_event(component_id, value) {
+                return Ok(false);
+            }
+            // 确保UI实例也有事件数据（这是关键！）
+            ui.handle_ui_event(component_id, value);
+        }
 
-                            // 清除事件状态，为下次事件做准备
-                            ui.clear_events();
+        // 创建包含UI事件数据的Context
+        let mut ui_event_data = std::collections::HashMap::new();
+        ui_event_data.insert(component_id.to_string(), value.to_string());
+        let context = Context::with_ui_event_data(instance_id.to_string(), ui_event_data);
+
+        // 只清除组件，保留事件状态用于本次update_ui
+        ui.clear_components_only();
+
+        let handler = target.handler;
+        let update_ui_result = target
+            .call(instance_id)
+            .status(PluginPhase::UpdateUi, || unsafe {
+                call_unwind3(
+                    (*handler).update_ui,
+                    (*handler).plugin_ptr,
+                    &context as *const Context as *const std::ffi::c_void,
+                    &mut *ui as *mut Ui as *mut std::ffi::c_void,
+                )
+            });
 
-                            drop(ui); // 释放锁
+        if let Err(e) = update_ui_result {
+            report_plugin_error(e);
+            return Ok(true);
+        }
 
-                            // 获取plugin_id用于后续通知
-                            let plugin_id = instance.plugin_id.clone();
-                            drop(instances); // 释放instances锁
+        // 更新UI数据
+        let ui_data = match serde_json::to_string(&ui.get_components()) {
+            Ok(json) => json,
+            Err(e) => {
+                log_error!("序列化UI数据失败: {}", e);
+                "[]".to_string()
+            }
+        };
 
-                            // 发送UI更新事件到前端
-                            let _ = self.notify_plugin_ui_update(&plugin_id, instance_id);
-                        }
-                    }
-                }
+        // 清除事件状态，为下次事件做准备
+        ui.clear_events();
+        drop(ui); // 释放锁
 
-                Ok(event_handled)
-            } else {
-                Err("插件实例未挂载".to_string())
-            }
-        } else {
-            Err("插件实例未找到 4".to_string())
+        if let Some(instance) = self.instances.lock().unwrap().get_mut(instance_id) {
+            instance.ui_data = Some(ui_data);
         }
+
+        // 发送UI更新事件到前端
+        let _ = self.notify_plugin_ui_update(&target.plugin_id, instance_id);
+        Ok(true)
     }
 
     /// 向指定插件实例发送消息
+    ///
+    /// 调用插件期间不持有实例表锁，插件可以在 `handle_message` 中通过回调调用其他插件
     pub fn send_message_to_plugin_instance(
         &self,
         plugin_id: &str,
@@ -655,9 +1262,12 @@ impl PluginManager {
         message: &str,
         history: Option<Vec<HistoryMessage>>,
     ) -> Result<String, String> {
-        let mut instances = self.instances.lock().unwrap();
+        let (handler, isolated, require_history, call_lock, last_error, free_string) = {
+            let instances = self.instances.lock().unwrap();
+            let instance = instances
+                .get(instance_id)
+                .ok_or_else(|| format!("插件实例 {} 未找到", instance_id))?;
 
-        if let Some(instance) = instances.get_mut(instance_id) {
             // 验证插件ID是否匹配
             if instance.plugin_id != plugin_id {
                 return Err(format!(
@@ -674,77 +1284,202 @@ impl PluginManager {
                 return Err(format!("插件实例 {} 未连接", instance_id));
             }
 
-            // 如果插件需要历史记录，先设置历史记录
-            if instance.metadata.require_history {
-                if let Some(history_data) = &history {
-                    // 将历史记录序列化为 JSON
-                    match serde_json::to_string(history_data) {
-                        Ok(history_json) => {
-                            let history_cstr = std::ffi::CString::new(history_json)
-                                .map_err(|_| "历史记录转换失败".to_string())?;
-
-                            // 调用插件的 set_history 方法
-                            let set_history_result = unsafe {
-                                ((*instance.handler).set_history)(
-                                    (*instance.handler).plugin_ptr,
-                                    history_cstr.as_ptr(),
-                                )
-                            };
+            (
+                instance.handler,
+                instance.isolated.clone(),
+                instance.metadata.require_history,
+                Arc::clone(&instance.call_lock),
+                instance.last_error,
+                instance.free_string,
+            )
+        };
 
-                            if set_history_result != 0 {
-                                log_error!("设置插件历史记录失败");
-                            }
-                        }
-                        Err(e) => {
-                            log_error!("序列化历史记录失败: {}", e);
-                        }
-                    }
-                } else {
-                    // 清除历史记录
-                    let _ = unsafe {
-                        ((*instance.handler).set_history)(
-                            (*instance.handler).plugin_ptr,
-                            std::ptr::null(),
-                        )
-                    };
-                }
-            }
+        // 持有调用锁，防止插件在处理消息期间被销毁
+        let _call_guard = call_lock.lock().unwrap();
+
+        // 获取调用锁期间实例可能已被卸载，需要重新确认
+        if !matches!(self.get_plugin_status(instance_id), Some((true, true))) {
+            return Err(format!("插件实例 {} 已卸载或断开连接", instance_id));
+        }
 
-            // 调用插件的 handle_message 方法
-            let message_cstr =
-                std::ffi::CString::new(message).map_err(|_| "消息转换失败".to_string())?;
+        let call = PluginCall::new(plugin_id, instance_id, last_error);
+        PLUGIN_CALL_STACK.with(|stack| stack.borrow_mut().push(plugin_id.to_string()));
+        let result = match isolated {
+            Some(isolated) => Self::invoke_isolated_handle_message(
+                &isolated,
+                call,
+                require_history,
+                message,
+                history,
+            ),
+            None => Self::invoke_handle_message(
+                handler,
+                call,
+                free_string,
+                require_history,
+                message,
+                history,
+            ),
+        };
+        PLUGIN_CALL_STACK.with(|stack| {
+            stack.borrow_mut().pop();
+        });
+
+        result
+    }
+
+    /// 通过宿主进程调用插件的 set_history 与 handle_message
+    fn invoke_isolated_handle_message(
+        isolated: &IsolatedPlugin,
+        call: PluginCall<'_>,
+        require_history: bool,
+        message: &str,
+        history: Option<Vec<HistoryMessage>>,
+    ) -> Result<String, String> {
+        let history_json = match &history {
+            Some(history_data) if require_history => Some(
+                serde_json::to_string(history_data)
+                    .map_err(|e| format!("序列化历史记录失败: {}", e))?,
+            ),
+            _ => None,
+        };
 
-            let mut response_ptr: *mut std::ffi::c_char = std::ptr::null_mut();
-            let result = unsafe {
-                ((*instance.handler).handle_message)(
-                    (*instance.handler).plugin_ptr,
-                    message_cstr.as_ptr(),
-                    &mut response_ptr,
+        let result = isolated
+            .request(
+                "handle_message",
+                json!({
+                    "message": message,
+                    "history": history_json,
+                    "require_history": require_history,
+                    "call_chain": current_call_chain(),
+                }),
+            )
+            .map_err(|e| report_plugin_error(call.remote(PluginPhase::HandleMessage, e)))?;
+        result
+            .as_str()
+            .map(|response| response.to_string())
+            .ok_or_else(|| {
+                report_plugin_error(
+                    call.invalid_response(PluginPhase::HandleMessage, "插件返回空响应"),
                 )
+            })
+    }
+
+    /// 在宿主进程中处理UI更新或事件，UI有变化时通知前端
+    fn handle_isolated_ui(
+        &self,
+        isolated: &IsolatedPlugin,
+        instance_id: &str,
+        component_id: &str,
+        value: &str,
+        method: &str,
+    ) -> Result<bool, String> {
+        let result = isolated
+            .request(
+                method,
+                json!({ "component_id": component_id, "value": value }),
+            )
+            .map_err(|e| {
+                let call = PluginCall::new(isolated.plugin_id(), instance_id, None);
+                report_plugin_error(call.remote(PluginPhase::UpdateUi, e))
+            })?;
+        let handled = result["handled"].as_bool().unwrap_or(false);
+
+        if result["updated"].as_bool().unwrap_or(false) {
+            let ui_data = result["ui"].as_str().unwrap_or("[]").to_string();
+            let plugin_id = {
+                let mut instances = self.instances.lock().unwrap();
+                let instance = instances
+                    .get_mut(instance_id)
+                    .ok_or_else(|| format!("插件实例 {} 未找到", instance_id))?;
+                instance.ui_data = Some(ui_data);
+                instance.plugin_id.clone()
             };
+            let _ = self.notify_plugin_ui_update(&plugin_id, instance_id);
+        }
 
-            if result != 0 {
-                return Err("插件处理消息失败".to_string());
-            }
+        Ok(handled)
+    }
+
+    /// 通过FFI调用插件的 set_history 与 handle_message
+    ///
+    /// 响应字符串由插件分配，复制后通过插件导出的 `free_plugin_string` 释放
+    fn invoke_handle_message(
+        handler: *mut PluginInterface,
+        call: PluginCall<'_>,
+        free_string: Option<FreePluginStringFn>,
+        require_history: bool,
+        message: &str,
+        history: Option<Vec<HistoryMessage>>,
+    ) -> Result<String, String> {
+        // 如果插件需要历史记录，先设置历史记录
+        if require_history {
+            if let Some(history_data) = &history {
+                // 将历史记录序列化为 JSON
+                match serde_json::to_string(history_data) {
+                    Ok(history_json) => {
+                        let history_cstr = std::ffi::CString::new(history_json)
+                            .map_err(|_| "历史记录转换失败".to_string())?;
+
+                        // 调用插件的 set_history 方法
+                        let set_history_result = call.status(PluginPhase::SetHistory, || unsafe {
+                            call_unwind2(
+                                (*handler).set_history,
+                                (*handler).plugin_ptr,
+                                history_cstr.as_ptr(),
+                            )
+                        });
 
-            if response_ptr.is_null() {
-                return Err("插件返回空响应".to_string());
+                        if let Err(e) = set_history_result {
+                            report_plugin_error(e);
+                        }
+                    }
+                    Err(e) => {
+                        log_error!("序列化历史记录失败: {}", e);
+                    }
+                }
+            } else {
+                // 清除历史记录
+                let clear_history_result = call.status(PluginPhase::SetHistory, || unsafe {
+                    call_unwind2(
+                        (*handler).set_history,
+                        (*handler).plugin_ptr,
+                        std::ptr::null(),
+                    )
+                });
+                if let Err(e) = clear_history_result {
+                    report_plugin_error(e);
+                }
             }
+        }
 
-            let response = unsafe {
-                let c_str = std::ffi::CStr::from_ptr(response_ptr);
-                let response_str = c_str
-                    .to_str()
-                    .map_err(|_| "响应转换失败".to_string())?
-                    .to_string();
+        // 调用插件的 handle_message 方法
+        let message_cstr =
+            std::ffi::CString::new(message).map_err(|_| "消息转换失败".to_string())?;
 
-                response_str
-            };
+        let mut response_ptr: *mut std::ffi::c_char = std::ptr::null_mut();
+        call.status(PluginPhase::HandleMessage, || unsafe {
+            call_unwind3(
+                (*handler).handle_message,
+                (*handler).plugin_ptr,
+                message_cstr.as_ptr(),
+                &mut response_ptr,
+            )
+        })
+        .map_err(report_plugin_error)?;
 
-            Ok(response)
-        } else {
-            Err(format!("插件实例 {} 未找到", instance_id))
+        if response_ptr.is_null() {
+            return Err(report_plugin_error(
+                call.invalid_response(PluginPhase::HandleMessage, "插件返回空响应"),
+            ));
         }
+
+        unsafe { extensions::take_plugin_string(response_ptr, free_string) }.map_err(|_| {
+            report_plugin_error(call.invalid_response(
+                PluginPhase::HandleMessage,
+                "插件响应不是有效的 UTF-8 字符串",
+            ))
+        })
     }
 
     /// 通知插件UI更新
@@ -771,56 +1506,161 @@ impl PluginManager {
         }
     }
 
+    /// 下载并安装插件：新版本下载、校验并暂存后，替换插件目录前销毁该插件的所有实例并释放动态库
+    pub async fn download_plugin(
+        &self,
+        plugin_id: &str,
+        task: &DownloadTask,
+    ) -> PluginDownloadResult {
+        let release = |plugin_id: &str| self.release_before_replace(plugin_id, "重新安装");
+        PluginRepository::with_release_hook(&release)
+            .download_plugin(plugin_id, task)
+            .await
+    }
+
+    /// 升级已安装的插件：新版本下载、校验并暂存后，替换插件目录前销毁该插件的所有实例并释放动态库
+    pub async fn upgrade_plugin(
+        &self,
+        plugin_id: &str,
+        task: &DownloadTask,
+    ) -> PluginDownloadResult {
+        if let Err(message) = self.check_installed_plugin(plugin_id) {
+            return Self::failure(plugin_id, message);
+        }
+
+        let release = |plugin_id: &str| self.release_before_replace(plugin_id, "升级");
+        PluginRepository::with_release_hook(&release)
+            .upgrade_plugin(plugin_id, task)
+            .await
+    }
+
+    /// 回滚插件到安装或升级前的版本：确认存在备份后销毁该插件的所有实例并释放动态库，再互换插件目录
+    pub fn rollback_plugin(&self, plugin_id: &str) -> PluginDownloadResult {
+        if let Err(message) = validate_plugin_id(plugin_id) {
+            return Self::failure(plugin_id, message);
+        }
+        if let Err(message) = self.check_installed_plugin(plugin_id) {
+            return Self::failure(plugin_id, message);
+        }
+
+        let release = |plugin_id: &str| self.release_before_replace(plugin_id, "回滚");
+        PluginRepository::with_release_hook(&release).rollback_plugin(plugin_id)
+    }
+
+    /// 卸载已安装的插件：先销毁该插件的所有实例并释放动态库，再删除插件文件
+    ///
+    /// 开发目录（`src/plugins`）中的插件不通过插件管理器卸载。
+    /// `keep_user_data` 为 true 时保留 `data/` 目录、插件设置和密钥；插件的备份版本总是被删除
+    pub fn uninstall_plugin(
+        &self,
+        plugin_id: &str,
+        force: bool,
+        keep_user_data: bool,
+    ) -> PluginDownloadResult {
+        if let Err(message) = self.check_installed_plugin(plugin_id) {
+            return Self::failure(plugin_id, message);
+        }
+
+        // 依赖检查失败时不销毁实例
+        let repository = PluginRepository::new();
+        let dependents = match repository.check_uninstall_dependents(plugin_id, force) {
+            Ok(dependents) => dependents,
+            Err(message) => return Self::failure(plugin_id, message),
+        };
+
+        self.release_before_replace(plugin_id, "卸载");
+
+        repository.uninstall_plugin(plugin_id, &dependents, keep_user_data)
+    }
+
+    fn failure(plugin_id: &str, message: String) -> PluginDownloadResult {
+        PluginDownloadResult {
+            success: false,
+            message,
+            plugin_id: Some(plugin_id.to_string()),
+            installed_path: None,
+        }
+    }
+
+    /// 替换或删除插件目录前销毁该插件的所有实例，避免已加载的动态库所在目录被移走
+    fn release_before_replace(&self, plugin_id: &str, action: &str) {
+        let released = self.release_plugin(plugin_id);
+        if released > 0 {
+            log_info!("{}插件 {} 前已销毁 {} 个实例", action, plugin_id, released);
+        }
+    }
+
+    /// 检查插件是否位于安装目录，开发目录中的同名插件拒绝卸载
+    fn check_installed_plugin(&self, plugin_id: &str) -> Result<(), String> {
+        let installed_root = get_root_plugin_installed_directory();
+        let config_paths = self
+            .scan_plugins()
+            .into_iter()
+            .filter(|plugin| plugin.id == plugin_id)
+            .map(|plugin| plugin.config_path)
+            .chain(
+                self.scan_incompatible_plugins()
+                    .into_iter()
+                    .filter(|plugin| plugin.id == plugin_id)
+                    .map(|plugin| plugin.config_path),
+            );
+
+        for config_path in config_paths {
+            if !Path::new(&config_path).starts_with(&installed_root) {
+                return Err(format!(
+                    "插件 {} 位于开发目录 {}，请手动管理",
+                    plugin_id, config_path
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 销毁插件的所有实例并移除实例记录以释放动态库，返回销毁的实例数量
+    fn release_plugin(&self, plugin_id: &str) -> usize {
+        let instance_ids: Vec<String> = self
+            .instances
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|instance| instance.plugin_id == plugin_id)
+            .map(|instance| instance.instance_id.clone())
+            .collect();
+
+        for instance_id in &instance_ids {
+            match self.dispose_plugin(instance_id) {
+                Ok(message) => log_info!("{}", message),
+                Err(e) => log_warn!("销毁插件实例 {} 失败: {}", instance_id, e),
+            }
+            // dispose_plugin 只标记实例已卸载，移除记录后动态库才会被释放
+            let removed = self.instances.lock().unwrap().remove(instance_id);
+            drop(removed);
+        }
+
+        self.plugin_instances.lock().unwrap().remove(plugin_id);
+        instance_ids.len()
+    }
+
     /// 清理所有已挂载的插件实例（应用关闭时调用）
     pub fn cleanup_all_plugins(&self) {
-        let mut instances = self.instances.lock().unwrap();
-
-        // 收集所有已挂载的实例ID
-        let mounted_instance_ids: Vec<String> = instances
+        // 收集所有已挂载的实例ID，逐个卸载时不持有实例表锁
+        let mounted_instance_ids: Vec<String> = self
+            .instances
+            .lock()
+            .unwrap()
             .iter()
             .filter(|(_, instance)| instance.is_mounted)
             .map(|(id, _)| id.clone())
             .collect();
 
-        // 逐个清理插件实例
         for instance_id in mounted_instance_ids {
-            if let Some(instance) = instances.get_mut(&instance_id) {
-                if instance.is_mounted {
-                    log_info!("正在清理插件: {}", instance.metadata.name);
-
-                    // 先断开连接
-                    if instance.is_connected {
-                        let _ = unsafe {
-                            ((*instance.handler).on_disconnect)((*instance.handler).plugin_ptr)
-                        };
-                        instance.is_connected = false;
-                    }
-
-                    // 调用 on_dispose
-                    let _ =
-                        unsafe { ((*instance.handler).on_dispose)((*instance.handler).plugin_ptr) };
-
-                    // 销毁插件实例
-                    unsafe {
-                        let destroy_plugin: Result<Symbol<DestroyPluginFn>, _> =
-                            instance.library.get(DESTROY_PLUGIN_SYMBOL);
-                        if let Ok(destroy_fn) = destroy_plugin {
-                            destroy_fn(instance.handler);
-                        }
-                    }
-
-                    instance.is_mounted = false;
-                    log_info!(
-                        "插件实例 {} ({}) 清理完成",
-                        instance.metadata.name,
-                        instance_id
-                    );
-                }
+            match self.dispose_plugin(&instance_id) {
+                Ok(message) => log_info!("{}", message),
+                Err(e) => log_warn!("清理插件实例 {} 失败: {}", instance_id, e),
             }
         }
 
-        // 清除所有映射和当前实例状态
-        drop(instances);
+        // 清除所有映射
         *self.plugin_instances.lock().unwrap() = HashMap::new();
 
         log_info!("所有插件实例清理完成");
@@ -872,9 +1712,11 @@ impl PluginManager {
                                     .as_millis() as u64,
                             };
 
-                            if let Ok(payload) = serde_json::to_string(&wrapper) {
+                            if let (Ok(payload), Some(app_handle)) =
+                                (serde_json::to_string(&wrapper), &self.app_handle)
+                            {
                                 // 直接使用 app_handle 发送到前端
-                                let _ = self.app_handle.emit("plugin-stream", payload);
+                                let _ = app_handle.emit("plugin-stream", payload);
                             }
 
                             Ok(format!("流式消息 {} 取消成功", stream_id))
@@ -1014,12 +1856,242 @@ impl PluginManager {
         }
     }
 
+    /// 获取插件声明的设置项定义
+    pub fn get_plugin_settings_schema(
+        &self,
+        plugin_id: &str,
+    ) -> Result<Vec<PluginSettingField>, String> {
+        let metadata = self.find_plugin_metadata(plugin_id)?;
+        Self::load_settings_schema(&metadata)
+    }
+
+    /// 获取插件（或指定实例）的设置
+    pub fn get_plugin_settings(
+        &self,
+        plugin_id: &str,
+        instance_id: Option<&str>,
+    ) -> Result<PluginSettingsView, String> {
+        let schema = self.get_plugin_settings_schema(plugin_id)?;
+        let view = PluginSettingsStore::new().view(plugin_id, schema, instance_id);
+        Ok(Self::merge_vault_secrets(plugin_id, view))
+    }
+
+    /// 更新插件（或指定实例）的设置，并重新下发给受影响的已挂载实例
+    pub fn set_plugin_settings(
+        &self,
+        plugin_id: &str,
+        instance_id: Option<&str>,
+        values: HashMap<String, serde_json::Value>,
+    ) -> Result<PluginSettingsView, String> {
+        let metadata = self.find_plugin_metadata(plugin_id)?;
+        let schema = Self::load_settings_schema(&metadata)?;
+
+        let secret_field = |key: &str| schema.iter().any(|field| field.key == key && field.secret);
+        // 密钥库按插件保存密钥，实例 ID 只在实例存续期间有效，敏感设置项只能在插件级别配置
+        if instance_id.is_some() {
+            if let Some(key) = values.keys().find(|key| secret_field(key)) {
+                return Err(format!("敏感设置项 {} 只能在插件级别配置", key));
+            }
+        }
+
+        // 敏感设置项保存到密钥库，插件通过扩展回调读取
+        let mut toml_values = HashMap::new();
+        for (key, value) in values {
+            let is_secret = secret_field(&key);
+            if is_secret {
+                let mut vault = get_secret_vault().lock().unwrap();
+                match value {
+                    serde_json::Value::Null => {
+                        vault.delete(plugin_id, &key)?;
+                    }
+                    serde_json::Value::String(secret) => vault.set(plugin_id, &key, &secret)?,
+                    _ => return Err(format!("敏感设置项 {} 的值必须是字符串", key)),
+                }
+                continue;
+            }
+
+            let value = if value.is_null() {
+                None
+            } else {
+                Some(
+                    serde_json::from_value::<toml::Value>(value)
+                        .map_err(|e| format!("设置项 {} 的值无效: {}", key, e))?,
+                )
+            };
+            toml_values.insert(key, value);
+        }
+
+        let store = PluginSettingsStore::new();
+        store.update(plugin_id, &schema, instance_id, toml_values)?;
+
+        // 重新下发给受影响的已挂载实例，调用插件期间不持有实例表锁
+        let affected: Vec<_> = self
+            .instances
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|instance| {
+                instance.plugin_id == plugin_id
+                    && instance.is_mounted
+                    && instance_id.is_none_or(|id| id == instance.instance_id)
+            })
+            .map(|instance| {
+                (
+                    instance.instance_id.clone(),
+                    instance.metadata.clone(),
+                    InstanceCallTarget::from(instance),
+                    instance
+                        .library
+                        .as_ref()
+                        .and_then(extensions::resolve_receive_settings),
+                )
+            })
+            .collect();
+        for (affected_id, metadata, target, receive_settings) in affected {
+            // 持有调用锁防止实例被销毁，函数指针在实例记录移除前一直有效
+            let _call_guard = target.call_lock.lock().unwrap();
+            if !matches!(self.get_plugin_status(&affected_id), Some((true, _))) {
+                continue;
+            }
+            match &target.isolated {
+                Some(isolated) => {
+                    Self::deliver_isolated_settings(isolated, &metadata, &affected_id)
+                }
+                None => Self::deliver_plugin_settings(receive_settings, &metadata, &affected_id),
+            }
+        }
+
+        let view = store.view(plugin_id, schema, instance_id);
+        Ok(Self::merge_vault_secrets(plugin_id, view))
+    }
+
+    /// 将密钥库中已配置的敏感设置项合并到设置视图
+    fn merge_vault_secrets(plugin_id: &str, mut view: PluginSettingsView) -> PluginSettingsView {
+        let Ok(names) = get_secret_vault().lock().unwrap().list(plugin_id) else {
+            return view;
+        };
+        for field in view.schema.iter().filter(|field| field.secret) {
+            if names.contains(&field.key) {
+                if !view.configured_secrets.contains(&field.key) {
+                    view.configured_secrets.push(field.key.clone());
+                }
+                view.missing_required.retain(|key| key != &field.key);
+            }
+        }
+        view
+    }
+
+    /// 检查插件的依赖是否都已安装且版本满足要求
+    fn check_plugin_dependencies(metadata: &PluginMetadata) -> Result<(), String> {
+        let dependencies = dependencies::load_plugin_dependencies(metadata)?;
+        if dependencies.is_empty() {
+            return Ok(());
+        }
+
+        let missing = dependencies::find_missing_dependencies(
+            &dependencies,
+            &dependencies::installed_plugin_versions(),
+        );
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "插件 {} 缺少依赖: {}",
+                metadata.id,
+                missing.join(", ")
+            ))
+        }
+    }
+
+    /// 从插件配置文件读取设置项定义
+    fn load_settings_schema(metadata: &PluginMetadata) -> Result<Vec<PluginSettingField>, String> {
+        PluginConfig::from_file(&metadata.config_path)
+            .map(|config| config.settings)
+            .map_err(|e| format!("读取插件配置失败: {}", e))
+    }
+
+    /// 解析插件实例的生效设置，插件未声明设置项时返回 `None`
+    fn resolve_plugin_settings(
+        metadata: &PluginMetadata,
+        instance_id: &str,
+    ) -> Option<HashMap<String, toml::Value>> {
+        let schema = match Self::load_settings_schema(metadata) {
+            Ok(schema) if !schema.is_empty() => schema,
+            Ok(_) => return None,
+            Err(e) => {
+                log_warn!("插件 {} 的设置项定义无效: {}", metadata.id, e);
+                return None;
+            }
+        };
+
+        let store = PluginSettingsStore::new();
+        let missing = store.missing_required(&metadata.id, &schema, Some(instance_id));
+        if !missing.is_empty() {
+            log_warn!("插件 {} 缺少必填设置项: {:?}", metadata.id, missing);
+        }
+
+        Some(store.resolve(&metadata.id, &schema, Some(instance_id)))
+    }
+
+    /// 通过宿主进程下发插件实例的设置，失败时只记录日志
+    fn deliver_isolated_settings(
+        isolated: &IsolatedPlugin,
+        metadata: &PluginMetadata,
+        instance_id: &str,
+    ) {
+        let Some(settings) = Self::resolve_plugin_settings(metadata, instance_id) else {
+            return;
+        };
+        match isolated.request("settings", json!({ "settings": settings })) {
+            Ok(_) => log_info!("已向插件实例 {} 下发设置", instance_id),
+            Err(e) => log_warn!("向插件实例 {} 下发设置失败: {}", instance_id, e),
+        }
+    }
+
+    /// 解析并下发插件实例的设置，失败时只记录日志
+    fn deliver_plugin_settings(
+        receive_settings: Option<ReceiveSettingsFn>,
+        metadata: &PluginMetadata,
+        instance_id: &str,
+    ) {
+        let Some(settings) = Self::resolve_plugin_settings(metadata, instance_id) else {
+            return;
+        };
+        let Some(receive_settings) = receive_settings else {
+            log_warn!("插件 {} 声明了设置项但未导出设置接收函数", metadata.id);
+            return;
+        };
+        match extensions::deliver_settings(receive_settings, instance_id, &settings) {
+            Ok(()) => log_info!("已向插件实例 {} 下发设置", instance_id),
+            Err(e) => log_warn!("向插件实例 {} 下发设置失败: {}", instance_id, e),
+        }
+    }
+
+    /// 删除用户关闭的实例的实例级别设置
+    fn remove_instance_settings(plugin_id: &str, instance_id: &str) {
+        if let Err(e) = PluginSettingsStore::new().remove_instance(plugin_id, instance_id) {
+            log_warn!("删除插件实例 {} 的设置失败: {}", instance_id, e);
+        }
+    }
+
     /// 查找插件元数据
     fn find_plugin_metadata(&self, plugin_id: &str) -> Result<PluginMetadata, String> {
         let plugins = self.scan_plugins();
-        plugins
+        if let Some(plugin) = plugins.into_iter().find(|p| p.id == plugin_id) {
+            return Ok(plugin);
+        }
+
+        // 区分插件不存在与插件不兼容两种情况
+        match self
+            .scan_incompatible_plugins()
             .into_iter()
             .find(|p| p.id == plugin_id)
-            .ok_or_else(|| format!("插件 {} 未找到", plugin_id))
+        {
+            Some(incompatible) => Err(format!(
+                "插件 {} 与当前客户端不兼容: {}",
+                plugin_id, incompatible.reason
+            )),
+            None => Err(format!("插件 {} 未找到", plugin_id)),
+        }
     }
 }
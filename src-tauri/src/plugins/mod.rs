@@ -1,16 +1,40 @@
+pub mod abi;
+pub mod archive;
+pub mod checksum;
+pub mod compatibility;
 pub mod config;
+pub mod dependencies;
 pub mod directories;
+pub mod download;
+pub mod error;
+pub mod extensions;
+pub mod ffi;
+pub mod http;
+pub mod isolation;
 pub mod loader;
+pub mod lockfile;
 pub mod manager;
+pub mod package;
+pub mod registry;
 pub mod repository;
+pub mod settings;
+pub mod signature;
+pub mod sources;
+pub mod staging;
 
-pub use config::{DownloadConfig, PlatformDownload, PluginConfig, PluginInfo};
-pub use loader::PluginLoader;
-pub use manager::PluginManager;
+pub use config::{
+    DownloadConfig, PlatformDownload, PluginConfig, PluginInfo, PluginSettingField,
+    PluginSettingType,
+};
+pub use error::{PluginError, PluginPhase};
+pub use loader::{IncompatiblePluginInfo, PluginLoader, ScannedPlugin};
+pub use manager::{PluginInstanceState, PluginManager};
 pub use plugin_interfaces::{
     CreatePluginFn, DestroyPluginFn, PluginHandler, PluginMetadata, CREATE_PLUGIN_SYMBOL,
     DESTROY_PLUGIN_SYMBOL,
 };
 pub use repository::{
-    AvailablePluginInfo, DownloadResponse, PluginDownloadResult, PluginRepository,
+    AvailablePluginInfo, DownloadResponse, PluginDownloadResult, PluginRepository, PluginUpdateInfo,
 };
+pub use settings::{PluginSettingsStore, PluginSettingsView};
+pub use sources::{RepositoryLock, RepositorySource, RepositorySources, ResolvedRepository};
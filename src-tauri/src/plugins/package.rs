@@ -0,0 +1,232 @@
+//! 本地插件包
+//!
+//! 支持从 `.zip`、`.tar.gz`（`.tgz`）归档或已解压的目录安装插件，无需访问插件仓库。
+//! 包内需包含 `config.toml` 和当前平台的动态库，可以直接位于根目录，也可以位于唯一的顶层目录中：
+//!
+//! ```text
+//! example.zip
+//! └── example/
+//!     ├── config.toml
+//!     └── libexample-1.0.0.so
+//! ```
+//!
+//! `.ccplugin` 是自包含的多平台插件包（zip 格式），清单 `manifest.toml` 与 config.toml 格式相同，
+//! 动态库按目标三元组存放，资源文件放在 `assets/` 中，安装时只保留与当前客户端匹配的动态库：
+//!
+//! ```text
+//! example-1.0.0.ccplugin
+//! ├── manifest.toml
+//! ├── lib/
+//! │   ├── x86_64-unknown-linux-gnu/libexample-1.0.0.so
+//! │   ├── aarch64-apple-darwin/libexample-1.0.0.dylib
+//! │   └── x86_64-pc-windows-msvc/example-1.0.0.dll
+//! └── assets/
+//!     └── prompts/default.md
+//! ```
+
+use libloading::Library;
+use plugin_interfaces::{log_info, CREATE_PLUGIN_SYMBOL, DESTROY_PLUGIN_SYMBOL};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::plugins::{
+    abi,
+    archive::{self, ExtractLimits},
+    compatibility::{check_library_arch, target_matches_current, CLIENT_TARGET},
+    config::PluginConfig,
+    extensions,
+    loader::library_filename,
+    staging::copy_dir_all,
+};
+
+/// `.ccplugin` 插件包扩展名
+pub const CCPLUGIN_EXTENSION: &str = "ccplugin";
+
+/// `.ccplugin` 插件包清单文件名
+pub const CCPLUGIN_MANIFEST_FILE: &str = "manifest.toml";
+
+/// 按目标三元组存放动态库的目录
+pub const TARGET_LIBRARY_DIRECTORY: &str = "lib";
+
+/// 插件包格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageFormat {
+    Directory,
+    Zip,
+    TarGz,
+    CcPlugin,
+}
+
+impl PackageFormat {
+    /// 根据路径判断插件包格式
+    pub fn detect(path: &Path) -> Result<Self, String> {
+        if path.is_dir() {
+            return Ok(PackageFormat::Directory);
+        }
+        if !path.is_file() {
+            return Err(format!("插件包不存在: {:?}", path));
+        }
+
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if file_name.ends_with(&format!(".{}", CCPLUGIN_EXTENSION)) {
+            Ok(PackageFormat::CcPlugin)
+        } else if file_name.ends_with(".zip") {
+            Ok(PackageFormat::Zip)
+        } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            Ok(PackageFormat::TarGz)
+        } else {
+            Err(format!(
+                "不支持的插件包格式: {}，请使用 .ccplugin、.zip、.tar.gz 或插件目录",
+                file_name
+            ))
+        }
+    }
+}
+
+/// 解包到 `target_dir`，返回包含 `config.toml` 的插件根目录
+pub fn unpack_plugin_package(path: &Path, target_dir: &Path) -> Result<PathBuf, String> {
+    let format = PackageFormat::detect(path)?;
+    log_info!("解包插件包 {:?}，格式: {:?}", path, format);
+    let limits = ExtractLimits::from_settings();
+
+    match format {
+        PackageFormat::Directory => {
+            copy_dir_all(path, target_dir).map_err(|e| format!("复制插件目录失败: {}", e))?
+        }
+        PackageFormat::Zip | PackageFormat::CcPlugin => {
+            archive::extract_zip(open_package(path)?, target_dir, false, &limits)?
+        }
+        PackageFormat::TarGz => {
+            archive::extract_tar_gz(open_package(path)?, target_dir, false, &limits)?
+        }
+    }
+
+    let plugin_root = find_plugin_root(target_dir)?;
+
+    // 清单与 config.toml 格式相同，安装后统一按 config.toml 加载
+    let config_path = plugin_root.join("config.toml");
+    if !config_path.is_file() {
+        fs::copy(plugin_root.join(CCPLUGIN_MANIFEST_FILE), &config_path)
+            .map_err(|e| format!("读取插件清单失败: {}", e))?;
+    }
+    Ok(plugin_root)
+}
+
+fn open_package(path: &Path) -> Result<fs::File, String> {
+    fs::File::open(path).map_err(|e| format!("打开插件包失败: {}", e))
+}
+
+/// 目录中是否包含 config.toml 或 `.ccplugin` 清单
+fn has_manifest(dir: &Path) -> bool {
+    dir.join("config.toml").is_file() || dir.join(CCPLUGIN_MANIFEST_FILE).is_file()
+}
+
+/// 查找插件根目录：解包目录本身或其唯一的顶层目录
+fn find_plugin_root(unpack_dir: &Path) -> Result<PathBuf, String> {
+    if has_manifest(unpack_dir) {
+        return Ok(unpack_dir.to_path_buf());
+    }
+
+    let entries: Vec<PathBuf> = fs::read_dir(unpack_dir)
+        .map_err(|e| format!("读取解包目录失败: {}", e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    if let [single] = entries.as_slice() {
+        if has_manifest(single) {
+            return Ok(single.clone());
+        }
+    }
+
+    Err(format!(
+        "插件包中找不到 config.toml 或 {}",
+        CCPLUGIN_MANIFEST_FILE
+    ))
+}
+
+/// 插件根目录中当前平台的动态库文件名
+pub fn package_library_filename(config: &PluginConfig) -> String {
+    let library_name = config
+        .plugin
+        .library
+        .clone()
+        .unwrap_or_else(|| format!("{}-{}", config.plugin.id, config.plugin.version));
+    library_filename(&library_name)
+}
+
+/// 在 `lib/<目标三元组>/` 中查找与当前客户端匹配的动态库，优先选择完全一致的目标三元组
+pub fn select_target_library(plugin_dir: &Path, library_filename: &str) -> Option<PathBuf> {
+    let library_dir = plugin_dir.join(TARGET_LIBRARY_DIRECTORY);
+    let exact = library_dir.join(CLIENT_TARGET).join(library_filename);
+    if exact.is_file() {
+        return Some(exact);
+    }
+
+    let mut targets: Vec<PathBuf> = fs::read_dir(&library_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| target_matches_current(&entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path().join(library_filename))
+        .filter(|path| path.is_file())
+        .collect();
+    targets.sort();
+    targets.into_iter().next()
+}
+
+/// 插件包中当前平台的动态库，返回相对插件根目录的路径
+pub fn locate_package_library(plugin_root: &Path, library_filename: &str) -> Option<PathBuf> {
+    if plugin_root.join(library_filename).is_file() {
+        return Some(PathBuf::from(library_filename));
+    }
+    select_target_library(plugin_root, library_filename)?
+        .strip_prefix(plugin_root)
+        .ok()
+        .map(Path::to_path_buf)
+}
+
+/// 删除 `lib/` 中与当前客户端不匹配的目标三元组目录，只保留选中的动态库
+pub fn prune_other_targets(plugin_dir: &Path, library: &Path) -> Result<(), String> {
+    let library_dir = plugin_dir.join(TARGET_LIBRARY_DIRECTORY);
+    let Ok(entries) = fs::read_dir(&library_dir) else {
+        return Ok(());
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if plugin_dir.join(library).starts_with(&path) {
+            continue;
+        }
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        result.map_err(|e| format!("删除其他平台的动态库失败 {:?}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+/// 加载动态库并检查插件必须导出的符号和 ABI 版本
+pub fn verify_library_symbols(library_path: &Path, plugin_id: &str) -> Result<(), String> {
+    // 先检查架构，避免加载失败时只得到难以理解的 dlopen 错误
+    check_library_arch(library_path)
+        .map_err(|reason| format!("插件 {} 无法在当前系统上运行: {}", plugin_id, reason))?;
+
+    let library =
+        unsafe { Library::new(library_path).map_err(|e| format!("加载动态库失败: {}", e))? };
+
+    let abi_version = abi::negotiate_abi_version(&library, plugin_id)?;
+    let free_string = extensions::resolve_free_plugin_string(&library);
+    abi::check_free_plugin_string(plugin_id, abi_version, free_string.is_some())?;
+    for symbol in [CREATE_PLUGIN_SYMBOL, DESTROY_PLUGIN_SYMBOL] {
+        unsafe { library.get::<*const ()>(symbol) }.map_err(|_| {
+            format!(
+                "插件 {} 的动态库缺少导出函数 {}",
+                plugin_id,
+                String::from_utf8_lossy(symbol).trim_end_matches('\0')
+            )
+        })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,279 @@
+//! JSON 插件注册表索引
+//!
+//! 仓库源地址以 `.json` 结尾时视为注册表索引，无需下载整个仓库归档即可列出插件：
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "plugins": [
+//!     {
+//!       "id": "example",
+//!       "name": "示例插件",
+//!       "version": "1.0.0",
+//!       "description": "...",
+//!       "author": "...",
+//!       "config_url": "example/config.toml",
+//!       "config_checksum": "sha256:...",
+//!       "artifacts": {
+//!         "linux": { "checksum": "sha256:...", "download_url": "example/libexample.so" }
+//!       }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! 远程索引缓存在仓库源的缓存目录中，刷新时携带 `If-None-Match` / `If-Modified-Since`，
+//! 服务器返回 304 时直接使用缓存。相对地址相对于索引文件所在位置解析。
+//! `config_url` 指向的配置文件和二进制文件一样需要通过校验值验证。
+
+use plugin_interfaces::log_info;
+use reqwest::{header, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::plugins::{
+    config::DownloadConfig, http::send_with_retry, repository::AvailablePluginInfo,
+};
+
+/// 缓存的索引文件名
+pub const REGISTRY_INDEX_FILE: &str = "index.json";
+
+/// 缓存元数据文件名
+const REGISTRY_CACHE_META_FILE: &str = "index.meta.json";
+
+/// 支持的索引格式版本
+pub const REGISTRY_INDEX_VERSION: u32 = 1;
+
+/// 注册表索引
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryIndex {
+    #[serde(default = "default_index_version")]
+    pub version: u32,
+    #[serde(default)]
+    pub plugins: Vec<RegistryEntry>,
+}
+
+fn default_index_version() -> u32 {
+    REGISTRY_INDEX_VERSION
+}
+
+/// 索引中的单个插件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub avatar: Option<String>,
+    #[serde(default)]
+    pub homepage: Option<String>,
+    #[serde(default)]
+    pub repository: Option<String>,
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub min_client_version: Option<String>,
+    #[serde(default)]
+    pub max_client_version: Option<String>,
+    #[serde(default)]
+    pub platform: Vec<String>,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// 插件 config.toml 的地址，安装时复制到插件目录
+    #[serde(default)]
+    pub config_url: Option<String>,
+    /// config.toml 的校验值，格式同 `PlatformDownload.checksum`，提供 `config_url` 时必填
+    #[serde(default)]
+    pub config_checksum: Option<String>,
+    /// 各平台的二进制文件
+    #[serde(default)]
+    pub artifacts: Option<DownloadConfig>,
+}
+
+impl RegistryEntry {
+    /// 转换为可用插件信息
+    pub fn into_available_plugin(
+        self,
+        source: &str,
+        incompatible_reason: Option<String>,
+    ) -> AvailablePluginInfo {
+        AvailablePluginInfo {
+            id: self.id,
+            name: self.name,
+            version: self.version,
+            description: self.description,
+            author: self.author,
+            avatar: self.avatar,
+            homepage: self.homepage,
+            repository: self.repository,
+            license: self.license,
+            keywords: self.keywords,
+            min_client_version: self.min_client_version,
+            max_client_version: self.max_client_version,
+            platform: self.platform,
+            dependencies: self.dependencies,
+            download: self.artifacts,
+            incompatible_reason,
+            source: source.to_string(),
+            config_url: self.config_url,
+            config_checksum: self.config_checksum,
+        }
+    }
+}
+
+/// 缓存元数据，用于条件请求
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheMeta {
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    last_modified: Option<String>,
+}
+
+/// 一次索引获取的结果
+#[derive(Debug, Clone)]
+pub struct FetchedIndex {
+    pub index: RegistryIndex,
+    /// 服务器返回 304，使用了本地缓存
+    pub not_modified: bool,
+}
+
+/// 解析索引内容并检查格式版本
+pub fn parse_index(data: &[u8]) -> Result<RegistryIndex, String> {
+    let index: RegistryIndex =
+        serde_json::from_slice(data).map_err(|e| format!("解析插件注册表索引失败: {}", e))?;
+    if index.version != REGISTRY_INDEX_VERSION {
+        return Err(format!(
+            "不支持的插件注册表索引版本 {}，当前支持版本 {}",
+            index.version, REGISTRY_INDEX_VERSION
+        ));
+    }
+    Ok(index)
+}
+
+/// 读取索引文件
+pub fn load_index(path: &Path) -> Result<RegistryIndex, String> {
+    let data = fs::read(path).map_err(|e| format!("读取插件注册表索引 {:?} 失败: {}", path, e))?;
+    parse_index(&data)
+}
+
+/// 索引的分离签名文件路径（`<索引文件>.sig`）
+pub fn signature_path(index_path: &Path) -> PathBuf {
+    let mut path = index_path.as_os_str().to_owned();
+    path.push(".sig");
+    PathBuf::from(path)
+}
+
+/// 读取索引的分离签名，不存在时返回 `None`
+pub fn read_index_signature(index_path: &Path) -> Result<Option<String>, String> {
+    let path = signature_path(index_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    fs::read_to_string(&path)
+        .map(|content| Some(content.trim().to_string()))
+        .map_err(|e| format!("读取注册表签名失败: {}", e))
+}
+
+/// 获取远程索引并缓存到 `cache_dir`，缓存有效时发送条件请求
+pub async fn fetch_index(
+    client: &reqwest::Client,
+    url: &str,
+    cache_dir: &Path,
+) -> Result<FetchedIndex, String> {
+    let index_path = cache_dir.join(REGISTRY_INDEX_FILE);
+    let meta_path = cache_dir.join(REGISTRY_CACHE_META_FILE);
+
+    // 只有缓存的索引存在时才发送条件请求
+    let meta = if index_path.exists() {
+        fs::read(&meta_path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<CacheMeta>(&data).ok())
+            .unwrap_or_default()
+    } else {
+        CacheMeta::default()
+    };
+
+    let mut request = client.get(url);
+    if let Some(etag) = &meta.etag {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &meta.last_modified {
+        request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+    }
+
+    let response = send_with_retry(request)
+        .await
+        .map_err(|e| format!("获取插件注册表索引失败: {}", e))?;
+
+    if response.status() == StatusCode::NOT_MODIFIED {
+        log_info!("插件注册表索引未变化，使用缓存: {}", url);
+        return Ok(FetchedIndex {
+            index: load_index(&index_path)?,
+            not_modified: true,
+        });
+    }
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "获取插件注册表索引失败，HTTP状态码: {}",
+            response.status()
+        ));
+    }
+
+    let header_value = |name: header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    let new_meta = CacheMeta {
+        etag: header_value(header::ETAG),
+        last_modified: header_value(header::LAST_MODIFIED),
+    };
+
+    let data = response
+        .bytes()
+        .await
+        .map_err(|e| format!("读取插件注册表索引失败: {}", e))?;
+    let index = parse_index(&data)?;
+
+    fs::create_dir_all(cache_dir).map_err(|e| format!("创建注册表缓存目录失败: {}", e))?;
+    fs::write(&index_path, &data).map_err(|e| format!("缓存插件注册表索引失败: {}", e))?;
+    let meta_data = serde_json::to_vec(&new_meta).map_err(|e| e.to_string())?;
+    fs::write(&meta_path, meta_data).map_err(|e| format!("缓存注册表元数据失败: {}", e))?;
+
+    // 索引更新后同步分离签名，签名不存在时删除旧签名
+    let cached_signature = signature_path(&index_path);
+    match fetch_signature(client, url).await {
+        Some(signature) => fs::write(&cached_signature, signature)
+            .map_err(|e| format!("缓存注册表签名失败: {}", e))?,
+        None if cached_signature.exists() => fs::remove_file(&cached_signature)
+            .map_err(|e| format!("删除旧注册表签名失败: {}", e))?,
+        None => {}
+    }
+
+    Ok(FetchedIndex {
+        index,
+        not_modified: false,
+    })
+}
+
+/// 获取索引的分离签名（`<索引地址>.sig`），不存在时返回 `None`
+async fn fetch_signature(client: &reqwest::Client, url: &str) -> Option<String> {
+    let response = send_with_retry(client.get(format!("{}.sig", url)))
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    response.text().await.ok()
+}
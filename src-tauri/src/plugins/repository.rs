@@ -1,16 +1,47 @@
 use plugin_interfaces::{log_info, log_warn};
 use serde::{Deserialize, Serialize};
-use std::{fs, io::Cursor};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+use uuid::Uuid;
 use walkdir::WalkDir;
-use zip::ZipArchive;
 
 use crate::plugins::{
+    archive::{self, ExtractLimits},
+    checksum::{verify_checksum, ChecksumAlgorithm},
+    compatibility::{
+        check_binary_arch, check_compatibility, current_arch, current_platform, parse_version,
+        target_matches_current, CLIENT_TARGET,
+    },
     config::{DownloadConfig, PlatformDownload, PluginConfig},
+    dependencies::{find_dependents, installed_plugin_versions, parse_dependencies},
     directories::{
-        get_plugin_repository_directory, get_plugin_repository_root,
+        get_plugin_staging_directory, get_repository_cache_directory,
         get_root_plugin_installed_directory,
     },
+    download::DownloadTask,
+    http::http_client,
+    lockfile::{InstalledLock, InstalledPluginEntry},
+    package::{
+        locate_package_library, package_library_filename, prune_other_targets,
+        unpack_plugin_package, verify_library_symbols, CCPLUGIN_EXTENSION,
+    },
+    registry::{fetch_index, load_index, read_index_signature},
+    settings::PluginSettingsStore,
+    signature::{read_repository_signature, repository_snapshot_message, SignatureVerifier},
+    sources::{
+        file_url_to_path, is_commit_sha, RepositoryLock, RepositorySource, RepositorySources,
+        ResolvedRepository,
+    },
+    staging::{
+        backup_version, copy_dir_all, remove_backup, rollback_installed_plugin, validate_plugin_id,
+        StagedInstall, USER_DATA_DIRECTORY,
+    },
 };
+use crate::secrets::get_secret_vault;
 
 /// 可用插件信息（来自插件仓库）
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +71,30 @@ pub struct AvailablePluginInfo {
     pub dependencies: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub download: Option<DownloadConfig>,
+    /// 与当前客户端不兼容的原因，兼容时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub incompatible_reason: Option<String>,
+    /// 插件来自的仓库源名称
+    #[serde(default)]
+    pub source: String,
+    /// 注册表索引中插件 config.toml 的地址，目录仓库为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_url: Option<String>,
+    /// `config_url` 指向的 config.toml 的校验值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_checksum: Option<String>,
+}
+
+/// 插件更新信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginUpdateInfo {
+    pub id: String,
+    pub name: String,
+    pub installed_version: String,
+    pub available_version: String,
+    /// 新版本与当前客户端不兼容的原因，兼容时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub incompatible_reason: Option<String>,
 }
 
 /// 插件下载结果
@@ -53,7 +108,7 @@ pub struct PluginDownloadResult {
     pub installed_path: Option<String>,
 }
 
-/// 下载GitHub仓库的响应结构
+/// 刷新插件仓库的响应结构
 #[derive(serde::Serialize)]
 pub struct DownloadResponse {
     pub success: bool,
@@ -61,73 +116,106 @@ pub struct DownloadResponse {
     pub download_path: Option<String>,
 }
 
-struct RepoInfo {
-    owner: String,
-    name: String,
+/// 插件包的来源，写入安装记录
+struct PackageOrigin<'a> {
+    /// 从插件仓库安装时期望的插件 ID
+    expected_id: Option<&'a str>,
+    source: &'a str,
+    download_url: &'a str,
 }
 
-const GITHUB_PLUGIN_REPO: &str = "https://github.com/luodeb/chat-client-plugin";
-
-/// 从GitHub URL提取仓库信息
-fn extract_repo_info(url: &str) -> Result<RepoInfo, String> {
-    // 移除可能的.git后缀和尾部斜杠
-    let clean_url = url.trim_end_matches('/').trim_end_matches(".git");
+/// 替换或回滚插件目录前调用的回调，参数为插件 ID，用于销毁插件实例并释放动态库
+pub type ReleaseHook<'h> = &'h (dyn Fn(&str) + Sync);
 
-    // 分割URL获取路径部分
-    let parts: Vec<&str> = clean_url.split('/').collect();
-
-    if parts.len() < 5 || parts[2] != "github.com" {
-        return Err("无效的GitHub仓库URL格式".to_string());
-    }
-
-    let owner = parts[3].to_string();
-    let name = parts[4].to_string();
-
-    if owner.is_empty() || name.is_empty() {
-        return Err("无法从URL中提取仓库所有者或名称".to_string());
-    }
-
-    Ok(RepoInfo { owner, name })
+pub struct PluginRepository<'h> {
+    release_hook: Option<ReleaseHook<'h>>,
 }
 
-#[derive(Debug)]
-pub struct PluginRepository;
-
-impl Default for PluginRepository {
+impl Default for PluginRepository<'_> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl PluginRepository {
+impl<'h> PluginRepository<'h> {
     pub fn new() -> Self {
-        Self
+        Self { release_hook: None }
+    }
+
+    /// 替换已安装插件的目录前调用 `release_hook`，下载或校验失败时不影响正在运行的插件实例
+    pub fn with_release_hook(release_hook: ReleaseHook<'h>) -> Self {
+        Self {
+            release_hook: Some(release_hook),
+        }
+    }
+
+    /// 即将替换插件目录时释放该插件的实例
+    fn release_before_replace(&self, plugin_id: &str) {
+        if let Some(release_hook) = self.release_hook {
+            release_hook(plugin_id);
+        }
     }
 
-    /// 扫描可用插件列表（从插件仓库目录）
+    /// 扫描可用插件列表，合并所有已启用的仓库源
+    /// 多个仓库源中存在同一插件时，保留优先级最高的仓库源中的版本
     pub fn scan_available_plugins(&self) -> Vec<AvailablePluginInfo> {
-        let mut plugins = Vec::new();
+        let mut plugins: Vec<AvailablePluginInfo> = Vec::new();
+        let sources = RepositorySources::load();
+
+        for source in sources.enabled_by_priority() {
+            if source.is_registry() {
+                for plugin_info in self.load_available_plugins_from_registry(source) {
+                    if plugins.iter().any(|p| p.id == plugin_info.id) {
+                        log_info!(
+                            "Plugin '{}' from source '{}' is shadowed by a higher priority source",
+                            plugin_info.id,
+                            source.name
+                        );
+                        continue;
+                    }
+                    plugins.push(plugin_info);
+                }
+                continue;
+            }
 
-        // 获取插件仓库目录
-        let repo_dir = get_plugin_repository_directory();
+            let repo_dir = source.plugins_directory();
 
-        if !repo_dir.exists() {
-            log_warn!("Plugin repository directory does not exist: {:?}", repo_dir);
-            return plugins;
-        }
+            if !repo_dir.exists() {
+                log_warn!(
+                    "Plugin repository directory does not exist: {:?} (source: {})",
+                    repo_dir,
+                    source.name
+                );
+                continue;
+            }
 
-        log_info!("Scanning plugin repository: {:?}", repo_dir);
+            log_info!(
+                "Scanning plugin repository: {:?} (source: {})",
+                repo_dir,
+                source.name
+            );
 
-        // 扫描插件仓库目录
-        for entry in WalkDir::new(&repo_dir)
-            .min_depth(1)
-            .max_depth(1)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if entry.file_type().is_dir() {
-                if let Some(plugin_info) = self.load_available_plugin_from_directory(entry.path()) {
-                    plugins.push(plugin_info);
+            // 扫描插件仓库目录
+            for entry in WalkDir::new(&repo_dir)
+                .min_depth(1)
+                .max_depth(1)
+                .into_iter()
+                .filter_map(|e| e.ok())
+            {
+                if entry.file_type().is_dir() {
+                    if let Some(plugin_info) =
+                        self.load_available_plugin_from_directory(entry.path(), source)
+                    {
+                        if plugins.iter().any(|p| p.id == plugin_info.id) {
+                            log_info!(
+                                "Plugin '{}' from source '{}' is shadowed by a higher priority source",
+                                plugin_info.id,
+                                source.name
+                            );
+                            continue;
+                        }
+                        plugins.push(plugin_info);
+                    }
                 }
             }
         }
@@ -136,7 +224,13 @@ impl PluginRepository {
     }
 
     /// 下载并安装插件
-    pub async fn download_plugin(&self, plugin_id: &str) -> PluginDownloadResult {
+    /// 下载进度通过 `task` 报告，取消后停止安装。插件已安装时，新版本下载、校验并暂存完成后
+    /// 才通过 [`Self::with_release_hook`] 传入的回调释放插件实例和动态库
+    pub async fn download_plugin(
+        &self,
+        plugin_id: &str,
+        task: &DownloadTask,
+    ) -> PluginDownloadResult {
         log_info!("开始下载插件: {}", plugin_id);
 
         // 首先查找插件信息
@@ -153,54 +247,489 @@ impl PluginRepository {
             }
         };
 
-        // 获取当前平台的下载信息
-        let platform_download = match self.get_platform_download_info(&plugin_info.download) {
-            Some(download) => download,
-            None => {
+        // 拒绝安装与当前客户端不兼容的插件，避免先装好依赖再失败
+        if let Some(reason) = &plugin_info.incompatible_reason {
+            return PluginDownloadResult {
+                success: false,
+                message: format!("插件 {} 与当前客户端不兼容: {}", plugin_id, reason),
+                plugin_id: Some(plugin_id.to_string()),
+                installed_path: None,
+            };
+        }
+
+        // 解析并安装尚未满足的依赖（按依赖顺序，被依赖者在前）
+        let dependencies = match self.resolve_dependencies(plugin_info, &available_plugins) {
+            Ok(dependencies) => dependencies,
+            Err(error) => {
                 return PluginDownloadResult {
                     success: false,
-                    message: format!("插件 {} 不支持当前平台", plugin_id),
+                    message: format!("解析插件 {} 的依赖失败: {}", plugin_id, error),
                     plugin_id: Some(plugin_id.to_string()),
                     installed_path: None,
                 };
             }
         };
 
-        // 执行下载
-        match self
-            .download_and_install_plugin(plugin_info, platform_download)
-            .await
+        // 插件信息来自仓库快照，安装前确认涉及的每个仓库源快照签名可信
+        let sources = RepositorySources::load();
+        let mut verified_sources = HashSet::new();
+        for info in dependencies
+            .iter()
+            .copied()
+            .chain(std::iter::once(plugin_info))
         {
-            Ok(installed_path) => PluginDownloadResult {
-                success: true,
-                message: format!("插件 {} 下载安装成功", plugin_info.name),
+            if !verified_sources.insert(info.source.as_str()) {
+                continue;
+            }
+            let result = match sources.find(&info.source) {
+                Some(source) => self.verify_repository_snapshot(source),
+                None => Err(format!("仓库源 {} 不存在", info.source)),
+            };
+            if let Err(error) = result {
+                return PluginDownloadResult {
+                    success: false,
+                    message: error,
+                    plugin_id: Some(plugin_id.to_string()),
+                    installed_path: None,
+                };
+            }
+        }
+
+        // 依赖均为本次新安装的插件，安装失败时删除本次已安装的依赖
+        let mut installed_dependencies = Vec::new();
+        for dependency in &dependencies {
+            log_info!("安装插件 {} 的依赖: {}", plugin_id, dependency.id);
+            if let Err(error) = self
+                .install_available_plugin(dependency, &sources, task)
+                .await
+            {
+                self.remove_installed_dependencies(plugin_id, &installed_dependencies);
+                return PluginDownloadResult {
+                    success: false,
+                    message: format!("安装依赖插件 {} 失败: {}", dependency.id, error),
+                    plugin_id: Some(plugin_id.to_string()),
+                    installed_path: None,
+                };
+            }
+            installed_dependencies.push(dependency.id.as_str());
+        }
+
+        // 执行下载
+        let result = self
+            .install_available_plugin(plugin_info, &sources, task)
+            .await;
+        if result.is_err() {
+            self.remove_installed_dependencies(plugin_id, &installed_dependencies);
+        }
+        match result {
+            Ok(installed_path) => {
+                let message = if dependencies.is_empty() {
+                    format!("插件 {} 下载安装成功", plugin_info.name)
+                } else {
+                    let names: Vec<&str> = dependencies.iter().map(|p| p.name.as_str()).collect();
+                    format!(
+                        "插件 {} 下载安装成功，同时安装了依赖: {}",
+                        plugin_info.name,
+                        names.join(", ")
+                    )
+                };
+                PluginDownloadResult {
+                    success: true,
+                    message,
+                    plugin_id: Some(plugin_id.to_string()),
+                    installed_path: Some(installed_path),
+                }
+            }
+            Err(error) => PluginDownloadResult {
+                success: false,
+                message: error,
                 plugin_id: Some(plugin_id.to_string()),
-                installed_path: Some(installed_path),
+                installed_path: None,
             },
+        }
+    }
+
+    /// 插件安装失败时按安装的逆序删除本次一并安装的依赖
+    fn remove_installed_dependencies(&self, plugin_id: &str, dependency_ids: &[&str]) {
+        for dependency_id in dependency_ids.iter().rev() {
+            log_info!(
+                "插件 {} 安装失败，删除本次安装的依赖: {}",
+                plugin_id,
+                dependency_id
+            );
+            let result = self.uninstall_plugin(dependency_id, &[], true);
+            if !result.success {
+                log_warn!("删除依赖插件 {} 失败: {}", dependency_id, result.message);
+            }
+        }
+    }
+
+    /// 按当前信任策略校验仓库快照签名，注册表仓库源校验索引文件的分离签名
+    fn verify_repository_snapshot(&self, source: &RepositorySource) -> Result<(), String> {
+        let verifier = SignatureVerifier::from_settings()?;
+        if source.is_registry() {
+            let index_path = source.registry_index_path();
+            let signature = read_index_signature(&index_path)?;
+            let message =
+                fs::read(&index_path).map_err(|e| format!("读取插件注册表索引失败: {}", e))?;
+            return verifier.check("插件注册表", &message, signature.as_deref());
+        }
+        Self::verify_repository_directory(&verifier, &source.snapshot_directory())
+    }
+
+    /// 校验仓库目录的签名
+    fn verify_repository_directory(
+        verifier: &SignatureVerifier,
+        repo_dir: &Path,
+    ) -> Result<(), String> {
+        let signature = read_repository_signature(repo_dir)?;
+        let message = repository_snapshot_message(repo_dir)?;
+        verifier.check("插件仓库", &message, signature.as_deref())
+    }
+
+    /// 检查兼容性后下载并安装单个仓库插件
+    async fn install_available_plugin(
+        &self,
+        plugin_info: &AvailablePluginInfo,
+        sources: &RepositorySources,
+        task: &DownloadTask,
+    ) -> Result<String, String> {
+        let source = sources
+            .find(&plugin_info.source)
+            .ok_or_else(|| format!("仓库源 {} 不存在", plugin_info.source))?;
+
+        // 拒绝安装与当前客户端不兼容的插件
+        if let Some(reason) = &plugin_info.incompatible_reason {
+            return Err(format!(
+                "插件 {} 与当前客户端不兼容: {}",
+                plugin_info.id, reason
+            ));
+        }
+
+        // 获取当前平台的下载信息
+        let platform_download = self
+            .get_platform_download_info(&plugin_info.download)
+            .ok_or_else(|| {
+                format!(
+                    "插件 {} 不支持当前平台（{}，架构 {}）",
+                    plugin_info.id,
+                    CLIENT_TARGET,
+                    current_arch()
+                )
+            })?;
+
+        self.download_and_install_plugin(plugin_info, platform_download, source, task)
+            .await
+            .map_err(|error| format!("下载插件失败: {}", error))
+    }
+
+    /// 解析插件需要一并安装的依赖，返回按安装顺序排列的仓库插件
+    /// 本地已安装且版本满足要求的依赖会被跳过，已安装但版本不满足要求时返回错误
+    fn resolve_dependencies<'a>(
+        &self,
+        plugin_info: &'a AvailablePluginInfo,
+        available_plugins: &'a [AvailablePluginInfo],
+    ) -> Result<Vec<&'a AvailablePluginInfo>, String> {
+        Self::resolve_install_order(plugin_info, available_plugins, &installed_plugin_versions())
+    }
+
+    /// 按已安装插件的版本（插件 ID 到版本号）解析需要安装的依赖，返回按安装顺序排列的仓库插件
+    fn resolve_install_order<'a>(
+        plugin_info: &'a AvailablePluginInfo,
+        available_plugins: &'a [AvailablePluginInfo],
+        installed: &HashMap<String, String>,
+    ) -> Result<Vec<&'a AvailablePluginInfo>, String> {
+        let mut visiting = Vec::new();
+        let mut install_order = Vec::new();
+        Self::visit_dependencies(
+            plugin_info,
+            available_plugins,
+            installed,
+            &mut visiting,
+            &mut install_order,
+        )?;
+        Ok(install_order)
+    }
+
+    /// 深度优先遍历依赖，检测循环依赖并记录安装顺序
+    fn visit_dependencies<'a>(
+        plugin_info: &'a AvailablePluginInfo,
+        available_plugins: &'a [AvailablePluginInfo],
+        installed: &HashMap<String, String>,
+        visiting: &mut Vec<String>,
+        install_order: &mut Vec<&'a AvailablePluginInfo>,
+    ) -> Result<(), String> {
+        visiting.push(plugin_info.id.clone());
+
+        for dependency in parse_dependencies(&plugin_info.dependencies)? {
+            // 已安装的版本不满足要求时拒绝安装，不覆盖其他插件可能依赖的现有版本
+            match installed.get(&dependency.id) {
+                Some(version) if dependency.matches(version) => continue,
+                Some(version) => {
+                    return Err(format!(
+                        "依赖 {} 与已安装的版本 {} 冲突，请先升级或卸载插件 {}",
+                        dependency, version, dependency.id
+                    ));
+                }
+                None => {}
+            }
+
+            if visiting.contains(&dependency.id) {
+                return Err(format!(
+                    "检测到循环依赖: {} -> {}",
+                    visiting.join(" -> "),
+                    dependency.id
+                ));
+            }
+
+            let candidate = available_plugins
+                .iter()
+                .find(|p| p.id == dependency.id)
+                .ok_or_else(|| format!("插件仓库中找不到依赖 {}", dependency))?;
+            if !dependency.matches(&candidate.version) {
+                return Err(format!(
+                    "依赖 {} 无法满足，插件仓库中的版本为 {}",
+                    dependency, candidate.version
+                ));
+            }
+
+            if install_order.iter().any(|p| p.id == candidate.id) {
+                continue;
+            }
+
+            Self::visit_dependencies(
+                candidate,
+                available_plugins,
+                installed,
+                visiting,
+                install_order,
+            )?;
+            install_order.push(candidate);
+        }
+
+        visiting.pop();
+        Ok(())
+    }
+
+    /// 检查已安装插件是否有新版本
+    pub fn check_plugin_updates(&self) -> Vec<PluginUpdateInfo> {
+        let available_plugins = self.scan_available_plugins();
+        let mut updates = Vec::new();
+
+        for (config, _) in self.scan_installed_plugins() {
+            let Some(available) = available_plugins.iter().find(|p| p.id == config.plugin.id)
+            else {
+                continue;
+            };
+            if !Self::is_newer_version(&available.version, &config.plugin.version) {
+                continue;
+            }
+            updates.push(PluginUpdateInfo {
+                id: config.plugin.id,
+                name: config.plugin.name,
+                installed_version: config.plugin.version,
+                available_version: available.version.clone(),
+                incompatible_reason: available.incompatible_reason.clone(),
+            });
+        }
+
+        updates
+    }
+
+    /// 升级已安装的插件，沿用安装流程，保留插件设置、密钥和 `data/` 目录中的用户数据
+    pub async fn upgrade_plugin(
+        &self,
+        plugin_id: &str,
+        task: &DownloadTask,
+    ) -> PluginDownloadResult {
+        log_info!("开始升级插件: {}", plugin_id);
+
+        let installed = self
+            .scan_installed_plugins()
+            .into_iter()
+            .find(|(config, _)| config.plugin.id == plugin_id);
+        let Some((installed_config, _)) = installed else {
+            return PluginDownloadResult {
+                success: false,
+                message: format!("插件 {} 未安装", plugin_id),
+                plugin_id: Some(plugin_id.to_string()),
+                installed_path: None,
+            };
+        };
+
+        let available_version = self
+            .scan_available_plugins()
+            .into_iter()
+            .find(|p| p.id == plugin_id)
+            .map(|p| p.version);
+        match available_version {
+            Some(version) if Self::is_newer_version(&version, &installed_config.plugin.version) => {
+            }
+            Some(_) => {
+                return PluginDownloadResult {
+                    success: false,
+                    message: format!(
+                        "插件 {} 已是最新版本 v{}",
+                        plugin_id, installed_config.plugin.version
+                    ),
+                    plugin_id: Some(plugin_id.to_string()),
+                    installed_path: None,
+                };
+            }
+            None => {
+                return PluginDownloadResult {
+                    success: false,
+                    message: format!("插件仓库中找不到插件 {}", plugin_id),
+                    plugin_id: Some(plugin_id.to_string()),
+                    installed_path: None,
+                };
+            }
+        }
+
+        // 插件设置和密钥按插件 ID 存放在安装目录之外，升级后自动沿用
+        let mut result = self.download_plugin(plugin_id, task).await;
+        if result.success {
+            result.message = format!(
+                "插件 {} 已从 v{} 升级，重新挂载后生效",
+                installed_config.plugin.name, installed_config.plugin.version
+            );
+        }
+        result
+    }
+
+    /// 扫描安装目录中的插件配置
+    fn scan_installed_plugins(&self) -> Vec<(PluginConfig, PathBuf)> {
+        let install_dir = get_root_plugin_installed_directory();
+        if !install_dir.exists() {
+            return Vec::new();
+        }
+
+        WalkDir::new(&install_dir)
+            .min_depth(1)
+            .max_depth(1)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|entry| entry.file_type().is_dir())
+            .filter_map(|entry| {
+                let config_path = entry.path().join("config.toml");
+                match PluginConfig::from_file(&config_path) {
+                    Ok(config) => Some((config, entry.path().to_path_buf())),
+                    Err(e) => {
+                        log_warn!("Failed to load plugin config from {:?}: {}", config_path, e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// `candidate` 是否比 `current` 更新，无法解析的版本号视为不可比较
+    fn is_newer_version(candidate: &str, current: &str) -> bool {
+        match (parse_version(candidate), parse_version(current)) {
+            (Ok(candidate), Ok(current)) => candidate > current,
+            _ => false,
+        }
+    }
+
+    /// 读取、修改并写回安装记录，失败时只记录警告
+    fn update_installed_lock<F>(update: F)
+    where
+        F: FnOnce(&mut InstalledLock),
+    {
+        let result = InstalledLock::load().and_then(|mut lock| {
+            update(&mut lock);
+            lock.save()
+        });
+        if let Err(e) = result {
+            log_warn!("更新安装记录失败: {}", e);
+        }
+    }
+
+    /// 回滚插件到安装或升级前的版本，确认存在备份后才释放插件实例和动态库
+    pub fn rollback_plugin(&self, plugin_id: &str) -> PluginDownloadResult {
+        log_info!("开始回滚插件: {}", plugin_id);
+
+        let previous_version = backup_version(plugin_id);
+        match rollback_installed_plugin(plugin_id, || self.release_before_replace(plugin_id)) {
+            Ok(plugin_dir) => {
+                log_info!("插件 {} 已回滚: {:?}", plugin_id, plugin_dir);
+                Self::update_installed_lock(|lock| lock.record_rollback(plugin_id));
+                PluginDownloadResult {
+                    success: true,
+                    message: match previous_version {
+                        Some(version) => format!("插件 {} 已回滚到 v{}", plugin_id, version),
+                        None => format!("插件 {} 已回滚", plugin_id),
+                    },
+                    plugin_id: Some(plugin_id.to_string()),
+                    installed_path: Some(plugin_dir.to_string_lossy().to_string()),
+                }
+            }
             Err(error) => PluginDownloadResult {
                 success: false,
-                message: format!("下载插件失败: {}", error),
+                message: format!("回滚插件失败: {}", error),
                 plugin_id: Some(plugin_id.to_string()),
                 installed_path: None,
             },
         }
     }
 
-    /// 卸载已安装的插件
-    pub fn uninstall_plugin(&self, plugin_id: &str) -> PluginDownloadResult {
+    /// 检查其他插件是否依赖该插件：默认拒绝卸载，`force` 为 true 时仅记录警告，返回依赖它的插件名称
+    pub fn check_uninstall_dependents(
+        &self,
+        plugin_id: &str,
+        force: bool,
+    ) -> Result<Vec<String>, String> {
+        let dependents: Vec<String> = find_dependents(plugin_id)
+            .into_iter()
+            .map(|plugin| plugin.name)
+            .collect();
+        if !dependents.is_empty() {
+            if !force {
+                return Err(format!(
+                    "以下插件依赖 {}，无法卸载: {}",
+                    plugin_id,
+                    dependents.join(", ")
+                ));
+            }
+            log_warn!(
+                "强制卸载插件 {}，以下插件将缺少依赖: {}",
+                plugin_id,
+                dependents.join(", ")
+            );
+        }
+        Ok(dependents)
+    }
+
+    /// 删除已安装插件的文件和备份版本，调用前需先通过
+    /// [`crate::plugins::PluginManager::uninstall_plugin`] 检查依赖关系并释放插件实例和动态库
+    ///
+    /// `dependents` 为依赖该插件的插件名称，用于提示卸载后无法启动的插件。
+    /// `keep_user_data` 为 true 时保留 `data/` 目录、插件设置和密钥，重新安装后继续使用
+    pub fn uninstall_plugin(
+        &self,
+        plugin_id: &str,
+        dependents: &[String],
+        keep_user_data: bool,
+    ) -> PluginDownloadResult {
         log_info!("开始卸载插件: {}", plugin_id);
 
+        let failure = |message: String| PluginDownloadResult {
+            success: false,
+            message,
+            plugin_id: Some(plugin_id.to_string()),
+            installed_path: None,
+        };
+
+        if let Err(message) = validate_plugin_id(plugin_id) {
+            return failure(message);
+        }
+
         // 获取已安装插件目录
         let install_dir = get_root_plugin_installed_directory();
         let plugin_dir = install_dir.join(plugin_id);
 
         if !plugin_dir.exists() {
-            return PluginDownloadResult {
-                success: false,
-                message: format!("插件 {} 未安装或已被删除", plugin_id),
-                plugin_id: Some(plugin_id.to_string()),
-                installed_path: None,
-            };
+            return failure(format!("插件 {} 未安装或已被删除", plugin_id));
         }
 
         // 读取插件配置以获取插件名称
@@ -217,37 +746,183 @@ impl PluginRepository {
             plugin_id.to_string()
         };
 
-        // 删除插件目录及其所有内容
-        match std::fs::remove_dir_all(&plugin_dir) {
+        let removed = if keep_user_data {
+            remove_plugin_files_except_data(&plugin_dir)
+        } else {
+            std::fs::remove_dir_all(&plugin_dir)
+                .map_err(|e| e.to_string())
+                .map(|_| remove_plugin_user_data(plugin_id))
+        };
+
+        match removed {
             Ok(_) => {
+                Self::update_installed_lock(|lock| {
+                    lock.remove(plugin_id);
+                });
+                if let Err(e) = remove_backup(plugin_id) {
+                    log_warn!("删除插件 {} 的备份失败: {}", plugin_id, e);
+                }
                 log_info!(
-                    "插件 {} 卸载成功，已删除目录: {:?}",
+                    "插件 {} 卸载成功，已删除目录: {:?}，保留用户数据: {}",
                     plugin_name,
-                    plugin_dir
+                    plugin_dir,
+                    keep_user_data
                 );
+                let message = if dependents.is_empty() {
+                    format!("插件 \"{}\" 卸载成功", plugin_name)
+                } else {
+                    format!(
+                        "插件 \"{}\" 卸载成功，以下插件将无法启动: {}",
+                        plugin_name,
+                        dependents.join(", ")
+                    )
+                };
                 PluginDownloadResult {
                     success: true,
-                    message: format!("插件 \"{}\" 卸载成功", plugin_name),
+                    message,
                     plugin_id: Some(plugin_id.to_string()),
                     installed_path: Some(plugin_dir.to_string_lossy().to_string()),
                 }
             }
             Err(error) => {
                 log_warn!("删除插件目录失败: {:?}, 错误: {}", plugin_dir, error);
+                failure(format!("卸载插件失败: {}", error))
+            }
+        }
+    }
+
+    /// 从本地插件包（`.ccplugin`、`.zip`、`.tar.gz` 或插件目录）安装插件，用于无法访问插件仓库的环境
+    pub fn install_plugin_from_path(&self, package_path: &Path) -> PluginDownloadResult {
+        log_info!("开始从本地插件包安装: {:?}", package_path);
+
+        let package_location = package_path.to_string_lossy();
+        let origin = PackageOrigin {
+            expected_id: None,
+            source: &package_location,
+            download_url: &package_location,
+        };
+        match self.install_package(package_path, &origin) {
+            Ok((config, plugin_dir)) => {
+                log_info!("插件 {} 安装完成: {:?}", config.plugin.name, plugin_dir);
                 PluginDownloadResult {
-                    success: false,
-                    message: format!("卸载插件失败: {}", error),
-                    plugin_id: Some(plugin_id.to_string()),
-                    installed_path: None,
+                    success: true,
+                    message: format!(
+                        "插件 {} v{} 已从本地插件包安装",
+                        config.plugin.name, config.plugin.version
+                    ),
+                    plugin_id: Some(config.plugin.id),
+                    installed_path: Some(plugin_dir.to_string_lossy().to_string()),
                 }
             }
+            Err(error) => PluginDownloadResult {
+                success: false,
+                message: format!("安装本地插件包失败: {}", error),
+                plugin_id: None,
+                installed_path: None,
+            },
+        }
+    }
+
+    /// 解包并安装插件包，返回插件配置和安装目录
+    fn install_package(
+        &self,
+        package_path: &Path,
+        origin: &PackageOrigin,
+    ) -> Result<(PluginConfig, PathBuf), String> {
+        // 先解包到临时目录，读取配置后才能确定插件 ID
+        let unpack_dir = get_plugin_staging_directory().join(format!("package-{}", Uuid::new_v4()));
+        let result = self.install_unpacked_package(package_path, &unpack_dir, origin);
+        if unpack_dir.exists() {
+            if let Err(e) = fs::remove_dir_all(&unpack_dir) {
+                log_warn!("清理解包目录失败: {:?}, 错误: {}", unpack_dir, e);
+            }
+        }
+        result
+    }
+
+    /// 解包、校验插件包并提交到安装目录
+    fn install_unpacked_package(
+        &self,
+        package_path: &Path,
+        unpack_dir: &Path,
+        origin: &PackageOrigin,
+    ) -> Result<(PluginConfig, PathBuf), String> {
+        let plugin_root = unpack_plugin_package(package_path, unpack_dir)?;
+        let config = PluginConfig::from_file(plugin_root.join("config.toml"))
+            .map_err(|e| format!("插件配置文件无效: {}", e))?;
+        let plugin_id = config.plugin.id.clone();
+        validate_plugin_id(&plugin_id)?;
+        if let Some(expected_id) = origin.expected_id {
+            if plugin_id != expected_id {
+                return Err(format!(
+                    "插件包中的插件 ID 不一致: 期望 {}，实际 {}",
+                    expected_id, plugin_id
+                ));
+            }
+        }
+
+        check_compatibility(
+            config.plugin.min_client_version.as_deref(),
+            config.plugin.max_client_version.as_deref(),
+            &config.plugin.platform,
+        )
+        .map_err(|reason| format!("插件 {} 与当前客户端不兼容: {}", plugin_id, reason))?;
+
+        // 动态库位于插件根目录，或 .ccplugin 的 lib/<目标三元组>/ 目录
+        let library_filename = package_library_filename(&config);
+        let library = locate_package_library(&plugin_root, &library_filename).ok_or_else(|| {
+            format!(
+                "插件包中缺少当前平台（{}）的动态库: {}",
+                CLIENT_TARGET, library_filename
+            )
+        })?;
+        verify_library_symbols(&plugin_root.join(&library), &plugin_id)?;
+
+        let installed = installed_plugin_versions();
+        for dependency in parse_dependencies(&config.plugin.dependencies)? {
+            if !installed
+                .get(&dependency.id)
+                .is_some_and(|version| dependency.matches(version))
+            {
+                log_warn!("插件 {} 的依赖 {} 尚未安装", plugin_id, dependency);
+            }
         }
+
+        let staged = StagedInstall::new(&plugin_id)?;
+        copy_dir_all(&plugin_root, staged.dir())
+            .map_err(|e| format!("复制插件文件到暂存目录失败: {}", e))?;
+        prune_other_targets(staged.dir(), &library)?;
+        let library = library.to_string_lossy().to_string();
+        staged.verify(&library)?;
+        let plugin_dir = staged.commit(|| self.release_before_replace(&plugin_id))?;
+
+        // 记录动态库本身的校验值，扫描时同样检查是否被篡改
+        let library_data =
+            fs::read(plugin_dir.join(&library)).map_err(|e| format!("读取动态库失败: {}", e))?;
+        let checksum = format!(
+            "{}:{}",
+            ChecksumAlgorithm::Sha256.as_str(),
+            ChecksumAlgorithm::Sha256.digest_hex(&library_data)
+        );
+        Self::update_installed_lock(|lock| {
+            lock.record_install(InstalledPluginEntry::new(
+                &plugin_id,
+                &config.plugin.version,
+                origin.source,
+                origin.download_url,
+                &checksum,
+                &library,
+            ))
+        });
+
+        Ok((config, plugin_dir))
     }
 
     /// 从目录加载可用插件信息
     fn load_available_plugin_from_directory(
         &self,
-        plugin_dir: &std::path::Path,
+        plugin_dir: &Path,
+        source: &RepositorySource,
     ) -> Option<AvailablePluginInfo> {
         let config_path = plugin_dir.join("config.toml");
 
@@ -257,6 +932,12 @@ impl PluginRepository {
 
         match PluginConfig::from_file(&config_path) {
             Ok(config) => Some(AvailablePluginInfo {
+                incompatible_reason: check_compatibility(
+                    config.plugin.min_client_version.as_deref(),
+                    config.plugin.max_client_version.as_deref(),
+                    &config.plugin.platform,
+                )
+                .err(),
                 id: config.plugin.id,
                 name: config.plugin.name,
                 version: config.plugin.version,
@@ -272,6 +953,9 @@ impl PluginRepository {
                 platform: config.plugin.platform,
                 dependencies: config.plugin.dependencies,
                 download: config.download,
+                source: source.name.clone(),
+                config_url: None,
+                config_checksum: None,
             }),
             Err(e) => {
                 log_warn!("Failed to load plugin config from {:?}: {}", config_path, e);
@@ -280,14 +964,78 @@ impl PluginRepository {
         }
     }
 
+    /// 从注册表索引加载可用插件信息
+    fn load_available_plugins_from_registry(
+        &self,
+        source: &RepositorySource,
+    ) -> Vec<AvailablePluginInfo> {
+        let index_path = source.registry_index_path();
+        if !index_path.exists() {
+            log_warn!(
+                "Plugin registry index does not exist: {:?} (source: {})",
+                index_path,
+                source.name
+            );
+            return Vec::new();
+        }
+
+        log_info!(
+            "Loading plugin registry index: {:?} (source: {})",
+            index_path,
+            source.name
+        );
+
+        match load_index(&index_path) {
+            Ok(index) => index
+                .plugins
+                .into_iter()
+                .map(|entry| {
+                    let incompatible_reason = check_compatibility(
+                        entry.min_client_version.as_deref(),
+                        entry.max_client_version.as_deref(),
+                        &entry.platform,
+                    )
+                    .err();
+                    entry.into_available_plugin(&source.name, incompatible_reason)
+                })
+                .collect(),
+            Err(e) => {
+                log_warn!(
+                    "Failed to load plugin registry index from {:?}: {}",
+                    index_path,
+                    e
+                );
+                Vec::new()
+            }
+        }
+    }
+
     /// 获取当前平台的下载信息
+    /// 依次匹配完整目标三元组、“系统-架构”、架构和系统一致的目标三元组、只按系统区分的旧写法，
+    /// 最后是跨平台插件包
     fn get_platform_download_info<'a>(
         &self,
         download_config: &'a Option<DownloadConfig>,
     ) -> Option<&'a PlatformDownload> {
         let download_config = download_config.as_ref()?;
 
-        if cfg!(target_os = "windows") {
+        let targets = &download_config.targets;
+        if let Some(download) = targets
+            .get(CLIENT_TARGET)
+            .or_else(|| targets.get(&format!("{}-{}", current_platform(), current_arch())))
+        {
+            return Some(download);
+        }
+        let mut matching: Vec<(&String, &PlatformDownload)> = targets
+            .iter()
+            .filter(|(target, _)| target_matches_current(target))
+            .collect();
+        matching.sort_by(|a, b| a.0.cmp(b.0));
+        if let Some((_, download)) = matching.first() {
+            return Some(download);
+        }
+
+        let platform_download = if cfg!(target_os = "windows") {
             download_config.windows.as_ref()
         } else if cfg!(target_os = "macos") {
             download_config.macos.as_ref()
@@ -295,7 +1043,8 @@ impl PluginRepository {
             download_config.linux.as_ref()
         } else {
             None
-        }
+        };
+        platform_download.or(download_config.package.as_ref())
     }
 
     /// 下载并安装插件
@@ -303,38 +1052,46 @@ impl PluginRepository {
         &self,
         plugin_info: &AvailablePluginInfo,
         platform_download: &PlatformDownload,
+        source: &RepositorySource,
+        task: &DownloadTask,
     ) -> Result<String, String> {
-        // 创建HTTP客户端
-        let client = reqwest::Client::new();
+        let plugin_repo_dir = source.plugins_directory().join(&plugin_info.id);
 
         // 下载文件
-        log_info!("正在下载: {}", platform_download.download_url);
-        let response = client
-            .get(&platform_download.download_url)
-            .send()
-            .await
-            .map_err(|e| format!("下载失败: {}", e))?;
-
-        if !response.status().is_success() {
-            return Err(format!("下载失败，HTTP状态码: {}", response.status()));
+        let download_url = source.resolve_url(&plugin_info.id, &platform_download.download_url);
+        log_info!("正在下载: {}", download_url);
+        let file_data = fetch_artifact(&download_url, task).await?;
+
+        // 写入磁盘前校验摘要，校验失败时保留现有安装
+        verify_checksum(&file_data, &platform_download.checksum)?;
+        log_info!("插件 {} 校验通过", plugin_info.id);
+        SignatureVerifier::from_settings()?.check(
+            &format!("插件 {}", plugin_info.id),
+            &file_data,
+            platform_download.signature.as_deref(),
+        )?;
+
+        // .ccplugin 插件包自带清单和多平台动态库，按插件包安装
+        if is_ccplugin_url(&download_url) {
+            return self.install_downloaded_package(
+                plugin_info,
+                &file_data,
+                &PackageOrigin {
+                    expected_id: Some(&plugin_info.id),
+                    source: &source.url,
+                    download_url: &platform_download.download_url,
+                },
+            );
         }
 
-        let file_data = response
-            .bytes()
-            .await
-            .map_err(|e| format!("读取下载数据失败: {}", e))?;
+        // 旧写法的下载项不区分架构，写入前拒绝与当前系统架构不一致的动态库
+        check_binary_arch(&file_data).map_err(|reason| {
+            format!("插件 {} 无法在当前系统上运行: {}", plugin_info.id, reason)
+        })?;
 
-        // 获取目标安装目录
-        let install_dir = get_root_plugin_installed_directory();
-        std::fs::create_dir_all(&install_dir).map_err(|e| format!("创建插件目录失败: {}", e))?;
-
-        // 创建插件专用目录
-        let plugin_dir = install_dir.join(&plugin_info.id);
-        if plugin_dir.exists() {
-            std::fs::remove_dir_all(&plugin_dir)
-                .map_err(|e| format!("删除现有插件目录失败: {}", e))?;
-        }
-        std::fs::create_dir_all(&plugin_dir).map_err(|e| format!("创建插件目录失败: {}", e))?;
+        // 先写入暂存目录，校验通过后再替换现有安装
+        let staged = StagedInstall::new(&plugin_info.id)?;
+        let staging_dir = staged.dir();
 
         // 生成文件名：平台前缀
         let platform_prefix = if cfg!(target_os = "windows") {
@@ -358,7 +1115,7 @@ impl PluginRepository {
             "{}{}-{}.{}",
             platform_prefix, plugin_info.id, plugin_info.version, file_extension
         );
-        let library_path = plugin_dir.join(&library_filename);
+        let library_path = staging_dir.join(&library_filename);
 
         log_info!("保存动态链接库到: {:?}", library_path);
 
@@ -366,13 +1123,23 @@ impl PluginRepository {
         std::fs::write(&library_path, &file_data)
             .map_err(|e| format!("保存动态链接库失败: {}", e))?;
 
-        // 复制config.toml文件
-        let source_config_path = get_plugin_repository_directory()
-            .join(&plugin_info.id)
-            .join("config.toml");
-        let target_config_path = plugin_dir.join("config.toml");
-
-        if source_config_path.exists() {
+        // 复制config.toml文件，注册表插件从 config_url 获取
+        let source_config_path = plugin_repo_dir.join("config.toml");
+        let target_config_path = staging_dir.join("config.toml");
+
+        if let Some(config_url) = &plugin_info.config_url {
+            let config_url = source.resolve_url(&plugin_info.id, config_url);
+            let config_checksum = plugin_info
+                .config_checksum
+                .as_deref()
+                .ok_or_else(|| format!("注册表未提供配置文件 {} 的校验值", config_url))?;
+            let config_data = fetch_artifact(&config_url, task).await?;
+            verify_checksum(&config_data, config_checksum)
+                .map_err(|e| format!("配置文件校验失败: {}", e))?;
+            std::fs::write(&target_config_path, config_data)
+                .map_err(|e| format!("保存配置文件失败: {}", e))?;
+            log_info!("配置文件已下载: {} -> {:?}", config_url, target_config_path);
+        } else if source_config_path.exists() {
             std::fs::copy(&source_config_path, &target_config_path)
                 .map_err(|e| format!("复制配置文件失败: {}", e))?;
             log_info!(
@@ -408,6 +1175,20 @@ library = "{}-{}"
             log_info!("已创建基本配置文件: {:?}", target_config_path);
         }
 
+        staged.verify(&library_filename)?;
+        let plugin_dir = staged.commit(|| self.release_before_replace(&plugin_info.id))?;
+
+        // 记录安装来源和校验值，扫描时据此检查动态库是否被篡改
+        Self::update_installed_lock(|lock| {
+            lock.record_install(InstalledPluginEntry::new(
+                &plugin_info.id,
+                &plugin_info.version,
+                &source.url,
+                &platform_download.download_url,
+                &platform_download.checksum,
+                &library_filename,
+            ))
+        });
         log_info!("插件 {} 安装完成: {:?}", plugin_info.name, plugin_dir);
 
         // 触发插件扫描以更新插件列表
@@ -416,111 +1197,331 @@ library = "{}-{}"
         Ok(plugin_dir.to_string_lossy().to_string())
     }
 
-    pub async fn download_github_repo(&self) -> Result<DownloadResponse, String> {
-        let repo_url: String = GITHUB_PLUGIN_REPO.to_string();
-        // 验证URL格式
-        if !repo_url.starts_with("https://github.com/") {
-            return Ok(DownloadResponse {
+    /// 安装从插件仓库下载的 `.ccplugin` 插件包
+    fn install_downloaded_package(
+        &self,
+        plugin_info: &AvailablePluginInfo,
+        package_data: &[u8],
+        origin: &PackageOrigin,
+    ) -> Result<String, String> {
+        let staging_root = get_plugin_staging_directory();
+        fs::create_dir_all(&staging_root).map_err(|e| format!("创建暂存目录失败: {}", e))?;
+        let package_path = staging_root.join(format!(
+            "{}-{}.{}",
+            plugin_info.id,
+            Uuid::new_v4(),
+            CCPLUGIN_EXTENSION
+        ));
+        fs::write(&package_path, package_data).map_err(|e| format!("保存插件包失败: {}", e))?;
+
+        let result = self.install_package(&package_path, origin);
+        if let Err(e) = fs::remove_file(&package_path) {
+            log_warn!("清理插件包失败: {:?}, 错误: {}", package_path, e);
+        }
+        let (config, plugin_dir) = result?;
+        if config.plugin.version != plugin_info.version {
+            log_warn!(
+                "插件包 {} 的版本 {} 与仓库信息中的版本 {} 不一致",
+                plugin_info.id,
+                config.plugin.version,
+                plugin_info.version
+            );
+        }
+        log_info!("插件 {} 安装完成: {:?}", plugin_info.name, plugin_dir);
+        Ok(plugin_dir.to_string_lossy().to_string())
+    }
+
+    /// 刷新所有已启用的仓库源：下载远程仓库快照，检查本地仓库目录，并校验快照签名
+    pub async fn refresh_repositories(
+        &self,
+        task: &DownloadTask,
+    ) -> Result<DownloadResponse, String> {
+        let sources = RepositorySources::load();
+        let mut failures = Vec::new();
+        let mut refreshed = 0;
+
+        for source in sources.enabled_by_priority() {
+            // 取消后不再刷新剩余的仓库源
+            task.check_cancelled()?;
+            match self.refresh_repository(source, task).await {
+                Ok(snapshot_dir) => {
+                    refreshed += 1;
+                    log_info!("仓库源 {} 已更新: {:?}", source.name, snapshot_dir);
+                }
+                Err(error) => {
+                    log_warn!("更新仓库源 {} 失败: {}", source.name, error);
+                    failures.push(format!("{}: {}", source.name, error));
+                }
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(DownloadResponse {
+                success: true,
+                message: format!("已更新 {} 个插件仓库源", refreshed),
+                download_path: Some(
+                    get_repository_cache_directory()
+                        .to_string_lossy()
+                        .to_string(),
+                ),
+            })
+        } else {
+            Ok(DownloadResponse {
                 success: false,
-                message: "无效的GitHub仓库URL".to_string(),
+                message: format!("部分插件仓库源更新失败: {}", failures.join("; ")),
                 download_path: None,
-            });
+            })
         }
+    }
 
-        // 从URL提取仓库信息
-        let repo_info = extract_repo_info(&repo_url)?;
-        let zip_url = format!(
-            "https://github.com/{}/{}/archive/refs/heads/main.zip",
-            repo_info.owner, repo_info.name
-        );
-
-        // 获取 home_dir/.chat_client
-        let plugins_dir = get_plugin_repository_root();
+    /// 刷新单个仓库源，返回快照目录
+    async fn refresh_repository(
+        &self,
+        source: &RepositorySource,
+        task: &DownloadTask,
+    ) -> Result<PathBuf, String> {
+        let target_dir = source.snapshot_directory();
+
+        if source.is_registry() {
+            if source.local_path().is_none() {
+                // 远程注册表只获取索引，未变化时沿用缓存
+                let client = http_client()?;
+                let fetched = fetch_index(&client, &source.url, &target_dir).await?;
+                log_info!(
+                    "仓库源 {} 的注册表索引包含 {} 个插件，未变化: {}",
+                    source.name,
+                    fetched.index.plugins.len(),
+                    fetched.not_modified
+                );
+            } else if !source.registry_index_path().is_file() {
+                return Err(format!(
+                    "本地注册表索引不存在: {:?}",
+                    source.registry_index_path()
+                ));
+            }
+            self.verify_repository_snapshot(source)?;
+            return Ok(target_dir);
+        }
 
-        // 确保plugins目录不存在
-        if !plugins_dir.exists() {
-            fs::create_dir_all(&plugins_dir).map_err(|e| format!("无法创建plugins目录: {}", e))?;
+        if source.local_path().is_some() {
+            // 本地仓库源无需下载，只检查目录并校验签名
+            if !target_dir.is_dir() {
+                return Err(format!("本地仓库目录不存在: {:?}", target_dir));
+            }
+            self.verify_repository_snapshot(source)?;
+            return Ok(target_dir);
         }
 
+        let zip_url = source.archive_url()?;
+
         // 下载ZIP文件
-        let client = reqwest::Client::new();
-        let response = client
-            .get(&zip_url)
-            .send()
-            .await
-            .map_err(|e| format!("下载失败: {}", e))?;
+        let client = http_client()?;
+        let zip_data = task.fetch(&client, &zip_url).await?;
+        let commit = resolve_snapshot_commit(source, &zip_data)?;
 
-        if !response.status().is_success() {
-            return Ok(DownloadResponse {
-                success: false,
-                message: format!("下载失败，HTTP状态码: {}", response.status()),
-                download_path: None,
-            });
+        if let Some(parent) = target_dir.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("无法创建仓库缓存目录: {}", e))?;
         }
 
-        let zip_data = response
-            .bytes()
-            .await
-            .map_err(|e| format!("读取下载数据失败: {}", e))?;
-
-        // 解压ZIP文件
-        let cursor = Cursor::new(zip_data);
-        let mut archive = ZipArchive::new(cursor).map_err(|e| format!("无法打开ZIP文件: {}", e))?;
-
-        // 目标目录路径
-        let target_dir = plugins_dir.join(&repo_info.name);
-
-        // 如果目标目录已存在，先删除
-        if target_dir.exists() {
-            fs::remove_dir_all(&target_dir).map_err(|e| format!("无法删除现有目录: {}", e))?;
-        }
-
-        // 解压文件
-        for i in 0..archive.len() {
-            let mut file = archive
-                .by_index(i)
-                .map_err(|e| format!("无法读取ZIP文件条目: {}", e))?;
-
-            let outpath = match file.enclosed_name() {
-                Some(path) => {
-                    // 移除ZIP文件中的根目录前缀（通常是 repo-name-main/）
-                    let path_components: Vec<_> = path.components().collect();
-                    if path_components.len() > 1 {
-                        let relative_path: std::path::PathBuf =
-                            path_components[1..].iter().collect();
-                        target_dir.join(relative_path)
-                    } else {
-                        continue; // 跳过根目录本身
-                    }
-                }
-                None => continue,
-            };
+        // 去掉归档中的根目录前缀（通常是 repo-name-main/）。解压后先校验仓库快照签名，
+        // 通过后才替换现有快照；按 require 策略校验失败时丢弃新快照，保留原有快照
+        let verifier = SignatureVerifier::from_settings()?;
+        archive::extract_zip_verified(
+            Cursor::new(&zip_data[..]),
+            &target_dir,
+            true,
+            &ExtractLimits::from_settings(),
+            |snapshot_dir| Self::verify_repository_directory(&verifier, snapshot_dir),
+        )?;
+
+        // 记录快照对应的提交，便于团队成员固定到同一提交
+        match &commit {
+            Some(commit) => log_info!("仓库源 {} 的快照对应提交 {}", source.name, commit),
+            None => log_warn!("无法确定仓库源 {} 的快照对应的提交", source.name),
+        }
+        let mut lock = RepositoryLock::load()?;
+        lock.record(ResolvedRepository::new(source, commit));
+        lock.save()?;
 
-            if file.name().ends_with('/') {
-                // 创建目录
-                fs::create_dir_all(&outpath)
-                    .map_err(|e| format!("无法创建目录 {:?}: {}", outpath, e))?;
-            } else {
-                // 创建文件
-                if let Some(p) = outpath.parent() {
-                    if !p.exists() {
-                        fs::create_dir_all(p)
-                            .map_err(|e| format!("无法创建父目录 {:?}: {}", p, e))?;
-                    }
-                }
+        Ok(target_dir)
+    }
+}
+
+/// 从归档注释中读取快照对应的提交，固定了提交的仓库源检查两者是否一致
+///
+/// 只有 GitHub 仓库源的下载地址会使用固定的提交，其他仓库源忽略该配置，不记录未生效的提交
+fn resolve_snapshot_commit(
+    source: &RepositorySource,
+    zip_data: &[u8],
+) -> Result<Option<String>, String> {
+    let archived = archive::zip_comment(Cursor::new(zip_data))
+        .filter(|comment| comment.len() == 40 && is_commit_sha(comment))
+        .map(|comment| comment.to_lowercase());
+
+    let Some(pinned) = source.commit.as_ref().filter(|_| source.is_github()) else {
+        return Ok(archived);
+    };
+    let pinned = pinned.to_lowercase();
+    match archived {
+        Some(archived) if !archived.starts_with(&pinned) => Err(format!(
+            "仓库源 {} 的快照对应提交 {}，与固定的提交 {} 不一致",
+            source.name, archived, pinned
+        )),
+        Some(archived) => Ok(Some(archived)),
+        None => Ok(Some(pinned)),
+    }
+}
 
-                let mut outfile = fs::File::create(&outpath)
-                    .map_err(|e| format!("无法创建文件 {:?}: {}", outpath, e))?;
+/// 删除插件目录中除 `data/` 以外的文件，保留用户数据供重新安装时使用
+fn remove_plugin_files_except_data(plugin_dir: &Path) -> Result<(), String> {
+    let entries = fs::read_dir(plugin_dir).map_err(|e| format!("读取插件目录失败: {}", e))?;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        if entry.file_name() == USER_DATA_DIRECTORY {
+            continue;
+        }
+        let path = entry.path();
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        result.map_err(|e| format!("删除 {:?} 失败: {}", path, e))?;
+    }
+    Ok(())
+}
 
-                std::io::copy(&mut file, &mut outfile)
-                    .map_err(|e| format!("无法写入文件 {:?}: {}", outpath, e))?;
+/// 删除插件设置和密钥库中的插件密钥，失败时只记录警告
+fn remove_plugin_user_data(plugin_id: &str) {
+    if let Err(e) = PluginSettingsStore::new().remove(plugin_id) {
+        log_warn!("删除插件 {} 的设置失败: {}", plugin_id, e);
+    }
+
+    // 密钥库未解锁时无法删除密钥，保留到下次手动清理
+    let mut vault = get_secret_vault().lock().unwrap();
+    match vault.list(plugin_id) {
+        Ok(names) => {
+            for name in names {
+                if let Err(e) = vault.delete(plugin_id, &name) {
+                    log_warn!("删除插件 {} 的密钥 {} 失败: {}", plugin_id, name, e);
+                }
             }
         }
+        Err(e) => log_warn!("无法清理插件 {} 的密钥: {}", plugin_id, e),
+    }
+}
+
+/// 下载地址是否指向 `.ccplugin` 插件包（忽略查询参数）
+fn is_ccplugin_url(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.to_lowercase()
+        .ends_with(&format!(".{}", CCPLUGIN_EXTENSION))
+}
+
+/// 读取插件文件：支持 http(s) URL、`file://` URL 和本地路径，相对地址需先经
+/// [`RepositorySource::resolve_url`] 解析
+async fn fetch_artifact(url: &str, task: &DownloadTask) -> Result<Vec<u8>, String> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        let client = http_client()?;
+        return task.fetch(&client, url).await;
+    }
+
+    task.check_cancelled()?;
+    let path = file_url_to_path(url);
+    fs::read(&path).map_err(|e| format!("读取插件文件 {:?} 失败: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::registry::RegistryIndex;
+    use serde_json::json;
+
+    /// 由 JSON 注册表索引构造内存中的可用插件列表
+    fn index(plugins: serde_json::Value) -> Vec<AvailablePluginInfo> {
+        serde_json::from_value::<RegistryIndex>(json!({ "plugins": plugins }))
+            .unwrap()
+            .plugins
+            .into_iter()
+            .map(|entry| entry.into_available_plugin("memory", None))
+            .collect()
+    }
+
+    /// 解析插件的依赖，返回按安装顺序排列的插件 ID
+    fn resolve<'a>(
+        available: &'a [AvailablePluginInfo],
+        plugin_id: &str,
+        installed: &[(&str, &str)],
+    ) -> Result<Vec<&'a str>, String> {
+        let plugin_info = available.iter().find(|p| p.id == plugin_id).unwrap();
+        let installed = installed
+            .iter()
+            .map(|(id, version)| (id.to_string(), version.to_string()))
+            .collect();
+        PluginRepository::resolve_install_order(plugin_info, available, &installed)
+            .map(|order| order.into_iter().map(|p| p.id.as_str()).collect())
+    }
+
+    #[test]
+    fn dependencies_are_installed_before_dependents() {
+        let available = index(json!([
+            { "id": "app", "name": "app", "version": "1.0.0", "dependencies": ["core", "ui >= 1.0"] },
+            { "id": "ui", "name": "ui", "version": "1.2.0", "dependencies": ["core ^2"] },
+            { "id": "core", "name": "core", "version": "2.1.0" },
+        ]));
+
+        assert_eq!(resolve(&available, "app", &[]), Ok(vec!["core", "ui"]));
+    }
+
+    #[test]
+    fn satisfied_installed_dependencies_are_skipped() {
+        let available = index(json!([
+            { "id": "app", "name": "app", "version": "1.0.0", "dependencies": ["core >= 2", "ui"] },
+            { "id": "ui", "name": "ui", "version": "1.0.0" },
+            { "id": "core", "name": "core", "version": "2.1.0" },
+        ]));
+
+        assert_eq!(
+            resolve(&available, "app", &[("core", "2.0.0")]),
+            Ok(vec!["ui"])
+        );
+    }
+
+    #[test]
+    fn installed_version_conflict_is_rejected() {
+        let available = index(json!([
+            { "id": "app", "name": "app", "version": "1.0.0", "dependencies": ["ui"] },
+            { "id": "ui", "name": "ui", "version": "1.0.0", "dependencies": ["core >= 2"] },
+            { "id": "core", "name": "core", "version": "2.1.0" },
+        ]));
+
+        // 仓库中有满足要求的版本也不覆盖已安装的版本
+        let error = resolve(&available, "app", &[("core", "1.5.0")]).unwrap_err();
+        assert!(error.contains("与已安装的版本 1.5.0 冲突"), "{}", error);
+    }
+
+    #[test]
+    fn dependency_cycles_are_rejected() {
+        let available = index(json!([
+            { "id": "a", "name": "a", "version": "1.0.0", "dependencies": ["b"] },
+            { "id": "b", "name": "b", "version": "1.0.0", "dependencies": ["c"] },
+            { "id": "c", "name": "c", "version": "1.0.0", "dependencies": ["a"] },
+        ]));
+
+        let error = resolve(&available, "a", &[]).unwrap_err();
+        assert!(error.contains("循环依赖: a -> b -> c -> a"), "{}", error);
+    }
 
-        Ok(DownloadResponse {
-            success: true,
-            message: format!("成功下载并解压仓库到: {:?}", target_dir),
-            download_path: Some(target_dir.to_string_lossy().to_string()),
-        })
+    #[test]
+    fn missing_or_unsatisfiable_dependencies_are_rejected() {
+        let available = index(json!([
+            { "id": "app", "name": "app", "version": "1.0.0", "dependencies": ["core >= 3"] },
+            { "id": "tool", "name": "tool", "version": "1.0.0", "dependencies": ["missing"] },
+            { "id": "core", "name": "core", "version": "2.1.0" },
+        ]));
+
+        let error = resolve(&available, "app", &[]).unwrap_err();
+        assert!(error.contains("插件仓库中的版本为 2.1.0"), "{}", error);
+        let error = resolve(&available, "tool", &[]).unwrap_err();
+        assert!(error.contains("找不到依赖 missing"), "{}", error);
     }
 }
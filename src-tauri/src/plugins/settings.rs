@@ -0,0 +1,242 @@
+use plugin_interfaces::log_warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::plugins::{config::PluginSettingField, directories::get_plugin_settings_directory};
+use crate::settings::store::write_file_atomic;
+
+/// 串行化插件设置文件的读取-修改-写入，避免并发更新互相覆盖
+static SETTINGS_WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 插件设置持久化文件结构（~/.chat_client/plugin_settings/<plugin_id>.toml）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PluginSettingsFile {
+    /// 插件级别的设置值
+    #[serde(default)]
+    values: HashMap<String, toml::Value>,
+    /// 实例级别的设置值，覆盖插件级别的设置
+    #[serde(default)]
+    instances: HashMap<String, HashMap<String, toml::Value>>,
+}
+
+/// 返回给前端的插件设置视图
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginSettingsView {
+    pub schema: Vec<PluginSettingField>,
+    /// 当前生效的非敏感设置值
+    pub values: HashMap<String, serde_json::Value>,
+    /// 已配置值的敏感设置项
+    pub configured_secrets: Vec<String>,
+    /// 尚未配置的必填设置项
+    pub missing_required: Vec<String>,
+}
+
+/// 插件设置存储
+#[derive(Debug)]
+pub struct PluginSettingsStore;
+
+impl Default for PluginSettingsStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PluginSettingsStore {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 解析插件实例最终生效的设置：默认值 < 插件级别设置 < 实例级别设置
+    pub fn resolve(
+        &self,
+        plugin_id: &str,
+        schema: &[PluginSettingField],
+        instance_id: Option<&str>,
+    ) -> HashMap<String, toml::Value> {
+        let file = self.load(plugin_id);
+        let instance_values = instance_id.and_then(|id| file.instances.get(id));
+
+        schema
+            .iter()
+            .filter_map(|field| {
+                instance_values
+                    .and_then(|values| values.get(&field.key))
+                    .or_else(|| file.values.get(&field.key))
+                    .or(field.default.as_ref())
+                    .map(|value| (field.key.clone(), value.clone()))
+            })
+            .collect()
+    }
+
+    /// 构建前端使用的设置视图，敏感设置项只返回是否已配置
+    pub fn view(
+        &self,
+        plugin_id: &str,
+        schema: Vec<PluginSettingField>,
+        instance_id: Option<&str>,
+    ) -> PluginSettingsView {
+        let resolved = self.resolve(plugin_id, &schema, instance_id);
+        let mut values = HashMap::new();
+        let mut configured_secrets = Vec::new();
+
+        for field in &schema {
+            let Some(value) = resolved.get(&field.key) else {
+                continue;
+            };
+            if field.secret {
+                configured_secrets.push(field.key.clone());
+            } else if let Ok(json) = serde_json::to_value(value) {
+                values.insert(field.key.clone(), json);
+            }
+        }
+
+        PluginSettingsView {
+            missing_required: self.missing_required(plugin_id, &schema, instance_id),
+            schema,
+            values,
+            configured_secrets,
+        }
+    }
+
+    /// 返回尚未配置的必填设置项
+    pub fn missing_required(
+        &self,
+        plugin_id: &str,
+        schema: &[PluginSettingField],
+        instance_id: Option<&str>,
+    ) -> Vec<String> {
+        let resolved = self.resolve(plugin_id, schema, instance_id);
+        schema
+            .iter()
+            .filter(|field| field.required && !resolved.contains_key(&field.key))
+            .map(|field| field.key.clone())
+            .collect()
+    }
+
+    /// 更新插件（或指定实例）的设置值
+    ///
+    /// 未出现在 `values` 中的设置项保持不变，值为 `None` 的设置项会被清除
+    pub fn update(
+        &self,
+        plugin_id: &str,
+        schema: &[PluginSettingField],
+        instance_id: Option<&str>,
+        values: HashMap<String, Option<toml::Value>>,
+    ) -> Result<(), String> {
+        for (key, value) in &values {
+            let field = schema
+                .iter()
+                .find(|field| &field.key == key)
+                .ok_or_else(|| format!("插件 {} 没有设置项 {}", plugin_id, key))?;
+            if let Some(value) = value {
+                field.validate(value)?;
+            }
+        }
+
+        let _guard = SETTINGS_WRITE_LOCK.lock().unwrap();
+        let mut file = self.load_for_update(plugin_id)?;
+        let target = match instance_id {
+            Some(id) => file.instances.entry(id.to_string()).or_default(),
+            None => &mut file.values,
+        };
+        for (key, value) in values {
+            match value {
+                Some(value) => target.insert(key, value),
+                None => target.remove(&key),
+            };
+        }
+
+        self.save(plugin_id, &file)
+    }
+
+    /// 删除插件实例的设置，返回是否存在该实例的设置
+    pub fn remove_instance(&self, plugin_id: &str, instance_id: &str) -> Result<bool, String> {
+        let _guard = SETTINGS_WRITE_LOCK.lock().unwrap();
+        let mut file = self.load_for_update(plugin_id)?;
+        if file.instances.remove(instance_id).is_none() {
+            return Ok(false);
+        }
+        self.save(plugin_id, &file)?;
+        Ok(true)
+    }
+
+    /// 删除插件的全部设置，返回是否存在设置文件
+    pub fn remove(&self, plugin_id: &str) -> Result<bool, String> {
+        let _guard = SETTINGS_WRITE_LOCK.lock().unwrap();
+        let path = Self::settings_path(plugin_id);
+        if !path.exists() {
+            return Ok(false);
+        }
+        std::fs::remove_file(&path).map_err(|e| format!("删除插件设置失败: {}", e))?;
+        Ok(true)
+    }
+
+    fn settings_path(plugin_id: &str) -> PathBuf {
+        get_plugin_settings_directory().join(format!("{}.toml", plugin_id))
+    }
+
+    /// 读取设置文件，无法解析时使用默认值，但不修改磁盘上的文件
+    fn load(&self, plugin_id: &str) -> PluginSettingsFile {
+        let path = Self::settings_path(plugin_id);
+        let content = Self::read(&path).unwrap_or_else(|e| {
+            log_warn!("{}", e);
+            None
+        });
+        content
+            .and_then(|content| match toml::from_str(&content) {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    log_warn!("解析插件设置文件 {:?} 失败，使用默认设置: {}", path, e);
+                    None
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    /// 读取将要修改的设置文件
+    ///
+    /// 无法解析的文件在被覆盖前重命名为 `<plugin_id>.toml.bak`，保留原内容以便手动恢复
+    fn load_for_update(&self, plugin_id: &str) -> Result<PluginSettingsFile, String> {
+        let path = Self::settings_path(plugin_id);
+        let Some(content) = Self::read(&path)? else {
+            return Ok(PluginSettingsFile::default());
+        };
+        match toml::from_str(&content) {
+            Ok(file) => Ok(file),
+            Err(e) => {
+                let backup_path = path.with_extension("toml.bak");
+                std::fs::rename(&path, &backup_path).map_err(|err| {
+                    format!("备份无法解析的插件设置文件 {:?} 失败: {}", path, err)
+                })?;
+                log_warn!(
+                    "解析插件设置文件 {:?} 失败，原文件已备份到 {:?}: {}",
+                    path,
+                    backup_path,
+                    e
+                );
+                Ok(PluginSettingsFile::default())
+            }
+        }
+    }
+
+    /// 读取设置文件内容，文件不存在时返回 `None`
+    fn read(path: &Path) -> Result<Option<String>, String> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("读取插件设置文件 {:?} 失败: {}", path, e)),
+        }
+    }
+
+    fn save(&self, plugin_id: &str, file: &PluginSettingsFile) -> Result<(), String> {
+        let path = Self::settings_path(plugin_id);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建插件设置目录失败: {}", e))?;
+        }
+        let content =
+            toml::to_string_pretty(file).map_err(|e| format!("序列化插件设置失败: {}", e))?;
+        write_file_atomic(&path, content.as_bytes()).map_err(|e| format!("保存插件设置失败: {}", e))
+    }
+}
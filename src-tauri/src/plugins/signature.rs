@@ -0,0 +1,237 @@
+//! 插件发布者签名校验
+//!
+//! 校验值和二进制来自同一个仓库快照，仓库被篡改时校验值无法提供保护，因此额外使用 Ed25519 签名：
+//!
+//! - 受信任的发布者公钥保存在 `~/.chat_client/trusted_keys.toml`：
+//!
+//!   ```toml
+//!   [[keys]]
+//!   id = "luodeb"
+//!   public_key = "<base64 编码的 32 字节公钥>"
+//!   ```
+//!
+//! - 仓库快照的分离签名保存在仓库根目录的 [`REPOSITORY_SIGNATURE_FILE`] 中，
+//!   签名内容见 [`repository_snapshot_message`]
+//! - 插件二进制的分离签名写在 config.toml 的 `[download.<平台>] signature` 中，签名内容为二进制文件本身
+//!
+//! 签名文本格式为 `<key_id>:<base64 签名>`，省略 `key_id` 时依次尝试所有受信任的公钥。
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ed25519_dalek::{Signature, VerifyingKey};
+use plugin_interfaces::{log_info, log_warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+use crate::plugins::directories::get_trusted_keys_file;
+use crate::settings::{get_app_config_store, SignaturePolicy};
+
+/// 仓库快照签名文件名
+pub const REPOSITORY_SIGNATURE_FILE: &str = "repository.sig";
+
+/// 受信任的发布者公钥
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedKey {
+    pub id: String,
+    /// base64 编码的 Ed25519 公钥
+    pub public_key: String,
+}
+
+/// 受信任公钥文件结构
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrustedKeys {
+    #[serde(default)]
+    pub keys: Vec<TrustedKey>,
+}
+
+impl TrustedKeys {
+    /// 从 `~/.chat_client/trusted_keys.toml` 读取，文件不存在时返回空列表
+    pub fn load() -> Result<Self, String> {
+        Self::load_from(&get_trusted_keys_file())
+    }
+
+    /// 从指定文件读取受信任公钥，文件不存在时返回空列表
+    pub fn load_from(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("读取受信任公钥文件失败: {}", e))?;
+        toml::from_str(&content).map_err(|e| format!("解析受信任公钥文件失败: {}", e))
+    }
+
+    /// 使用受信任公钥校验签名，返回签名所用公钥的 ID
+    pub fn verify(&self, message: &[u8], signature: &str) -> Result<String, String> {
+        let (key_id, signature) = parse_signature(signature)?;
+
+        let candidates: Vec<&TrustedKey> = match &key_id {
+            Some(key_id) => self.keys.iter().filter(|k| &k.id == key_id).collect(),
+            None => self.keys.iter().collect(),
+        };
+        if candidates.is_empty() {
+            return Err(match key_id {
+                Some(key_id) => format!("签名使用的公钥 {} 不在受信任列表中", key_id),
+                None => "未配置任何受信任的公钥".to_string(),
+            });
+        }
+
+        for key in candidates {
+            let verifying_key = match decode_public_key(&key.public_key) {
+                Ok(verifying_key) => verifying_key,
+                Err(e) => {
+                    log_warn!("受信任公钥 {} 无效: {}", key.id, e);
+                    continue;
+                }
+            };
+            if verifying_key.verify_strict(message, &signature).is_ok() {
+                return Ok(key.id.clone());
+            }
+        }
+
+        Err("签名与受信任的公钥不匹配".to_string())
+    }
+}
+
+/// 按当前信任策略校验签名
+pub struct SignatureVerifier {
+    policy: SignaturePolicy,
+    keys: TrustedKeys,
+}
+
+impl SignatureVerifier {
+    /// 使用指定的信任策略和受信任公钥创建校验器
+    pub fn new(policy: SignaturePolicy, keys: TrustedKeys) -> Self {
+        Self { policy, keys }
+    }
+
+    /// 使用应用设置中的信任策略和受信任公钥文件创建校验器
+    pub fn from_settings() -> Result<Self, String> {
+        let policy = get_app_config_store()
+            .lock()
+            .unwrap()
+            .settings()
+            .plugin
+            .signature_policy;
+        let keys = match policy {
+            SignaturePolicy::Off => TrustedKeys::default(),
+            _ => TrustedKeys::load()?,
+        };
+        Ok(Self::new(policy, keys))
+    }
+
+    /// 校验 `subject` 的签名
+    ///
+    /// `require` 策略下缺少签名或校验失败返回错误；`warn` 策略下只记录警告；`off` 策略下不校验
+    pub fn check(
+        &self,
+        subject: &str,
+        message: &[u8],
+        signature: Option<&str>,
+    ) -> Result<(), String> {
+        if self.policy == SignaturePolicy::Off {
+            return Ok(());
+        }
+
+        let result = match signature {
+            Some(signature) => self.keys.verify(message, signature),
+            None => Err("缺少签名".to_string()),
+        };
+
+        match (result, self.policy) {
+            (Ok(key_id), _) => {
+                log_info!("{} 签名校验通过（公钥 {}）", subject, key_id);
+                Ok(())
+            }
+            (Err(e), SignaturePolicy::Require) => Err(format!("{} 签名校验失败: {}", subject, e)),
+            (Err(e), _) => {
+                log_warn!("{} 签名校验失败，按当前策略继续: {}", subject, e);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 计算仓库快照的签名内容
+///
+/// 按相对路径排序，对除签名文件和 `.git` 目录外的每个文件输出一行 `<相对路径>\0<sha256>\n`，
+/// 路径分隔符统一为 `/`
+pub fn repository_snapshot_message(repo_dir: &Path) -> Result<Vec<u8>, String> {
+    let mut entries = Vec::new();
+    for entry in WalkDir::new(repo_dir)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+    {
+        let entry = entry.map_err(|e| format!("遍历仓库目录失败: {}", e))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(repo_dir)
+            .map_err(|e| format!("计算相对路径失败: {}", e))?;
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if relative == REPOSITORY_SIGNATURE_FILE {
+            continue;
+        }
+        let data = fs::read(entry.path())
+            .map_err(|e| format!("读取文件 {:?} 失败: {}", entry.path(), e))?;
+        let digest: String = Sha256::digest(&data)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        entries.push((relative, digest));
+    }
+    entries.sort();
+
+    let mut message = Vec::new();
+    for (path, digest) in entries {
+        message.extend_from_slice(path.as_bytes());
+        message.push(0);
+        message.extend_from_slice(digest.as_bytes());
+        message.push(b'\n');
+    }
+    Ok(message)
+}
+
+/// 读取仓库快照的签名文件，文件不存在时返回 `None`
+pub fn read_repository_signature(repo_dir: &Path) -> Result<Option<String>, String> {
+    let path = repo_dir.join(REPOSITORY_SIGNATURE_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    fs::read_to_string(&path)
+        .map(|content| Some(content.trim().to_string()))
+        .map_err(|e| format!("读取仓库签名失败: {}", e))
+}
+
+/// 解析 `<key_id>:<base64 签名>` 格式的签名文本
+fn parse_signature(text: &str) -> Result<(Option<String>, Signature), String> {
+    let text = text.trim();
+    let (key_id, encoded) = match text.split_once(':') {
+        Some((key_id, encoded)) => (Some(key_id.trim().to_string()), encoded.trim()),
+        None => (None, text),
+    };
+    let bytes = BASE64
+        .decode(encoded)
+        .map_err(|e| format!("签名不是有效的 base64: {}", e))?;
+    let bytes: [u8; 64] = bytes
+        .try_into()
+        .map_err(|_| "签名长度无效，应为 64 字节".to_string())?;
+    Ok((key_id, Signature::from_bytes(&bytes)))
+}
+
+fn decode_public_key(encoded: &str) -> Result<VerifyingKey, String> {
+    let bytes = BASE64
+        .decode(encoded.trim())
+        .map_err(|e| format!("公钥不是有效的 base64: {}", e))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| "公钥长度无效，应为 32 字节".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("公钥无效: {}", e))
+}
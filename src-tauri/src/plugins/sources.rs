@@ -0,0 +1,422 @@
+//! 插件仓库源配置（~/.chat_client/repositories.toml）
+//!
+//! 每个仓库源可以是 GitHub 仓库、直接指向 zip 归档的 URL、本地目录或 `file://` URL，
+//! 仓库内插件的布局均为 `plugins/<id>/config.toml`；地址以 `.json` 结尾时为 JSON 注册表索引，
+//! 格式见 [`crate::plugins::registry`]。多个仓库源中存在同一插件时，优先级高的源生效。
+//!
+//! GitHub 仓库源可以固定到分支、标签或提交（三者只能指定一个），每次下载快照后实际对应的提交
+//! 记录在 `~/.chat_client/repositories.lock` 中。zip 归档、本地目录和注册表索引不支持固定引用。
+//!
+//! ```toml
+//! [[repositories]]
+//! name = "official"
+//! url = "https://github.com/luodeb/chat-client-plugin"
+//! branch = "main"
+//! priority = 0
+//!
+//! [[repositories]]
+//! name = "team"
+//! url = "https://github.com/example/chat-client-plugins"
+//! commit = "3f2a9c1d0b7e4a5f6c8d9e0a1b2c3d4e5f6a7b8c"
+//! priority = 5
+//!
+//! [[repositories]]
+//! name = "internal"
+//! url = "file:///srv/chat-client-plugins"
+//! priority = 10
+//! ```
+
+use plugin_interfaces::log_warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::plugins::{
+    directories::{
+        get_repository_cache_directory, get_repository_lock_file, get_repository_sources_file,
+    },
+    registry::REGISTRY_INDEX_FILE,
+};
+
+/// 默认仓库源名称
+pub const DEFAULT_REPOSITORY_NAME: &str = "official";
+
+/// 默认仓库源地址
+pub const DEFAULT_REPOSITORY_URL: &str = "https://github.com/luodeb/chat-client-plugin";
+
+/// 插件仓库源
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepositorySource {
+    /// 仓库源名称，同时用作本地缓存目录名
+    pub name: String,
+    /// GitHub 仓库地址、zip 归档地址、本地目录或 `file://` URL
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// 固定的提交 SHA（至少 7 位）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// 优先级，数值越大越优先
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+struct RepoInfo {
+    owner: String,
+    name: String,
+}
+
+/// 从GitHub URL提取仓库信息
+fn extract_repo_info(url: &str) -> Result<RepoInfo, String> {
+    // 移除可能的.git后缀和尾部斜杠
+    let clean_url = url.trim_end_matches('/').trim_end_matches(".git");
+
+    // 分割URL获取路径部分
+    let parts: Vec<&str> = clean_url.split('/').collect();
+
+    if parts.len() < 5 || parts[2] != "github.com" {
+        return Err("无效的GitHub仓库URL格式".to_string());
+    }
+
+    let owner = parts[3].to_string();
+    let name = parts[4].to_string();
+
+    if owner.is_empty() || name.is_empty() {
+        return Err("无法从URL中提取仓库所有者或名称".to_string());
+    }
+
+    Ok(RepoInfo { owner, name })
+}
+
+impl RepositorySource {
+    /// 官方仓库源
+    pub fn official() -> Self {
+        Self {
+            name: DEFAULT_REPOSITORY_NAME.to_string(),
+            url: DEFAULT_REPOSITORY_URL.to_string(),
+            branch: Some("main".to_string()),
+            tag: None,
+            commit: None,
+            priority: 0,
+            enabled: true,
+        }
+    }
+
+    /// 本地仓库源的目录（本地路径或 `file://` URL），远程仓库源返回 `None`
+    pub fn local_path(&self) -> Option<PathBuf> {
+        if self.url.starts_with("http://") || self.url.starts_with("https://") {
+            return None;
+        }
+        Some(file_url_to_path(&self.url))
+    }
+
+    /// 是否为 GitHub 仓库源，只有 GitHub 仓库源支持固定分支、标签或提交
+    pub fn is_github(&self) -> bool {
+        self.local_path().is_none() && !self.is_registry() && !self.url.ends_with(".zip")
+    }
+
+    /// 是否为 JSON 注册表索引
+    pub fn is_registry(&self) -> bool {
+        self.url.ends_with(".json")
+    }
+
+    /// 注册表索引文件路径：本地索引即其文件，远程索引为缓存文件
+    pub fn registry_index_path(&self) -> PathBuf {
+        match self.local_path() {
+            Some(path) => path,
+            None => self.snapshot_directory().join(REGISTRY_INDEX_FILE),
+        }
+    }
+
+    /// 仓库快照根目录：本地仓库源即其目录（注册表为索引所在目录），远程仓库源为下载缓存目录
+    pub fn snapshot_directory(&self) -> PathBuf {
+        match self.local_path() {
+            Some(path) if self.is_registry() => {
+                path.parent().map(Path::to_path_buf).unwrap_or_default()
+            }
+            Some(path) => path,
+            None => get_repository_cache_directory().join(&self.name),
+        }
+    }
+
+    /// 解析插件文件地址：绝对地址原样返回，相对地址相对于插件在仓库中的位置
+    /// （注册表为索引所在位置，目录仓库为 `plugins/<id>/`）
+    pub fn resolve_url(&self, plugin_id: &str, url: &str) -> String {
+        if url.starts_with("http://")
+            || url.starts_with("https://")
+            || url.starts_with("file://")
+            || Path::new(url).is_absolute()
+        {
+            return url.to_string();
+        }
+
+        if self.is_registry() && self.local_path().is_none() {
+            let base = self
+                .url
+                .rsplit_once('/')
+                .map(|(base, _)| base)
+                .unwrap_or(&self.url);
+            return format!("{}/{}", base, url);
+        }
+
+        let base = if self.is_registry() {
+            self.snapshot_directory()
+        } else {
+            self.plugins_directory().join(plugin_id)
+        };
+        base.join(url).to_string_lossy().to_string()
+    }
+
+    /// 仓库中存放插件的目录
+    pub fn plugins_directory(&self) -> PathBuf {
+        self.snapshot_directory().join("plugins")
+    }
+
+    /// 远程仓库源的 zip 归档下载地址
+    pub fn archive_url(&self) -> Result<String, String> {
+        if self.url.ends_with(".zip") {
+            return Ok(self.url.clone());
+        }
+
+        let repo_info = extract_repo_info(&self.url)?;
+        let reference = match (&self.commit, &self.tag, &self.branch) {
+            (Some(commit), _, _) => commit.clone(),
+            (None, Some(tag), _) => format!("refs/tags/{}", tag),
+            (None, None, Some(branch)) => format!("refs/heads/{}", branch),
+            (None, None, None) => "refs/heads/main".to_string(),
+        };
+        Ok(format!(
+            "https://github.com/{}/{}/archive/{}.zip",
+            repo_info.owner, repo_info.name, reference
+        ))
+    }
+
+    /// 仓库源固定的引用，例如 `branch:main`、`tag:v1.0.0`、`commit:3f2a9c1`，
+    /// 非 GitHub 仓库源为 `none`
+    pub fn reference(&self) -> String {
+        if !self.is_github() {
+            return "none".to_string();
+        }
+        match (&self.commit, &self.tag, &self.branch) {
+            (Some(commit), _, _) => format!("commit:{}", commit),
+            (None, Some(tag), _) => format!("tag:{}", tag),
+            (None, None, Some(branch)) => format!("branch:{}", branch),
+            (None, None, None) => "branch:main".to_string(),
+        }
+    }
+
+    /// 检查仓库源配置是否有效
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("仓库源名称不能为空".to_string());
+        }
+        if self
+            .name
+            .chars()
+            .any(|c| matches!(c, '/' | '\\' | ':' | '.'))
+        {
+            return Err(format!("仓库源名称 {} 不能包含路径字符", self.name));
+        }
+        if self.url.trim().is_empty() {
+            return Err(format!("仓库源 {} 的地址不能为空", self.name));
+        }
+        let pinned = [
+            self.branch.is_some(),
+            self.tag.is_some(),
+            self.commit.is_some(),
+        ];
+        if pinned.iter().any(|pinned| *pinned) && !self.is_github() {
+            return Err(format!(
+                "仓库源 {} 不是 GitHub 仓库，不支持指定分支、标签或提交",
+                self.name
+            ));
+        }
+        if pinned.iter().filter(|pinned| **pinned).count() > 1 {
+            return Err(format!(
+                "仓库源 {} 只能指定分支、标签或提交中的一个",
+                self.name
+            ));
+        }
+        if let Some(commit) = &self.commit {
+            if !is_commit_sha(commit) {
+                return Err(format!(
+                    "仓库源 {} 的提交 {} 无效，应为 7 到 40 位十六进制 SHA",
+                    self.name, commit
+                ));
+            }
+        }
+        if self.local_path().is_none() && !self.is_registry() {
+            self.archive_url()
+                .map_err(|e| format!("仓库源 {} 的地址无效: {}", self.name, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// 仓库源列表
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepositorySources {
+    #[serde(default)]
+    pub repositories: Vec<RepositorySource>,
+}
+
+impl Default for RepositorySources {
+    fn default() -> Self {
+        Self {
+            repositories: vec![RepositorySource::official()],
+        }
+    }
+}
+
+impl RepositorySources {
+    /// 读取仓库源配置，文件不存在或无效时使用官方仓库源
+    pub fn load() -> Self {
+        let path = get_repository_sources_file();
+        if !path.exists() {
+            return Self::default();
+        }
+        match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| toml::from_str(&content).map_err(|e| e.to_string()))
+        {
+            Ok(sources) => sources,
+            Err(e) => {
+                log_warn!("Failed to load repository sources from {:?}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// 校验并保存仓库源配置
+    pub fn save(&self) -> Result<(), String> {
+        self.validate()?;
+        let path = get_repository_sources_file();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        let content =
+            toml::to_string_pretty(self).map_err(|e| format!("序列化仓库源配置失败: {}", e))?;
+        fs::write(&path, content).map_err(|e| format!("保存仓库源配置失败: {}", e))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (index, source) in self.repositories.iter().enumerate() {
+            source.validate()?;
+            if self.repositories[..index]
+                .iter()
+                .any(|other| other.name == source.name)
+            {
+                return Err(format!("仓库源名称 {} 重复", source.name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn find(&self, name: &str) -> Option<&RepositorySource> {
+        self.repositories.iter().find(|source| source.name == name)
+    }
+
+    /// 按优先级从高到低排列的已启用仓库源，优先级相同时保持配置顺序
+    pub fn enabled_by_priority(&self) -> Vec<&RepositorySource> {
+        let mut sources: Vec<&RepositorySource> =
+            self.repositories.iter().filter(|s| s.enabled).collect();
+        sources.sort_by_key(|source| std::cmp::Reverse(source.priority));
+        sources
+    }
+}
+
+/// 是否为 7 到 40 位十六进制的提交 SHA
+pub fn is_commit_sha(value: &str) -> bool {
+    (7..=40).contains(&value.len()) && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// 仓库源最近一次下载的快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedRepository {
+    /// 仓库源名称
+    pub name: String,
+    pub url: String,
+    /// 下载时固定的引用，格式见 [`RepositorySource::reference`]
+    pub reference: String,
+    /// 快照实际对应的提交 SHA，无法确定时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// 下载时间（Unix 时间戳，秒）
+    pub fetched_at: u64,
+}
+
+impl ResolvedRepository {
+    pub fn new(source: &RepositorySource, commit: Option<String>) -> Self {
+        Self {
+            name: source.name.clone(),
+            url: source.url.clone(),
+            reference: source.reference(),
+            commit,
+            fetched_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// 仓库源锁文件（~/.chat_client/repositories.lock），记录每个仓库源解析到的提交
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepositoryLock {
+    #[serde(default)]
+    pub repositories: Vec<ResolvedRepository>,
+}
+
+impl RepositoryLock {
+    /// 读取锁文件，文件不存在时返回空记录
+    pub fn load() -> Result<Self, String> {
+        let path = get_repository_lock_file();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("读取仓库锁文件失败: {}", e))?;
+        toml::from_str(&content).map_err(|e| format!("解析仓库锁文件失败: {}", e))
+    }
+
+    /// 写回锁文件
+    pub fn save(&self) -> Result<(), String> {
+        let path = get_repository_lock_file();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        let content =
+            toml::to_string_pretty(self).map_err(|e| format!("序列化仓库锁文件失败: {}", e))?;
+        fs::write(&path, content).map_err(|e| format!("写入仓库锁文件失败: {}", e))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ResolvedRepository> {
+        self.repositories.iter().find(|entry| entry.name == name)
+    }
+
+    /// 记录仓库源的最新快照，替换原有记录
+    pub fn record(&mut self, entry: ResolvedRepository) {
+        self.repositories.retain(|e| e.name != entry.name);
+        self.repositories.push(entry);
+    }
+}
+
+/// 把 `file://` URL 或本地路径转换为路径
+pub fn file_url_to_path(url: &str) -> PathBuf {
+    let path = url.strip_prefix("file://").unwrap_or(url);
+    // Windows 下 file:///C:/path 去掉前导斜杠
+    let bytes = path.as_bytes();
+    if bytes.len() > 2 && bytes[0] == b'/' && bytes[2] == b':' {
+        return PathBuf::from(&path[1..]);
+    }
+    PathBuf::from(path)
+}
@@ -0,0 +1,219 @@
+//! 插件分阶段安装
+//!
+//! 安装和升级时先把文件写入 `~/.chat_client/plugin_staging` 下的临时目录，校验通过后再通过重命名
+//! 替换 `installed_plugins/<id>`。下载、校验和暂存都不影响正在运行的插件实例，只有即将替换目录时才调用
+//! `before_swap` 回调释放实例。被替换的旧版本移动到 `~/.chat_client/plugin_backups/<id>`，
+//! 供 [`rollback_installed_plugin`] 恢复。暂存目录、安装目录和备份目录位于同一文件系统，重命名是原子的。
+
+use plugin_interfaces::{log_info, log_warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::plugins::{
+    config::PluginConfig,
+    directories::{
+        get_plugin_backup_directory, get_plugin_staging_directory,
+        get_root_plugin_installed_directory,
+    },
+};
+
+/// 插件安装目录中存放用户数据的子目录，升级时会被保留
+pub const USER_DATA_DIRECTORY: &str = "data";
+
+/// 插件 ID 用作安装、暂存和备份目录名，拒绝可能越出这些目录的 ID
+pub fn validate_plugin_id(plugin_id: &str) -> Result<(), String> {
+    if plugin_id.is_empty() || plugin_id.contains(['/', '\\']) || plugin_id.contains("..") {
+        return Err(format!("插件 ID {:?} 无效", plugin_id));
+    }
+    Ok(())
+}
+
+/// 一次暂存中的安装，未提交时在析构时清理暂存目录
+pub struct StagedInstall {
+    plugin_id: String,
+    dir: PathBuf,
+    committed: bool,
+}
+
+impl StagedInstall {
+    /// 为插件创建新的暂存目录
+    pub fn new(plugin_id: &str) -> Result<Self, String> {
+        validate_plugin_id(plugin_id)?;
+        let dir = get_plugin_staging_directory().join(format!("{}-{}", plugin_id, Uuid::new_v4()));
+        fs::create_dir_all(&dir).map_err(|e| format!("创建暂存目录失败: {}", e))?;
+        Ok(Self {
+            plugin_id: plugin_id.to_string(),
+            dir,
+            committed: false,
+        })
+    }
+
+    /// 暂存目录路径
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 把已安装版本的用户数据目录（`data/`）复制到暂存目录，升级或重装后保留
+    fn preserve_user_data(&self) -> Result<(), String> {
+        let data_dir = get_root_plugin_installed_directory()
+            .join(&self.plugin_id)
+            .join(USER_DATA_DIRECTORY);
+        if !data_dir.is_dir() {
+            return Ok(());
+        }
+        copy_dir_all(&data_dir, &self.dir.join(USER_DATA_DIRECTORY))
+            .map_err(|e| format!("保留插件用户数据失败: {}", e))?;
+        log_info!("已保留插件 {} 的用户数据: {:?}", self.plugin_id, data_dir);
+        Ok(())
+    }
+
+    /// 校验暂存内容：配置文件可解析、插件 ID 一致且动态库存在
+    pub fn verify(&self, library_filename: &str) -> Result<(), String> {
+        let config = PluginConfig::from_file(self.dir.join("config.toml"))
+            .map_err(|e| format!("暂存的配置文件无效: {}", e))?;
+        if config.plugin.id != self.plugin_id {
+            return Err(format!(
+                "暂存的配置文件插件 ID 不一致: 期望 {}，实际 {}",
+                self.plugin_id, config.plugin.id
+            ));
+        }
+        if !self.dir.join(library_filename).is_file() {
+            return Err(format!("暂存目录中缺少动态库: {}", library_filename));
+        }
+        Ok(())
+    }
+
+    /// 将暂存内容替换到安装目录，旧版本移动到备份目录
+    ///
+    /// 替换前先调用 `before_swap` 释放正在使用旧版本的插件实例，再复制旧版本的用户数据，
+    /// 实例在释放前写入的数据也会被保留
+    pub fn commit(mut self, before_swap: impl FnOnce()) -> Result<PathBuf, String> {
+        let install_dir = get_root_plugin_installed_directory();
+        fs::create_dir_all(&install_dir).map_err(|e| format!("创建插件目录失败: {}", e))?;
+        let plugin_dir = install_dir.join(&self.plugin_id);
+
+        before_swap();
+        self.preserve_user_data()?;
+
+        let backup_dir = get_plugin_backup_directory().join(&self.plugin_id);
+        let had_previous = plugin_dir.exists();
+        if had_previous {
+            if let Some(parent) = backup_dir.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("创建备份目录失败: {}", e))?;
+            }
+            if backup_dir.exists() {
+                fs::remove_dir_all(&backup_dir).map_err(|e| format!("删除旧备份失败: {}", e))?;
+            }
+            fs::rename(&plugin_dir, &backup_dir).map_err(|e| format!("备份现有插件失败: {}", e))?;
+        }
+
+        if let Err(e) = fs::rename(&self.dir, &plugin_dir) {
+            // 替换失败时恢复原有安装
+            if had_previous {
+                if let Err(restore_error) = fs::rename(&backup_dir, &plugin_dir) {
+                    log_warn!(
+                        "恢复插件 {} 的原有安装失败: {}",
+                        self.plugin_id,
+                        restore_error
+                    );
+                }
+            }
+            return Err(format!("替换插件目录失败: {}", e));
+        }
+
+        self.committed = true;
+        if had_previous {
+            log_info!("插件 {} 的旧版本已备份到: {:?}", self.plugin_id, backup_dir);
+        }
+        Ok(plugin_dir)
+    }
+}
+
+impl Drop for StagedInstall {
+    fn drop(&mut self) {
+        if !self.committed && self.dir.exists() {
+            if let Err(e) = fs::remove_dir_all(&self.dir) {
+                log_warn!("清理暂存目录失败: {:?}, 错误: {}", self.dir, e);
+            }
+        }
+    }
+}
+
+/// 递归复制目录
+pub fn copy_dir_all(source: &Path, target: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target_path = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target_path)?;
+        } else {
+            fs::copy(entry.path(), &target_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// 读取备份中的插件版本
+pub fn backup_version(plugin_id: &str) -> Option<String> {
+    validate_plugin_id(plugin_id).ok()?;
+    let config_path = get_plugin_backup_directory()
+        .join(plugin_id)
+        .join("config.toml");
+    PluginConfig::from_file(config_path)
+        .ok()
+        .map(|config| config.plugin.version)
+}
+
+/// 删除插件的备份版本，卸载插件后不再保留可回滚的旧版本
+pub fn remove_backup(plugin_id: &str) -> Result<(), String> {
+    validate_plugin_id(plugin_id)?;
+    let backup_dir = get_plugin_backup_directory().join(plugin_id);
+    if !backup_dir.exists() {
+        return Ok(());
+    }
+    fs::remove_dir_all(&backup_dir).map_err(|e| format!("删除插件备份失败: {}", e))
+}
+
+/// 用备份恢复插件的上一个版本，当前版本与备份互换，可再次回滚撤销
+///
+/// 确认存在备份后才调用 `before_swap` 释放正在使用当前版本的插件实例
+pub fn rollback_installed_plugin(
+    plugin_id: &str,
+    before_swap: impl FnOnce(),
+) -> Result<PathBuf, String> {
+    validate_plugin_id(plugin_id)?;
+    let backup_dir = get_plugin_backup_directory().join(plugin_id);
+    if !backup_dir.is_dir() {
+        return Err(format!("插件 {} 没有可回滚的旧版本", plugin_id));
+    }
+
+    let install_dir = get_root_plugin_installed_directory();
+    fs::create_dir_all(&install_dir).map_err(|e| format!("创建插件目录失败: {}", e))?;
+    let plugin_dir = install_dir.join(plugin_id);
+
+    before_swap();
+    if !plugin_dir.exists() {
+        fs::rename(&backup_dir, &plugin_dir).map_err(|e| format!("恢复旧版本失败: {}", e))?;
+        return Ok(plugin_dir);
+    }
+
+    // 当前版本先移到暂存目录，再互换
+    let staging_root = get_plugin_staging_directory();
+    fs::create_dir_all(&staging_root).map_err(|e| format!("创建暂存目录失败: {}", e))?;
+    let current_dir = staging_root.join(format!("{}-{}", plugin_id, Uuid::new_v4()));
+
+    fs::rename(&plugin_dir, &current_dir).map_err(|e| format!("移动当前版本失败: {}", e))?;
+    if let Err(e) = fs::rename(&backup_dir, &plugin_dir) {
+        if let Err(restore_error) = fs::rename(&current_dir, &plugin_dir) {
+            log_warn!("恢复插件 {} 的当前版本失败: {}", plugin_id, restore_error);
+        }
+        return Err(format!("恢复旧版本失败: {}", e));
+    }
+    if let Err(e) = fs::rename(&current_dir, &backup_dir) {
+        log_warn!("保存插件 {} 的当前版本为备份失败: {}", plugin_id, e);
+    }
+
+    Ok(plugin_dir)
+}
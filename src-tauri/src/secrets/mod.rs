@@ -0,0 +1,3 @@
+pub mod vault;
+
+pub use vault::{get_secret_vault, SecretVault, SecretVaultStatus};
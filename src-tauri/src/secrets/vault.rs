@@ -0,0 +1,351 @@
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use plugin_interfaces::log_info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use zeroize::Zeroizing;
+
+use crate::plugins::directories::get_secret_vault_file;
+
+// 版本 1 的明文只包含插件密钥，版本 2 增加主程序自身使用的密钥
+const VAULT_VERSION: u32 = 2;
+const LEGACY_VAULT_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+
+type SecretMap = HashMap<String, Zeroizing<String>>;
+
+// 全局密钥库
+static SECRET_VAULT: OnceLock<Mutex<SecretVault>> = OnceLock::new();
+
+/// 获取全局密钥库
+pub fn get_secret_vault() -> &'static Mutex<SecretVault> {
+    SECRET_VAULT.get_or_init(|| Mutex::new(SecretVault::new(get_secret_vault_file())))
+}
+
+/// 密钥库文件结构（~/.chat_client/secrets.vault）
+///
+/// 所有密钥序列化为 JSON 后整体使用 ChaCha20-Poly1305 加密，
+/// 加密密钥由用户口令经 Argon2id 派生
+#[derive(Debug, Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// 解密后的密钥库内容（版本 2）
+#[derive(Debug, Default, Deserialize)]
+struct VaultContent {
+    #[serde(default)]
+    plugins: HashMap<String, HashMap<String, String>>,
+    #[serde(default)]
+    host: HashMap<String, String>,
+}
+
+/// 写入时借用内存中的密钥，避免产生额外的明文副本
+#[derive(Serialize)]
+struct VaultContentRef<'a> {
+    plugins: HashMap<&'a String, HashMap<&'a String, &'a str>>,
+    host: HashMap<&'a String, &'a str>,
+}
+
+/// 密钥库状态，返回给前端
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretVaultStatus {
+    pub initialized: bool,
+    pub unlocked: bool,
+}
+
+/// 插件密钥库
+pub struct SecretVault {
+    path: PathBuf,
+    salt: Vec<u8>,
+    key: Option<Zeroizing<[u8; 32]>>,
+    secrets: HashMap<String, SecretMap>, // 键为 plugin_id
+    host_secrets: SecretMap,             // 主程序自身使用的密钥（如代理密码），插件无法读取
+}
+
+impl std::fmt::Debug for SecretVault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretVault")
+            .field("path", &self.path)
+            .field("unlocked", &self.key.is_some())
+            .finish()
+    }
+}
+
+impl SecretVault {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            salt: Vec::new(),
+            key: None,
+            secrets: HashMap::new(),
+            host_secrets: HashMap::new(),
+        }
+    }
+
+    /// 获取密钥库状态
+    pub fn status(&self) -> SecretVaultStatus {
+        SecretVaultStatus {
+            initialized: self.path.exists(),
+            unlocked: self.key.is_some(),
+        }
+    }
+
+    /// 使用口令解锁密钥库，密钥库文件不存在时使用该口令创建新的密钥库
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), String> {
+        if passphrase.is_empty() {
+            return Err("口令不能为空".to_string());
+        }
+
+        if !self.path.exists() {
+            let mut salt = vec![0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            self.key = Some(Self::derive_key(passphrase, &salt)?);
+            self.salt = salt;
+            self.secrets = HashMap::new();
+            self.host_secrets = HashMap::new();
+            self.save()?;
+            log_info!("已创建密钥库: {:?}", self.path);
+            return Ok(());
+        }
+
+        let content =
+            std::fs::read_to_string(&self.path).map_err(|e| format!("读取密钥库失败: {}", e))?;
+        let file: VaultFile =
+            toml::from_str(&content).map_err(|e| format!("密钥库文件格式无效: {}", e))?;
+        if file.version != VAULT_VERSION && file.version != LEGACY_VAULT_VERSION {
+            return Err(format!("不支持的密钥库版本: {}", file.version));
+        }
+
+        let salt = BASE64
+            .decode(&file.salt)
+            .map_err(|e| format!("密钥库文件格式无效: {}", e))?;
+        let nonce = BASE64
+            .decode(&file.nonce)
+            .map_err(|e| format!("密钥库文件格式无效: {}", e))?;
+        let ciphertext = BASE64
+            .decode(&file.ciphertext)
+            .map_err(|e| format!("密钥库文件格式无效: {}", e))?;
+        if nonce.len() != 12 {
+            return Err("密钥库文件格式无效: nonce 长度错误".to_string());
+        }
+
+        let key = Self::derive_key(passphrase, &salt)?;
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key[..]));
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+                .map_err(|_| "口令错误或密钥库已损坏".to_string())?,
+        );
+        let content = if file.version == LEGACY_VAULT_VERSION {
+            VaultContent {
+                plugins: serde_json::from_slice(&plaintext)
+                    .map_err(|e| format!("密钥库内容无效: {}", e))?,
+                host: HashMap::new(),
+            }
+        } else {
+            serde_json::from_slice::<VaultContent>(&plaintext)
+                .map_err(|e| format!("密钥库内容无效: {}", e))?
+        };
+
+        self.salt = salt;
+        self.key = Some(key);
+        self.secrets = content
+            .plugins
+            .into_iter()
+            .map(|(plugin_id, values)| (plugin_id, into_secret_map(values)))
+            .collect();
+        self.host_secrets = into_secret_map(content.host);
+        Ok(())
+    }
+
+    /// 锁定密钥库，清除内存中的密钥和明文
+    pub fn lock(&mut self) {
+        self.key = None;
+        self.secrets.clear();
+        self.host_secrets.clear();
+    }
+
+    /// 读取插件的密钥
+    pub fn get(&self, plugin_id: &str, name: &str) -> Result<Option<String>, String> {
+        self.ensure_unlocked()?;
+        Ok(self
+            .secrets
+            .get(plugin_id)
+            .and_then(|values| values.get(name))
+            .map(|value| value.to_string()))
+    }
+
+    /// 列出插件的密钥名称（不包含值）
+    pub fn list(&self, plugin_id: &str) -> Result<Vec<String>, String> {
+        self.ensure_unlocked()?;
+        let mut names: Vec<String> = self
+            .secrets
+            .get(plugin_id)
+            .map(|values| values.keys().cloned().collect())
+            .unwrap_or_default();
+        names.sort();
+        Ok(names)
+    }
+
+    /// 设置插件的密钥
+    pub fn set(&mut self, plugin_id: &str, name: &str, value: &str) -> Result<(), String> {
+        self.ensure_unlocked()?;
+        if name.is_empty() {
+            return Err("密钥名称不能为空".to_string());
+        }
+        self.secrets
+            .entry(plugin_id.to_string())
+            .or_default()
+            .insert(name.to_string(), Zeroizing::new(value.to_string()));
+        self.save()
+    }
+
+    /// 删除插件的密钥，返回密钥是否存在
+    pub fn delete(&mut self, plugin_id: &str, name: &str) -> Result<bool, String> {
+        self.ensure_unlocked()?;
+        let removed = match self.secrets.get_mut(plugin_id) {
+            Some(values) => {
+                let removed = values.remove(name).is_some();
+                if values.is_empty() {
+                    self.secrets.remove(plugin_id);
+                }
+                removed
+            }
+            None => false,
+        };
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// 读取主程序自身使用的密钥
+    pub fn get_host_secret(&self, name: &str) -> Result<Option<String>, String> {
+        self.ensure_unlocked()?;
+        Ok(self.host_secrets.get(name).map(|value| value.to_string()))
+    }
+
+    /// 设置主程序自身使用的密钥，值为空时删除
+    pub fn set_host_secret(&mut self, name: &str, value: &str) -> Result<(), String> {
+        self.ensure_unlocked()?;
+        if value.is_empty() {
+            self.host_secrets.remove(name);
+        } else {
+            self.host_secrets
+                .insert(name.to_string(), Zeroizing::new(value.to_string()));
+        }
+        self.save()
+    }
+
+    fn ensure_unlocked(&self) -> Result<(), String> {
+        if self.key.is_none() {
+            return Err("密钥库未解锁".to_string());
+        }
+        Ok(())
+    }
+
+    fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>, String> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key[..])
+            .map_err(|e| format!("派生密钥失败: {}", e))?;
+        Ok(key)
+    }
+
+    /// 加密并写入密钥库文件
+    fn save(&self) -> Result<(), String> {
+        let key = self.key.as_ref().ok_or("密钥库未解锁")?;
+
+        let plain = VaultContentRef {
+            plugins: self
+                .secrets
+                .iter()
+                .map(|(plugin_id, values)| (plugin_id, plain_secret_map(values)))
+                .collect(),
+            host: plain_secret_map(&self.host_secrets),
+        };
+        let plaintext = Zeroizing::new(
+            serde_json::to_vec(&plain).map_err(|e| format!("序列化密钥失败: {}", e))?,
+        );
+
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key[..]));
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_ref())
+            .map_err(|_| "加密密钥库失败".to_string())?;
+
+        let file = VaultFile {
+            version: VAULT_VERSION,
+            salt: BASE64.encode(&self.salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+        let content =
+            toml::to_string_pretty(&file).map_err(|e| format!("序列化密钥库失败: {}", e))?;
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建密钥库目录失败: {}", e))?;
+        }
+        write_private_file(&self.path, content.as_bytes())
+            .map_err(|e| format!("保存密钥库失败: {}", e))
+    }
+}
+
+/// 先写入同目录下仅当前用户可读写（0600）的临时文件，再重命名替换目标文件，
+/// 写入中断时不会留下损坏的密钥库
+fn write_private_file(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&temp_path)?;
+        // 临时文件可能是之前残留的，mode 只在创建时生效
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(content)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn into_secret_map(values: HashMap<String, String>) -> SecretMap {
+    values
+        .into_iter()
+        .map(|(name, value)| (name, Zeroizing::new(value)))
+        .collect()
+}
+
+fn plain_secret_map(values: &SecretMap) -> HashMap<&String, &str> {
+    values
+        .iter()
+        .map(|(name, value)| (name, value.as_str()))
+        .collect()
+}
@@ -0,0 +1,5 @@
+pub mod schema;
+pub mod store;
+
+pub use schema::{AppSettings, NetworkSettings, SignaturePolicy};
+pub use store::{get_app_config_store, AppConfigChange, AppConfigStore, APP_CONFIG_CHANGED_EVENT};
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+
+use crate::plugins::archive::{
+    DEFAULT_MAX_ENTRIES, DEFAULT_MAX_EXTRACTED_SIZE_MB, DEFAULT_MAX_FILE_SIZE_MB,
+};
+use crate::plugins::http::{
+    DEFAULT_CONNECT_TIMEOUT_SECS, DEFAULT_MAX_RETRIES, DEFAULT_READ_TIMEOUT_SECS,
+    DEFAULT_RETRY_BACKOFF_MS,
+};
+
+/// 应用设置（持久化到 ~/.chat_client/settings.toml）
+///
+/// 每个分组对应一个命名空间，配置项通过 `分组.字段` 形式的键访问，例如 `general.theme`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub general: GeneralSettings,
+    pub plugin: PluginSettings,
+    pub message: MessageSettings,
+    pub network: NetworkSettings,
+    pub advanced: AdvancedSettings,
+}
+
+/// 通用设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneralSettings {
+    pub theme: Theme,
+    pub language: Language,
+    pub auto_connect: bool,
+}
+
+/// 插件设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginSettings {
+    pub directory: String,
+    pub hot_reload: bool,
+    pub log_level: LogLevel,
+    /// 是否在独立进程中运行插件，插件崩溃不会影响主程序
+    pub isolation: bool,
+    /// 是否允许加载未导出 ABI 版本信息的旧插件，这类插件的结构布局无法确认，可能导致崩溃
+    pub allow_legacy_abi: bool,
+    /// 插件仓库和插件二进制的签名校验策略
+    pub signature_policy: SignaturePolicy,
+    /// 解压插件仓库或插件包时的总大小上限（MB）
+    pub archive_max_extracted_size_mb: u64,
+    /// 解压时单个文件的大小上限（MB）
+    pub archive_max_file_size_mb: u64,
+    /// 归档中的条目数量上限
+    pub archive_max_entries: u32,
+}
+
+/// 消息设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessageSettings {
+    pub retention_days: u32,
+    pub max_display_messages: u32,
+    pub auto_scroll_to_latest: bool,
+    pub enable_markdown: bool,
+    pub render_input_message_as_markdown: bool,
+    pub clear_message_input_on_send: bool,
+}
+
+/// 网络设置，用于插件仓库和插件文件的下载
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// 连接超时（秒）
+    pub connect_timeout_secs: u64,
+    /// 读取超时（秒），两次收到数据之间的最长等待时间
+    pub read_timeout_secs: u64,
+    /// 代理地址，例如 `http://proxy.example.com:8080`，为空时使用系统代理环境变量
+    pub proxy_url: String,
+    /// 代理认证用户名，为空时不认证；密码保存在密钥库中
+    pub proxy_username: String,
+    /// 额外信任的根证书文件（PEM 格式，可包含多个证书）
+    pub ca_certificates: Vec<String>,
+    /// 请求失败后的最大重试次数
+    pub max_retries: u32,
+    /// 首次重试前的等待时间（毫秒），之后每次翻倍
+    pub retry_backoff_ms: u64,
+}
+
+/// 高级设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdvancedSettings {
+    pub developer_mode: bool,
+    pub hardware_acceleration: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+    #[serde(rename = "en-US")]
+    EnUs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+}
+
+/// 签名校验策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignaturePolicy {
+    /// 缺少签名或签名无效时拒绝安装
+    Require,
+    /// 缺少签名或签名无效时记录警告后继续安装
+    #[default]
+    Warn,
+    /// 不校验签名
+    Off,
+}
+
+impl Default for GeneralSettings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            language: Language::default(),
+            auto_connect: true,
+        }
+    }
+}
+
+impl Default for PluginSettings {
+    fn default() -> Self {
+        Self {
+            directory: "./plugins".to_string(),
+            hot_reload: false,
+            log_level: LogLevel::default(),
+            isolation: false,
+            allow_legacy_abi: false,
+            signature_policy: SignaturePolicy::default(),
+            archive_max_extracted_size_mb: DEFAULT_MAX_EXTRACTED_SIZE_MB,
+            archive_max_file_size_mb: DEFAULT_MAX_FILE_SIZE_MB,
+            archive_max_entries: DEFAULT_MAX_ENTRIES,
+        }
+    }
+}
+
+impl Default for MessageSettings {
+    fn default() -> Self {
+        Self {
+            retention_days: 30,
+            max_display_messages: 200,
+            auto_scroll_to_latest: true,
+            enable_markdown: true,
+            render_input_message_as_markdown: false,
+            clear_message_input_on_send: true,
+        }
+    }
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: DEFAULT_CONNECT_TIMEOUT_SECS,
+            read_timeout_secs: DEFAULT_READ_TIMEOUT_SECS,
+            proxy_url: String::new(),
+            proxy_username: String::new(),
+            ca_certificates: Vec::new(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_backoff_ms: DEFAULT_RETRY_BACKOFF_MS,
+        }
+    }
+}
+
+impl Default for AdvancedSettings {
+    fn default() -> Self {
+        Self {
+            developer_mode: false,
+            hardware_acceleration: true,
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use plugin_interfaces::{log_info, log_warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::plugins::directories::get_app_settings_file;
+use crate::settings::schema::AppSettings;
+
+/// 配置变更事件名称
+pub const APP_CONFIG_CHANGED_EVENT: &str = "app-config-changed";
+
+/// 插件可以读取的配置分组，`network` 等包含代理地址和凭据的分组不对插件开放
+pub const PLUGIN_READABLE_NAMESPACES: &[&str] = &["general", "plugin", "message", "advanced"];
+
+// 全局应用配置存储
+static APP_CONFIG_STORE: OnceLock<Mutex<AppConfigStore>> = OnceLock::new();
+
+/// 获取全局应用配置存储（首次访问时从磁盘加载）
+pub fn get_app_config_store() -> &'static Mutex<AppConfigStore> {
+    APP_CONFIG_STORE.get_or_init(|| Mutex::new(AppConfigStore::load(get_app_settings_file())))
+}
+
+/// 单个配置项的变更，作为事件载荷发送到前端
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppConfigChange {
+    pub key: String,
+    pub value: Value,
+}
+
+/// 应用配置存储
+#[derive(Debug)]
+pub struct AppConfigStore {
+    path: PathBuf,
+    settings: AppSettings,
+}
+
+impl AppConfigStore {
+    /// 从 TOML 文件加载配置，文件不存在或解析失败时使用默认配置
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let settings = match std::fs::read_to_string(&path) {
+            Ok(content) => match toml::from_str::<AppSettings>(&content) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    log_warn!("解析配置文件 {:?} 失败，使用默认配置: {}", path, e);
+                    AppSettings::default()
+                }
+            },
+            Err(_) => AppSettings::default(),
+        };
+
+        Self { path, settings }
+    }
+
+    /// 获取完整配置
+    pub fn settings(&self) -> &AppSettings {
+        &self.settings
+    }
+
+    /// 按命名空间键获取配置值，例如 `general.theme`
+    pub fn get(&self, key: &str) -> Result<Value, String> {
+        let root = serde_json::to_value(&self.settings).map_err(|e| e.to_string())?;
+        key.split('.')
+            .try_fold(&root, |node, segment| node.get(segment))
+            .cloned()
+            .ok_or_else(|| format!("未知的配置项: {}", key))
+    }
+
+    /// 以字符串形式获取配置值
+    ///
+    /// 字符串值原样返回，其余类型返回其 JSON 表示
+    pub fn get_string(&self, key: &str) -> Option<String> {
+        match self.get(key).ok()? {
+            Value::String(value) => Some(value),
+            value => Some(value.to_string()),
+        }
+    }
+
+    /// 供插件读取配置：只允许 [`PLUGIN_READABLE_NAMESPACES`] 中的分组
+    pub fn get_string_for_plugin(&self, key: &str) -> Option<String> {
+        let namespace = key.split('.').next().unwrap_or_default();
+        if !PLUGIN_READABLE_NAMESPACES.contains(&namespace) {
+            log_warn!("拒绝插件读取配置项: {}", key);
+            return None;
+        }
+        self.get_string(key)
+    }
+
+    /// 设置单个配置项并持久化
+    pub fn set(&mut self, key: &str, value: Value) -> Result<AppConfigChange, String> {
+        let mut changes = self.update(HashMap::from([(key.to_string(), value)]))?;
+        Ok(changes.remove(0))
+    }
+
+    /// 批量设置配置项并持久化，任一配置项无效时不做任何修改
+    pub fn update(
+        &mut self,
+        values: HashMap<String, Value>,
+    ) -> Result<Vec<AppConfigChange>, String> {
+        let mut root = serde_json::to_value(&self.settings).map_err(|e| e.to_string())?;
+
+        for (key, value) in &values {
+            let slot = key
+                .split('.')
+                .try_fold(&mut root, |node, segment| node.get_mut(segment))
+                .filter(|slot| !slot.is_object())
+                .ok_or_else(|| format!("未知的配置项: {}", key))?;
+            *slot = value.clone();
+        }
+
+        let settings: AppSettings =
+            serde_json::from_value(root).map_err(|e| format!("配置值无效: {}", e))?;
+        self.save(&settings)?;
+        self.settings = settings;
+
+        let mut changes = Vec::new();
+        for key in values.keys() {
+            changes.push(AppConfigChange {
+                key: key.clone(),
+                value: self.get(key)?,
+            });
+        }
+        Ok(changes)
+    }
+
+    /// 将配置写入磁盘
+    fn save(&self, settings: &AppSettings) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {}", e))?;
+        }
+
+        let content =
+            toml::to_string_pretty(settings).map_err(|e| format!("序列化配置失败: {}", e))?;
+        write_file_atomic(&self.path, content.as_bytes())
+            .map_err(|e| format!("保存配置文件失败: {}", e))?;
+
+        log_info!("应用配置已保存: {:?}", self.path);
+        Ok(())
+    }
+}
+
+/// 先写入同目录下的临时文件再重命名，写入中途失败或崩溃不会留下截断的配置文件
+pub fn write_file_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
@@ -0,0 +1,97 @@
+//! 验证应用配置的批量更新：写入磁盘后可重新加载，无效的配置项不修改内存和磁盘中的配置
+
+use chat_client_lib::settings::schema::Theme;
+use chat_client_lib::settings::store::AppConfigStore;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// 每个测试使用独立的临时配置文件
+fn settings_path(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!(
+        "chat-client-settings-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&root);
+    root.join("settings.toml")
+}
+
+fn values(entries: &[(&str, Value)]) -> HashMap<String, Value> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.clone()))
+        .collect()
+}
+
+#[test]
+fn update_persists_and_reloads() {
+    let path = settings_path("round-trip");
+    let mut store = AppConfigStore::load(&path);
+
+    let mut changes = store
+        .update(values(&[
+            ("general.theme", json!("dark")),
+            ("message.retention_days", json!(7)),
+        ]))
+        .unwrap();
+    changes.sort_by(|a, b| a.key.cmp(&b.key));
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].key, "general.theme");
+    assert_eq!(changes[0].value, json!("dark"));
+    assert_eq!(changes[1].key, "message.retention_days");
+    assert_eq!(changes[1].value, json!(7));
+
+    let reloaded = AppConfigStore::load(&path);
+    assert_eq!(reloaded.settings().general.theme, Theme::Dark);
+    assert_eq!(reloaded.settings().message.retention_days, 7);
+    assert!(
+        !path.with_file_name("settings.toml.tmp").exists(),
+        "临时文件未被清理"
+    );
+    let _ = fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn update_rejects_unknown_key() {
+    let path = settings_path("unknown-key");
+    let mut store = AppConfigStore::load(&path);
+
+    for key in ["general.missing", "missing.theme", "general"] {
+        let error = store
+            .update(values(&[
+                ("general.theme", json!("dark")),
+                (key, json!(true)),
+            ]))
+            .unwrap_err();
+        assert!(error.contains("未知的配置项"), "{}", error);
+    }
+
+    assert_eq!(store.settings().general.theme, Theme::Light);
+    assert!(!path.exists(), "无效的更新不应写入磁盘");
+    let _ = fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn update_rejects_type_mismatch() {
+    let path = settings_path("type-mismatch");
+    let mut store = AppConfigStore::load(&path);
+    store.set("general.theme", json!("dark")).unwrap();
+    let saved = fs::read_to_string(&path).unwrap();
+
+    for (key, value) in [
+        ("message.retention_days", json!("seven")),
+        ("general.auto_connect", json!(1)),
+        ("general.theme", json!("purple")),
+    ] {
+        let error = store
+            .update(values(&[("general.theme", json!("auto")), (key, value)]))
+            .unwrap_err();
+        assert!(error.contains("配置值无效"), "{}", error);
+    }
+
+    assert_eq!(store.settings().general.theme, Theme::Dark);
+    assert_eq!(fs::read_to_string(&path).unwrap(), saved);
+    let _ = fs::remove_dir_all(path.parent().unwrap());
+}
@@ -0,0 +1,223 @@
+//! 验证受限解压拒绝恶意归档，且失败时保留目标目录原有内容
+
+use chat_client_lib::plugins::archive::{
+    extract_tar_gz, extract_zip, extract_zip_verified, ExtractLimits,
+};
+use flate2::{write::GzEncoder, Compression};
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+const KB: u64 = 1024;
+
+fn small_limits() -> ExtractLimits {
+    ExtractLimits {
+        max_total_size: 64 * KB,
+        max_file_size: 32 * KB,
+        max_entries: 8,
+    }
+}
+
+/// 创建包含 `existing.txt` 的目标目录，模拟已有的快照
+fn target_dir(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!(
+        "chat-client-archive-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&root);
+    let target = root.join("snapshot");
+    fs::create_dir_all(&target).unwrap();
+    fs::write(target.join("existing.txt"), "previous").unwrap();
+    target
+}
+
+/// 目标目录保持原样，且没有残留的临时解压目录
+fn assert_untouched(target: &Path) {
+    assert_eq!(
+        fs::read_to_string(target.join("existing.txt")).unwrap(),
+        "previous"
+    );
+    let leftovers = fs::read_dir(target.parent().unwrap())
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name() != "snapshot")
+        .count();
+    assert_eq!(leftovers, 0, "临时解压目录未被清理");
+    let _ = fs::remove_dir_all(target.parent().unwrap());
+}
+
+fn zip_archive(files: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in files {
+        writer
+            .start_file(*name, SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(data).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+/// 按原始字节写入条目名称，绕过 tar 构建器对路径的检查
+fn tar_gz_archive(entries: &[(&[u8], tar::EntryType, Vec<u8>)]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (name, entry_type, data) in entries {
+        let mut header = tar::Header::new_old();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name);
+        header.set_entry_type(*entry_type);
+        header.set_mode(0o644);
+        header.set_size(data.len() as u64);
+        header.set_cksum();
+        builder.append(&header, &data[..]).unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+#[test]
+fn extracts_zip_and_strips_root() {
+    let target = target_dir("zip-ok");
+    let data = zip_archive(&[
+        ("repo-main/plugin/config.toml", b"id = \"demo\"".to_vec()),
+        ("repo-main/README.md", b"demo".to_vec()),
+    ]);
+
+    extract_zip(Cursor::new(data), &target, true, &small_limits()).unwrap();
+
+    assert!(target.join("plugin/config.toml").is_file());
+    assert!(target.join("README.md").is_file());
+    assert!(!target.join("existing.txt").exists());
+    let _ = fs::remove_dir_all(target.parent().unwrap());
+}
+
+#[test]
+fn rejects_zip_bomb() {
+    let target = target_dir("zip-bomb");
+    // 高度可压缩的数据，压缩后远小于单个文件大小上限
+    let data = zip_archive(&[("bomb.bin", vec![0u8; (256 * KB) as usize])]);
+    assert!((data.len() as u64) < small_limits().max_file_size);
+
+    let error = extract_zip(Cursor::new(data), &target, false, &small_limits()).unwrap_err();
+    assert!(error.contains("超过单个文件大小上限"), "{}", error);
+    assert_untouched(&target);
+}
+
+#[test]
+fn rejects_archive_exceeding_total_size() {
+    let target = target_dir("zip-total");
+    let chunk = vec![1u8; (30 * KB) as usize];
+    let data = zip_archive(&[
+        ("a.bin", chunk.clone()),
+        ("b.bin", chunk.clone()),
+        ("c.bin", chunk),
+    ]);
+
+    let error = extract_zip(Cursor::new(data), &target, false, &small_limits()).unwrap_err();
+    assert!(error.contains("总大小上限"), "{}", error);
+    assert_untouched(&target);
+}
+
+#[test]
+fn rejects_too_many_entries() {
+    let target = target_dir("zip-entries");
+    let names: Vec<String> = (0..20).map(|i| format!("file-{}.txt", i)).collect();
+    let files: Vec<(&str, Vec<u8>)> = names
+        .iter()
+        .map(|name| (name.as_str(), Vec::new()))
+        .collect();
+
+    let error = extract_zip(
+        Cursor::new(zip_archive(&files)),
+        &target,
+        false,
+        &small_limits(),
+    )
+    .unwrap_err();
+    assert!(error.contains("超过上限"), "{}", error);
+    assert_untouched(&target);
+
+    let target = target_dir("tar-entries");
+    let entries: Vec<(&[u8], tar::EntryType, Vec<u8>)> = names
+        .iter()
+        .map(|name| (name.as_bytes(), tar::EntryType::Regular, Vec::new()))
+        .collect();
+    let error = extract_tar_gz(
+        Cursor::new(tar_gz_archive(&entries)),
+        &target,
+        false,
+        &small_limits(),
+    )
+    .unwrap_err();
+    assert!(error.contains("超过上限"), "{}", error);
+    assert_untouched(&target);
+}
+
+#[test]
+fn rejects_zip_symlink() {
+    let target = target_dir("zip-symlink");
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    writer
+        .add_symlink("link", "/etc/passwd", SimpleFileOptions::default())
+        .unwrap();
+    let data = writer.finish().unwrap().into_inner();
+
+    let error = extract_zip(Cursor::new(data), &target, false, &small_limits()).unwrap_err();
+    assert!(error.contains("不是普通文件或目录"), "{}", error);
+    assert_untouched(&target);
+}
+
+#[test]
+fn rejects_tar_symlink_and_device_entries() {
+    for (name, entry_type) in [
+        ("tar-symlink", tar::EntryType::Symlink),
+        ("tar-hardlink", tar::EntryType::Link),
+        ("tar-char-device", tar::EntryType::Char),
+        ("tar-block-device", tar::EntryType::Block),
+        ("tar-fifo", tar::EntryType::Fifo),
+    ] {
+        let target = target_dir(name);
+        let data = tar_gz_archive(&[(b"special", entry_type, Vec::new())]);
+
+        let error =
+            extract_tar_gz(Cursor::new(data), &target, false, &small_limits()).expect_err(name);
+        assert!(error.contains("不是普通文件或目录"), "{}", error);
+        assert_untouched(&target);
+    }
+}
+
+#[test]
+fn rejects_parent_directory_paths() {
+    let target = target_dir("zip-traversal");
+    let data = zip_archive(&[("../escape.txt", b"escape".to_vec())]);
+    let error = extract_zip(Cursor::new(data), &target, false, &small_limits()).unwrap_err();
+    assert!(error.contains("路径无效"), "{}", error);
+    assert!(!target.parent().unwrap().join("escape.txt").exists());
+    assert_untouched(&target);
+
+    let target = target_dir("tar-traversal");
+    let data = tar_gz_archive(&[(
+        b"../escape.txt",
+        tar::EntryType::Regular,
+        b"escape".to_vec(),
+    )]);
+    let error = extract_tar_gz(Cursor::new(data), &target, false, &small_limits()).unwrap_err();
+    assert!(error.contains("路径无效"), "{}", error);
+    assert!(!target.parent().unwrap().join("escape.txt").exists());
+    assert_untouched(&target);
+}
+
+#[test]
+fn keeps_previous_snapshot_when_verification_fails() {
+    let target = target_dir("zip-verify");
+    let data = zip_archive(&[("repo-main/config.toml", b"id = \"demo\"".to_vec())]);
+
+    let error = extract_zip_verified(Cursor::new(data), &target, true, &small_limits(), |dir| {
+        assert!(dir.join("config.toml").is_file());
+        Err("签名校验失败".to_string())
+    })
+    .unwrap_err();
+
+    assert_eq!(error, "签名校验失败");
+    assert_untouched(&target);
+}
@@ -0,0 +1,219 @@
+//! 集成测试共用的插件运行环境：独立的用户目录、构建好的示例插件和插件宿主程序
+
+#![allow(dead_code)]
+
+use chat_client_lib::plugins::checksum::ChecksumAlgorithm;
+use chat_client_lib::plugins::compatibility::CLIENT_TARGET;
+use chat_client_lib::plugins::loader::library_filename;
+use chat_client_lib::plugins::sources::{RepositorySource, RepositorySources};
+use chat_client_lib::plugins::PluginManager;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex, OnceLock};
+
+pub const EXAMPLE_PLUGIN_ID: &str = "example_plugin";
+
+/// 将 HOME 和工作目录指向测试专用的临时目录，避免读写真实的 ~/.chat_client
+///
+/// 必须在访问任何配置、设置或插件目录之前调用
+pub fn isolated_home() -> &'static Path {
+    static HOME: OnceLock<PathBuf> = OnceLock::new();
+    HOME.get_or_init(|| {
+        let home = std::env::temp_dir().join(format!("chat-client-home-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        fs::create_dir_all(&home).unwrap();
+        std::env::set_var("HOME", &home);
+        std::env::set_var("USERPROFILE", &home);
+        std::env::set_current_dir(&home).unwrap();
+        home
+    })
+}
+
+/// 构建示例插件和插件宿主程序，返回构建产物所在目录
+///
+/// 插件宿主程序被复制到测试程序所在目录，隔离模式按主程序目录查找宿主程序
+pub fn plugin_artifacts() -> &'static Path {
+    static ARTIFACTS: OnceLock<PathBuf> = OnceLock::new();
+    ARTIFACTS.get_or_init(|| {
+        let test_exe = std::env::current_exe().unwrap();
+        let deps_dir = test_exe.parent().unwrap();
+        let profile_dir = deps_dir.parent().unwrap().to_path_buf();
+        let profile = match profile_dir.file_name().and_then(|name| name.to_str()) {
+            Some("debug") | None => "dev".to_string(),
+            Some(name) => name.to_string(),
+        };
+
+        let status = Command::new(env!("CARGO"))
+            .args(["build", "-p", "example-plugin", "-p", "plugin-host"])
+            .args(["--profile", &profile])
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .status()
+            .expect("无法运行 cargo build");
+        assert!(status.success(), "构建示例插件和插件宿主程序失败");
+
+        let host_binary = format!("plugin-host{}", std::env::consts::EXE_SUFFIX);
+        fs::copy(profile_dir.join(&host_binary), deps_dir.join(&host_binary)).unwrap();
+        profile_dir
+    })
+}
+
+/// 将示例插件安装到测试用户目录的 installed_plugins 中，返回插件目录
+///
+/// 只安装一次，避免覆盖其他测试已加载的动态库
+pub fn install_example_plugin() -> &'static Path {
+    static PLUGIN_DIR: OnceLock<PathBuf> = OnceLock::new();
+    PLUGIN_DIR.get_or_init(|| {
+        let home = isolated_home();
+        let artifacts = plugin_artifacts();
+        let plugin_dir = home
+            .join(".chat_client")
+            .join("installed_plugins")
+            .join(EXAMPLE_PLUGIN_ID);
+        fs::create_dir_all(&plugin_dir).unwrap();
+
+        let source_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/plugins/example");
+        fs::copy(
+            source_dir.join("config.toml"),
+            plugin_dir.join("config.toml"),
+        )
+        .unwrap();
+        let library = library_filename("example");
+        fs::copy(artifacts.join(&library), plugin_dir.join(&library)).unwrap();
+        plugin_dir
+    })
+}
+
+/// 把示例插件复制到 `target_dir`，使用指定的插件 ID 和版本号，用于构造其他已安装插件或备份版本
+pub fn copy_example_plugin(target_dir: &Path, plugin_id: &str, version: &str) {
+    let example_dir = install_example_plugin();
+    fs::create_dir_all(target_dir).unwrap();
+    let config = fs::read_to_string(example_dir.join("config.toml"))
+        .unwrap()
+        .replace(
+            &format!("id = \"{}\"", EXAMPLE_PLUGIN_ID),
+            &format!("id = \"{}\"", plugin_id),
+        )
+        .replace("version = \"1.0.0\"", &format!("version = \"{}\"", version));
+    fs::write(target_dir.join("config.toml"), config).unwrap();
+    let library = library_filename("example");
+    fs::copy(example_dir.join(&library), target_dir.join(&library)).unwrap();
+}
+
+/// 测试进程共用的插件管理器，注册为全局管理器以支持插件间调用
+pub fn plugin_manager() -> &'static Arc<PluginManager> {
+    static MANAGER: OnceLock<Arc<PluginManager>> = OnceLock::new();
+    MANAGER.get_or_init(|| {
+        isolated_home();
+        let manager = Arc::new(PluginManager::new(None));
+        PluginManager::register_global(&manager);
+        manager
+    })
+}
+
+/// 计算数据的 `sha256:<hex>` 校验值
+pub fn sha256_checksum(data: &[u8]) -> String {
+    format!("sha256:{}", ChecksumAlgorithm::Sha256.digest_hex(data))
+}
+
+/// 测试用户目录中的本地 JSON 注册表，插件的二进制文件为示例插件的动态库
+pub struct LocalRegistry {
+    name: String,
+    dir: PathBuf,
+    plugins: Vec<Value>,
+}
+
+impl LocalRegistry {
+    /// 创建空的注册表目录，`name` 同时用作仓库源名称
+    pub fn new(name: &str) -> Self {
+        let dir = isolated_home().join("registries").join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self {
+            name: name.to_string(),
+            dir,
+            plugins: Vec::new(),
+        }
+    }
+
+    /// 写入插件的配置文件和动态库并添加索引条目，返回条目供测试修改校验值或签名
+    pub fn add_plugin(
+        &mut self,
+        plugin_id: &str,
+        version: &str,
+        dependencies: &[&str],
+    ) -> &mut Value {
+        let plugin_dir = self.dir.join(plugin_id);
+        fs::create_dir_all(&plugin_dir).unwrap();
+
+        let config = format!(
+            "[plugin]\nid = \"{id}\"\nname = \"{id}\"\nversion = \"{version}\"\ndescription = \"\"\nauthor = \"\"\nlibrary = \"{id}-{version}\"\ndependencies = {dependencies:?}\n",
+            id = plugin_id,
+            version = version,
+            dependencies = dependencies,
+        );
+        fs::write(plugin_dir.join("config.toml"), &config).unwrap();
+        let library = library_filename("example");
+        let library_data = fs::read(install_example_plugin().join(&library)).unwrap();
+        fs::write(plugin_dir.join(&library), &library_data).unwrap();
+
+        self.plugins.push(json!({
+            "id": plugin_id,
+            "name": plugin_id,
+            "version": version,
+            "dependencies": dependencies,
+            "config_url": format!("{}/config.toml", plugin_id),
+            "config_checksum": sha256_checksum(config.as_bytes()),
+            "artifacts": {
+                "targets": {
+                    CLIENT_TARGET: {
+                        "checksum": sha256_checksum(&library_data),
+                        "download_url": format!("{}/{}", plugin_id, library),
+                    }
+                }
+            }
+        }));
+        self.plugins.last_mut().unwrap()
+    }
+
+    /// 插件在当前平台的下载项
+    pub fn artifact(entry: &mut Value) -> &mut Value {
+        &mut entry["artifacts"]["targets"][CLIENT_TARGET]
+    }
+
+    /// 插件动态库在注册表中的路径
+    pub fn library_path(&self, plugin_id: &str) -> PathBuf {
+        self.dir.join(plugin_id).join(library_filename("example"))
+    }
+
+    /// 索引文件路径
+    pub fn index_path(&self) -> PathBuf {
+        self.dir.join("index.json")
+    }
+
+    /// 写入索引文件并注册为仓库源
+    pub fn publish(&self) {
+        let index_path = self.index_path();
+        let index = json!({ "version": 1, "plugins": self.plugins });
+        fs::write(&index_path, serde_json::to_vec_pretty(&index).unwrap()).unwrap();
+
+        // 并行运行的测试共用同一个仓库源配置文件
+        static SOURCES_LOCK: Mutex<()> = Mutex::new(());
+        let _guard = SOURCES_LOCK.lock().unwrap();
+        let mut sources = RepositorySources::load();
+        sources
+            .repositories
+            .retain(|source| source.name != self.name);
+        sources.repositories.push(RepositorySource {
+            name: self.name.clone(),
+            url: index_path.to_string_lossy().to_string(),
+            branch: None,
+            tag: None,
+            commit: None,
+            priority: 0,
+            enabled: true,
+        });
+        sources.save().unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+//! 验证已安装插件锁文件：原子写入，以及动态库被替换或修改后重新校验并拒绝加载
+
+mod common;
+
+use chat_client_lib::plugins::checksum::ChecksumAlgorithm;
+use chat_client_lib::plugins::directories::get_installed_lock_file;
+use chat_client_lib::plugins::lockfile::{
+    verify_installed_library, InstalledLock, InstalledPluginEntry,
+};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const LIBRARY: &[u8] = b"library contents v1";
+/// 与 [`LIBRARY`] 长度相同的篡改内容
+const TAMPERED: &[u8] = b"library contents v2";
+
+fn entry(checksum: &str) -> InstalledPluginEntry {
+    InstalledPluginEntry::new(
+        "lock_test",
+        "1.0.0",
+        "https://example.com/repo",
+        "https://example.com/liblock_test.so",
+        checksum,
+        "liblock_test.so",
+    )
+}
+
+fn sha256(data: &[u8]) -> String {
+    format!("sha256:{}", ChecksumAlgorithm::Sha256.digest_hex(data))
+}
+
+/// 在独立目录中写入动态库，返回路径
+fn write_library(name: &str) -> PathBuf {
+    let dir = common::isolated_home().join("lockfile-libraries");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, LIBRARY).unwrap();
+    path
+}
+
+/// 修改文件后恢复原来的修改时间，只留下 inode 或 ctime 的变化
+fn restore_modified(path: &Path, modified: std::time::SystemTime) {
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+}
+
+#[test]
+fn save_replaces_lock_file_atomically() {
+    common::isolated_home();
+    let mut lock = InstalledLock::default();
+    lock.record_install(entry(&sha256(LIBRARY)));
+    lock.save().unwrap();
+    lock.record_install(entry(&sha256(TAMPERED)));
+    lock.save().unwrap();
+
+    let lock_file = get_installed_lock_file();
+    let mut temp_name = lock_file.file_name().unwrap().to_os_string();
+    temp_name.push(".tmp");
+    assert!(!lock_file.with_file_name(temp_name).exists());
+
+    let loaded = InstalledLock::load().unwrap();
+    assert_eq!(loaded.get("lock_test").unwrap().checksum, sha256(TAMPERED));
+    assert_eq!(loaded.previous.len(), 1);
+    assert_eq!(loaded.previous[0].checksum, sha256(LIBRARY));
+}
+
+#[test]
+fn checksum_mismatch_is_refused() {
+    let path = write_library("mismatch.so");
+    let entry = entry(&sha256(TAMPERED));
+
+    let error = verify_installed_library(&entry, &path).unwrap_err();
+    assert!(error.contains("篡改"), "{}", error);
+    // 缓存的失败结果同样拒绝加载
+    let error = verify_installed_library(&entry, &path).unwrap_err();
+    assert!(error.contains("篡改"), "{}", error);
+}
+
+#[test]
+fn replaced_library_is_verified_again() {
+    let path = write_library("replaced.so");
+    let entry = entry(&sha256(LIBRARY));
+    verify_installed_library(&entry, &path).unwrap();
+
+    // 用长度和修改时间都相同的文件替换，只有 inode 不同
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+    let replacement = path.with_extension("new");
+    fs::write(&replacement, TAMPERED).unwrap();
+    restore_modified(&replacement, modified);
+    fs::rename(&replacement, &path).unwrap();
+
+    assert!(verify_installed_library(&entry, &path).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn modified_in_place_library_is_verified_again() {
+    let path = write_library("in_place.so");
+    let entry = entry(&sha256(LIBRARY));
+    verify_installed_library(&entry, &path).unwrap();
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+
+    // 等待超过文件系统时间戳的粒度，保证 ctime 发生变化
+    std::thread::sleep(Duration::from_millis(50));
+    let mut file = File::options().write(true).open(&path).unwrap();
+    file.write_all(TAMPERED).unwrap();
+    drop(file);
+    restore_modified(&path, modified);
+
+    assert!(verify_installed_library(&entry, &path).is_err());
+}
@@ -0,0 +1,124 @@
+//! 验证插件下载校验：支持的摘要算法、校验失败和无效校验值，以及校验失败时不写入任何文件
+
+mod common;
+
+use chat_client_lib::plugins::checksum::{parse_checksum, verify_checksum, ChecksumAlgorithm};
+use chat_client_lib::plugins::directories::{
+    get_plugin_staging_directory, get_root_plugin_installed_directory,
+};
+use chat_client_lib::plugins::download::DownloadTask;
+use chat_client_lib::plugins::PluginRepository;
+use common::LocalRegistry;
+use serde_json::json;
+use std::fs;
+
+const DATA: &[u8] = b"plugin binary";
+
+fn checksum(algorithm: ChecksumAlgorithm) -> String {
+    format!("{}:{}", algorithm.as_str(), algorithm.digest_hex(DATA))
+}
+
+#[test]
+fn sha256_and_sha512_checksums_match() {
+    for algorithm in [ChecksumAlgorithm::Sha256, ChecksumAlgorithm::Sha512] {
+        let checksum = checksum(algorithm);
+        verify_checksum(DATA, &checksum).unwrap();
+        // 算法前缀和摘要不区分大小写
+        verify_checksum(DATA, &checksum.to_ascii_uppercase()).unwrap();
+    }
+
+    // 不带前缀时按 SHA-256 处理
+    let digest = ChecksumAlgorithm::Sha256.digest_hex(DATA);
+    assert_eq!(
+        parse_checksum(&digest).unwrap(),
+        (ChecksumAlgorithm::Sha256, digest.clone())
+    );
+    verify_checksum(DATA, &digest).unwrap();
+}
+
+#[test]
+fn mismatched_data_is_rejected() {
+    for algorithm in [ChecksumAlgorithm::Sha256, ChecksumAlgorithm::Sha512] {
+        let error = verify_checksum(b"tampered binary", &checksum(algorithm)).unwrap_err();
+        assert!(
+            error.contains(&format!("{} 校验失败", algorithm.as_str())),
+            "{}",
+            error
+        );
+    }
+}
+
+#[test]
+fn empty_checksum_is_rejected() {
+    for checksum in ["", "   "] {
+        let error = verify_checksum(DATA, checksum).unwrap_err();
+        assert!(error.contains("未声明校验值"), "{}", error);
+    }
+}
+
+#[test]
+fn unknown_algorithm_prefix_is_rejected() {
+    let error = verify_checksum(DATA, "md5:0123456789abcdef0123456789abcdef").unwrap_err();
+    assert!(error.contains("不支持的校验算法: md5"), "{}", error);
+}
+
+#[test]
+fn malformed_digest_is_rejected() {
+    let sha256 = ChecksumAlgorithm::Sha256.digest_hex(DATA);
+    for checksum in [
+        format!("sha512:{}", sha256),
+        format!("sha256:{}", &sha256[1..]),
+        format!("sha256:{}zz", &sha256[2..]),
+    ] {
+        let error = verify_checksum(DATA, &checksum).unwrap_err();
+        assert!(error.contains("无效的"), "{}", error);
+    }
+}
+
+#[tokio::test]
+async fn mismatched_download_is_rejected_before_writing_to_disk() {
+    let plugin_id = "checksum_mismatch";
+    let mut registry = LocalRegistry::new("checksum");
+    let entry = registry.add_plugin(plugin_id, "1.0.0", &[]);
+    LocalRegistry::artifact(entry)["checksum"] = json!(checksum(ChecksumAlgorithm::Sha256));
+    registry.publish();
+
+    let task = DownloadTask::new(None, None).unwrap();
+    let result = PluginRepository::new()
+        .download_plugin(plugin_id, &task)
+        .await;
+    assert!(!result.success);
+    assert!(
+        result.message.contains("sha256 校验失败"),
+        "{}",
+        result.message
+    );
+
+    assert!(!get_root_plugin_installed_directory()
+        .join(plugin_id)
+        .exists());
+    let staged = fs::read_dir(get_plugin_staging_directory())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .any(|entry| entry.file_name().to_string_lossy().starts_with(plugin_id));
+    assert!(!staged, "校验失败的插件不应写入暂存目录");
+}
+
+#[tokio::test]
+async fn matching_download_is_installed() {
+    let plugin_id = "checksum_match";
+    let mut registry = LocalRegistry::new("checksum_valid");
+    registry.add_plugin(plugin_id, "1.0.0", &[]);
+    registry.publish();
+
+    let task = DownloadTask::new(None, None).unwrap();
+    let result = PluginRepository::new()
+        .download_plugin(plugin_id, &task)
+        .await;
+    assert!(result.success, "{}", result.message);
+    assert!(get_root_plugin_installed_directory()
+        .join(plugin_id)
+        .join("config.toml")
+        .is_file());
+}
@@ -0,0 +1,66 @@
+//! 验证安装插件时一并安装依赖：插件安装失败时删除本次新安装的依赖，已安装的依赖保持不变
+
+mod common;
+
+use chat_client_lib::plugins::directories::get_root_plugin_installed_directory;
+use chat_client_lib::plugins::download::DownloadTask;
+use chat_client_lib::plugins::PluginRepository;
+use common::{copy_example_plugin, LocalRegistry};
+use serde_json::json;
+
+fn is_installed(plugin_id: &str) -> bool {
+    get_root_plugin_installed_directory()
+        .join(plugin_id)
+        .join("config.toml")
+        .is_file()
+}
+
+#[tokio::test]
+async fn dependencies_are_installed_before_the_plugin() {
+    let mut registry = LocalRegistry::new("dependencies");
+    registry.add_plugin("with_dependency", "1.0.0", &["fresh_dependency >= 1.0"]);
+    registry.add_plugin("fresh_dependency", "1.2.0", &[]);
+    registry.publish();
+
+    let task = DownloadTask::new(None, None).unwrap();
+    let result = PluginRepository::new()
+        .download_plugin("with_dependency", &task)
+        .await;
+    assert!(result.success, "{}", result.message);
+    assert!(
+        result.message.contains("fresh_dependency"),
+        "{}",
+        result.message
+    );
+    assert!(is_installed("with_dependency"));
+    assert!(is_installed("fresh_dependency"));
+}
+
+#[tokio::test]
+async fn failed_install_removes_newly_installed_dependencies() {
+    common::isolated_home();
+    let existing_dir = get_root_plugin_installed_directory().join("existing_dependency");
+    copy_example_plugin(&existing_dir, "existing_dependency", "1.0.0");
+
+    let mut registry = LocalRegistry::new("rollback_dependencies");
+    let entry = registry.add_plugin(
+        "broken_plugin",
+        "1.0.0",
+        &["new_dependency", "existing_dependency >= 1.0"],
+    );
+    LocalRegistry::artifact(entry)["checksum"] = json!(format!("sha256:{}", "0".repeat(64)));
+    registry.add_plugin("new_dependency", "1.0.0", &[]);
+    registry.add_plugin("existing_dependency", "2.0.0", &[]);
+    registry.publish();
+
+    let task = DownloadTask::new(None, None).unwrap();
+    let result = PluginRepository::new()
+        .download_plugin("broken_plugin", &task)
+        .await;
+    assert!(!result.success);
+    assert!(result.message.contains("校验失败"), "{}", result.message);
+
+    assert!(!is_installed("broken_plugin"));
+    assert!(!is_installed("new_dependency"), "本次新安装的依赖应被删除");
+    assert!(is_installed("existing_dependency"));
+}
@@ -0,0 +1,57 @@
+//! 验证插件 FFI 调用边界：展开到主程序的 panic 被捕获并转换为插件错误
+
+use chat_client_lib::plugins::ffi::{call_unwind1, PluginCall};
+use chat_client_lib::plugins::{PluginError, PluginPhase};
+use std::ffi::c_void;
+
+/// 以允许展开的方式编译的插件函数
+extern "C-unwind" fn panicking_on_connect(_plugin: *mut c_void) -> i32 {
+    panic!("connect exploded");
+}
+
+extern "C-unwind" fn failing_on_connect(_plugin: *mut c_void) -> i32 {
+    3
+}
+
+/// 插件接口中的函数指针按 `extern "C"` 声明
+fn as_plugin_fn(
+    f: extern "C-unwind" fn(*mut c_void) -> i32,
+) -> unsafe extern "C" fn(*mut c_void) -> i32 {
+    unsafe {
+        std::mem::transmute::<
+            extern "C-unwind" fn(*mut c_void) -> i32,
+            unsafe extern "C" fn(*mut c_void) -> i32,
+        >(f)
+    }
+}
+
+#[test]
+fn panic_is_reported_as_plugin_error() {
+    let on_connect = as_plugin_fn(panicking_on_connect);
+    let call = PluginCall::new("panicking_plugin", "instance", None);
+
+    let error = call
+        .status(PluginPhase::Connect, || unsafe {
+            call_unwind1(on_connect, std::ptr::null_mut())
+        })
+        .unwrap_err();
+
+    assert!(matches!(error, PluginError::Panicked { .. }), "{:?}", error);
+    assert_eq!(error.message(), "connect exploded");
+    assert_eq!(error.plugin_id(), "panicking_plugin");
+    assert_eq!(error.phase(), PluginPhase::Connect);
+}
+
+#[test]
+fn status_codes_still_map_to_failures() {
+    let on_connect = as_plugin_fn(failing_on_connect);
+    let call = PluginCall::new("failing_plugin", "instance", None);
+
+    let error = call
+        .status(PluginPhase::Connect, || unsafe {
+            call_unwind1(on_connect, std::ptr::null_mut())
+        })
+        .unwrap_err();
+
+    assert_eq!(error.code(), Some(3));
+}
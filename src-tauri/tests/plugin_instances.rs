@@ -0,0 +1,85 @@
+//! 验证插件实例的生命周期：卸载后使用相同实例ID重新挂载时保留实例级别设置，
+//! 用户关闭实例时删除实例级别设置；插件间调用不会自动挂载目标插件
+
+mod common;
+
+use common::{copy_example_plugin, install_example_plugin, plugin_manager, EXAMPLE_PLUGIN_ID};
+use serde_json::json;
+use std::collections::HashMap;
+
+fn send(instance_id: &str, message: &str) -> String {
+    plugin_manager()
+        .send_message_to_plugin_instance(EXAMPLE_PLUGIN_ID, instance_id, message, Some(vec![]))
+        .unwrap()
+}
+
+fn mount_and_connect(instance_id: &str) {
+    let manager = plugin_manager();
+    manager
+        .mount_plugin(EXAMPLE_PLUGIN_ID, Some(instance_id.to_string()))
+        .unwrap();
+    manager.connect_plugin(instance_id).unwrap();
+}
+
+#[test]
+fn instance_settings_survive_dispose_and_remount() {
+    install_example_plugin();
+    let manager = plugin_manager();
+    let instance_id = "settings-remount";
+
+    mount_and_connect(instance_id);
+    manager
+        .set_plugin_settings(
+            EXAMPLE_PLUGIN_ID,
+            Some(instance_id),
+            HashMap::from([("reply_prefix".to_string(), json!("Hi"))]),
+        )
+        .unwrap();
+    assert!(send(instance_id, "ping").starts_with("Hi from"));
+
+    manager.dispose_plugin(instance_id).unwrap();
+    mount_and_connect(instance_id);
+    let response = send(instance_id, "ping");
+    assert!(response.starts_with("Hi from"), "{}", response);
+
+    manager.remove_plugin_instance(instance_id).unwrap();
+}
+
+#[test]
+fn removing_instance_drops_its_settings() {
+    install_example_plugin();
+    let manager = plugin_manager();
+    let instance_id = "settings-removed";
+
+    mount_and_connect(instance_id);
+    manager
+        .set_plugin_settings(
+            EXAMPLE_PLUGIN_ID,
+            Some(instance_id),
+            HashMap::from([("reply_prefix".to_string(), json!("Hi"))]),
+        )
+        .unwrap();
+
+    manager.remove_plugin_instance(instance_id).unwrap();
+    assert!(manager.get_plugin_status(instance_id).is_none());
+
+    mount_and_connect(instance_id);
+    let response = send(instance_id, "ping");
+    assert!(response.starts_with("Echo from"), "{}", response);
+
+    manager.remove_plugin_instance(instance_id).unwrap();
+}
+
+#[test]
+fn calling_unmounted_plugin_fails_without_mounting_it() {
+    // 复制一份使用其他ID的示例插件，避免与其他测试挂载的实例冲突
+    let peer_dir = install_example_plugin().with_file_name("example_peer");
+    copy_example_plugin(&peer_dir, "example_peer", "1.0.0");
+
+    let manager = plugin_manager();
+    let error = manager
+        .call_other_plugin("example_peer", "ping")
+        .unwrap_err();
+    assert!(error.contains("请先挂载"), "{}", error);
+    assert_eq!(manager.get_plugin_abi_version("example_peer"), None);
+}
@@ -0,0 +1,72 @@
+//! 验证隔离模式下的插件崩溃恢复：宿主进程崩溃后实例被标记为崩溃，重启后重新挂载并恢复连接
+//!
+//! 隔离模式是进程级配置，与非隔离模式的测试放在不同的测试程序中
+
+mod common;
+
+use chat_client_lib::plugins::PluginInstanceState;
+use chat_client_lib::settings::get_app_config_store;
+use common::{install_example_plugin, plugin_manager, EXAMPLE_PLUGIN_ID};
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+fn enable_isolation() {
+    common::isolated_home();
+    get_app_config_store()
+        .lock()
+        .unwrap()
+        .set("plugin.isolation", json!(true))
+        .unwrap();
+}
+
+fn wait_for_state(instance_id: &str, expected: PluginInstanceState) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let state = plugin_manager().get_plugin_instance_state(instance_id);
+        if state == Some(expected) {
+            return;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "实例 {} 的状态为 {:?}，期望 {:?}",
+            instance_id,
+            state,
+            expected
+        );
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn crashed_instance_reconnects_after_restart() {
+    enable_isolation();
+    install_example_plugin();
+    let manager = plugin_manager();
+    let instance_id = "crash-restart";
+
+    manager
+        .mount_plugin(EXAMPLE_PLUGIN_ID, Some(instance_id.to_string()))
+        .unwrap();
+    manager.connect_plugin(instance_id).unwrap();
+
+    let crashed = manager.send_message_to_plugin_instance(
+        EXAMPLE_PLUGIN_ID,
+        instance_id,
+        "/crash",
+        Some(vec![]),
+    );
+    assert!(crashed.is_err());
+    wait_for_state(instance_id, PluginInstanceState::Crashed);
+
+    manager.restart_plugin(instance_id).unwrap();
+    assert_eq!(
+        manager.get_plugin_instance_state(instance_id),
+        Some(PluginInstanceState::Connected)
+    );
+    let response = manager
+        .send_message_to_plugin_instance(EXAMPLE_PLUGIN_ID, instance_id, "ping", Some(vec![]))
+        .unwrap();
+    assert!(response.starts_with("Echo from"), "{}", response);
+
+    manager.remove_plugin_instance(instance_id).unwrap();
+}
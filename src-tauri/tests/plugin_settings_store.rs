@@ -0,0 +1,92 @@
+//! 验证插件设置存储：并发更新不会互相覆盖，无法解析的设置文件在覆盖前被备份
+
+mod common;
+
+use chat_client_lib::plugins::config::PluginSettingField;
+use chat_client_lib::plugins::directories::get_plugin_settings_directory;
+use chat_client_lib::plugins::PluginSettingsStore;
+use std::collections::HashMap;
+use std::fs;
+use std::thread;
+
+fn schema() -> Vec<PluginSettingField> {
+    let mut field: PluginSettingField = toml::from_str("type = \"string\"").unwrap();
+    field.key = "reply_prefix".to_string();
+    vec![field]
+}
+
+fn prefix(value: &str) -> HashMap<String, Option<toml::Value>> {
+    HashMap::from([(
+        "reply_prefix".to_string(),
+        Some(toml::Value::String(value.to_string())),
+    )])
+}
+
+#[test]
+fn concurrent_instance_updates_are_all_kept() {
+    common::isolated_home();
+    let plugin_id = "concurrent_settings";
+
+    let handles: Vec<_> = (0..8)
+        .map(|index| {
+            thread::spawn(move || {
+                let instance_id = format!("instance-{}", index);
+                PluginSettingsStore::new()
+                    .update(
+                        plugin_id,
+                        &schema(),
+                        Some(&instance_id),
+                        prefix(&index.to_string()),
+                    )
+                    .unwrap();
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let store = PluginSettingsStore::new();
+    for index in 0..8 {
+        let instance_id = format!("instance-{}", index);
+        let resolved = store.resolve(plugin_id, &schema(), Some(&instance_id));
+        assert_eq!(
+            resolved.get("reply_prefix"),
+            Some(&toml::Value::String(index.to_string())),
+            "实例 {} 的设置丢失",
+            instance_id
+        );
+    }
+    assert!(!get_plugin_settings_directory()
+        .join(format!("{}.toml.tmp", plugin_id))
+        .exists());
+}
+
+#[test]
+fn unparseable_settings_file_is_backed_up_before_update() {
+    common::isolated_home();
+    let plugin_id = "corrupt_settings";
+    let directory = get_plugin_settings_directory();
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(format!("{}.toml", plugin_id));
+    fs::write(&path, "values = [not toml").unwrap();
+
+    let store = PluginSettingsStore::new();
+    // 读取时使用默认值，不修改原文件
+    assert!(store.resolve(plugin_id, &schema(), None).is_empty());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "values = [not toml");
+
+    store
+        .update(plugin_id, &schema(), None, prefix("Hi"))
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(directory.join(format!("{}.toml.bak", plugin_id))).unwrap(),
+        "values = [not toml"
+    );
+    assert_eq!(
+        store
+            .resolve(plugin_id, &schema(), None)
+            .get("reply_prefix"),
+        Some(&toml::Value::String("Hi".to_string()))
+    );
+}
@@ -0,0 +1,184 @@
+//! 验证发布者签名校验：有效签名、被篡改的内容、不受信任的公钥、无效的公钥文件和缺少签名时的信任策略
+
+mod common;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chat_client_lib::plugins::directories::{
+    get_root_plugin_installed_directory, get_trusted_keys_file,
+};
+use chat_client_lib::plugins::download::DownloadTask;
+use chat_client_lib::plugins::registry::signature_path;
+use chat_client_lib::plugins::signature::{SignatureVerifier, TrustedKey, TrustedKeys};
+use chat_client_lib::plugins::PluginRepository;
+use chat_client_lib::settings::{get_app_config_store, SignaturePolicy};
+use common::LocalRegistry;
+use ed25519_dalek::{Signer, SigningKey};
+use serde_json::json;
+use std::fs;
+
+const MESSAGE: &[u8] = b"plugin binary";
+
+fn signing_key(seed: u8) -> SigningKey {
+    SigningKey::from_bytes(&[seed; 32])
+}
+
+fn trusted_keys(id: &str, key: &SigningKey) -> TrustedKeys {
+    TrustedKeys {
+        keys: vec![TrustedKey {
+            id: id.to_string(),
+            public_key: BASE64.encode(key.verifying_key().to_bytes()),
+        }],
+    }
+}
+
+/// 生成 `<key_id>:<base64 签名>` 格式的签名文本
+fn sign(key_id: &str, key: &SigningKey, message: &[u8]) -> String {
+    format!("{}:{}", key_id, BASE64.encode(key.sign(message).to_bytes()))
+}
+
+#[test]
+fn valid_signature_is_accepted() {
+    let key = signing_key(1);
+    let keys = trusted_keys("publisher", &key);
+
+    assert_eq!(
+        keys.verify(MESSAGE, &sign("publisher", &key, MESSAGE)),
+        Ok("publisher".to_string())
+    );
+    // 省略公钥 ID 时依次尝试所有受信任的公钥
+    let anonymous = BASE64.encode(key.sign(MESSAGE).to_bytes());
+    assert_eq!(
+        keys.verify(MESSAGE, &anonymous),
+        Ok("publisher".to_string())
+    );
+}
+
+#[test]
+fn tampered_payload_is_rejected() {
+    let key = signing_key(1);
+    let keys = trusted_keys("publisher", &key);
+    let signature = sign("publisher", &key, MESSAGE);
+
+    let error = keys.verify(b"tampered binary", &signature).unwrap_err();
+    assert!(error.contains("不匹配"), "{}", error);
+}
+
+#[test]
+fn untrusted_key_is_rejected() {
+    let trusted = signing_key(1);
+    let untrusted = signing_key(2);
+    let keys = trusted_keys("publisher", &trusted);
+
+    // 冒用受信任公钥的 ID
+    let error = keys
+        .verify(MESSAGE, &sign("publisher", &untrusted, MESSAGE))
+        .unwrap_err();
+    assert!(error.contains("不匹配"), "{}", error);
+
+    let error = keys
+        .verify(MESSAGE, &sign("stranger", &untrusted, MESSAGE))
+        .unwrap_err();
+    assert!(error.contains("stranger 不在受信任列表中"), "{}", error);
+}
+
+#[test]
+fn malformed_trusted_keys_file_is_rejected() {
+    let dir = common::isolated_home().join("malformed-keys");
+    fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join("trusted_keys.toml");
+    fs::write(&path, "[[keys]]\nid = \"publisher\"\npublic_key = ").unwrap();
+    let error = TrustedKeys::load_from(&path).unwrap_err();
+    assert!(error.contains("解析受信任公钥文件失败"), "{}", error);
+
+    // 公钥本身无效时跳过该公钥，签名无法通过校验
+    fs::write(
+        &path,
+        "[[keys]]\nid = \"publisher\"\npublic_key = \"not base64\"\n",
+    )
+    .unwrap();
+    let keys = TrustedKeys::load_from(&path).unwrap();
+    let error = keys
+        .verify(MESSAGE, &sign("publisher", &signing_key(1), MESSAGE))
+        .unwrap_err();
+    assert!(error.contains("不匹配"), "{}", error);
+
+    assert!(TrustedKeys::load_from(&dir.join("missing.toml"))
+        .unwrap()
+        .keys
+        .is_empty());
+}
+
+#[test]
+fn unsigned_package_follows_policy() {
+    let keys = trusted_keys("publisher", &signing_key(1));
+
+    let error = SignatureVerifier::new(SignaturePolicy::Require, keys.clone())
+        .check("插件 unsigned", MESSAGE, None)
+        .unwrap_err();
+    assert!(error.contains("缺少签名"), "{}", error);
+    SignatureVerifier::new(SignaturePolicy::Warn, keys.clone())
+        .check("插件 unsigned", MESSAGE, None)
+        .unwrap();
+    SignatureVerifier::new(SignaturePolicy::Off, keys.clone())
+        .check("插件 unsigned", MESSAGE, Some("invalid"))
+        .unwrap();
+
+    // require 策略下签名无效同样拒绝
+    let error = SignatureVerifier::new(SignaturePolicy::Require, keys)
+        .check("插件 tampered", MESSAGE, Some("invalid"))
+        .unwrap_err();
+    assert!(error.contains("签名校验失败"), "{}", error);
+}
+
+#[tokio::test]
+async fn require_policy_installs_only_signed_packages() {
+    let key = signing_key(3);
+    let keys_file = get_trusted_keys_file();
+    fs::create_dir_all(keys_file.parent().unwrap()).unwrap();
+    fs::write(
+        &keys_file,
+        toml::to_string(&trusted_keys("publisher", &key)).unwrap(),
+    )
+    .unwrap();
+    get_app_config_store()
+        .lock()
+        .unwrap()
+        .set("plugin.signature_policy", json!("require"))
+        .unwrap();
+
+    let mut registry = LocalRegistry::new("signed");
+    registry.add_plugin("signature_missing", "1.0.0", &[]);
+    // 注册表中的插件使用同一个示例动态库
+    let library = fs::read(registry.library_path("signature_missing")).unwrap();
+    let entry = registry.add_plugin("signed_plugin", "1.0.0", &[]);
+    LocalRegistry::artifact(entry)["signature"] = json!(sign("publisher", &key, &library));
+    registry.publish();
+    let index = fs::read(registry.index_path()).unwrap();
+    fs::write(
+        signature_path(&registry.index_path()),
+        sign("publisher", &key, &index),
+    )
+    .unwrap();
+
+    let task = DownloadTask::new(None, None).unwrap();
+    let result = PluginRepository::new()
+        .download_plugin("signature_missing", &task)
+        .await;
+    assert!(!result.success);
+    assert!(
+        result
+            .message
+            .contains("插件 signature_missing 签名校验失败: 缺少签名"),
+        "{}",
+        result.message
+    );
+    assert!(!get_root_plugin_installed_directory()
+        .join("signature_missing")
+        .exists());
+
+    let result = PluginRepository::new()
+        .download_plugin("signed_plugin", &task)
+        .await;
+    assert!(result.success, "{}", result.message);
+}
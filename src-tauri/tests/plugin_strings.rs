@@ -0,0 +1,53 @@
+//! 验证插件返回的字符串在主程序复制后通过插件导出的 `free_plugin_string` 归还
+
+use chat_client_lib::plugins::extensions::{take_plugin_string, FreePluginStringFn};
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+
+thread_local! {
+    /// 当前测试线程中被释放的字符串
+    static FREED: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// 模拟插件导出的释放函数，记录每次释放的内容
+unsafe extern "C" fn counting_free(ptr: *mut c_char) {
+    let value = CString::from_raw(ptr).into_bytes();
+    FREED.with(|freed| freed.borrow_mut().push(value));
+}
+
+fn freed() -> Vec<Vec<u8>> {
+    FREED.with(|freed| freed.borrow_mut().drain(..).collect())
+}
+
+fn plugin_string(bytes: &[u8]) -> *mut c_char {
+    CString::new(bytes).unwrap().into_raw()
+}
+
+const FREE: Option<FreePluginStringFn> = Some(counting_free);
+
+#[test]
+fn response_is_copied_then_freed() {
+    let response = unsafe { take_plugin_string(plugin_string(b"pong"), FREE) };
+
+    assert_eq!(response.unwrap(), "pong");
+    assert_eq!(freed(), vec![b"pong".to_vec()]);
+}
+
+#[test]
+fn invalid_utf8_response_is_still_freed() {
+    let response = unsafe { take_plugin_string(plugin_string(b"\xff\xfe"), FREE) };
+
+    assert!(response.is_err());
+    assert_eq!(freed(), vec![b"\xff\xfe".to_vec()]);
+}
+
+#[test]
+fn legacy_plugin_without_free_function_is_only_copied() {
+    let ptr = plugin_string(b"legacy");
+    let response = unsafe { take_plugin_string(ptr, None) };
+
+    assert_eq!(response.unwrap(), "legacy");
+    assert!(freed().is_empty());
+    // 测试中的字符串由测试分配，自行释放
+    drop(unsafe { CString::from_raw(ptr) });
+}
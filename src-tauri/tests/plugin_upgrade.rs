@@ -0,0 +1,162 @@
+//! 验证插件替换流程：新版本暂存并校验通过后才释放插件实例并替换目录，下载或回滚失败时实例保持运行
+
+mod common;
+
+use chat_client_lib::plugins::config::PluginConfig;
+use chat_client_lib::plugins::directories::{
+    get_plugin_backup_directory, get_root_plugin_installed_directory,
+};
+use chat_client_lib::plugins::download::DownloadTask;
+use chat_client_lib::plugins::staging::{
+    backup_version, rollback_installed_plugin, StagedInstall, USER_DATA_DIRECTORY,
+};
+use chat_client_lib::plugins::PluginInstanceState;
+use common::{copy_example_plugin, plugin_manager};
+use std::cell::Cell;
+use std::fs;
+use std::path::PathBuf;
+
+fn installed_dir(plugin_id: &str) -> PathBuf {
+    get_root_plugin_installed_directory().join(plugin_id)
+}
+
+fn installed_version(plugin_id: &str) -> String {
+    PluginConfig::from_file(installed_dir(plugin_id).join("config.toml"))
+        .unwrap()
+        .plugin
+        .version
+}
+
+/// 安装插件并挂载、连接一个实例
+fn mount_installed_copy(plugin_id: &str, instance_id: &str) {
+    common::isolated_home();
+    copy_example_plugin(&installed_dir(plugin_id), plugin_id, "1.0.0");
+    let manager = plugin_manager();
+    manager
+        .mount_plugin(plugin_id, Some(instance_id.to_string()))
+        .unwrap();
+    manager.connect_plugin(instance_id).unwrap();
+}
+
+#[test]
+fn staged_install_failing_verification_keeps_existing_install() {
+    common::isolated_home();
+    let plugin_id = "staged_invalid";
+    copy_example_plugin(&installed_dir(plugin_id), plugin_id, "1.0.0");
+
+    let staged = StagedInstall::new(plugin_id).unwrap();
+    let staging_dir = staged.dir().to_path_buf();
+    copy_example_plugin(&staging_dir, "other_plugin", "2.0.0");
+    let error = staged.verify("missing-library.so").unwrap_err();
+    assert!(error.contains("插件 ID 不一致"), "{}", error);
+    drop(staged);
+
+    assert!(!staging_dir.exists());
+    assert_eq!(installed_version(plugin_id), "1.0.0");
+}
+
+#[test]
+fn commit_releases_before_swap_and_keeps_user_data() {
+    common::isolated_home();
+    let plugin_id = "staged_commit";
+    copy_example_plugin(&installed_dir(plugin_id), plugin_id, "1.0.0");
+    let data_dir = installed_dir(plugin_id).join(USER_DATA_DIRECTORY);
+    fs::create_dir_all(&data_dir).unwrap();
+    fs::write(data_dir.join("notes.txt"), "before release").unwrap();
+
+    let staged = StagedInstall::new(plugin_id).unwrap();
+    copy_example_plugin(staged.dir(), plugin_id, "2.0.0");
+    let released = Cell::new(false);
+    let plugin_dir = staged
+        .commit(|| {
+            // 释放实例时旧版本仍在原位，实例释放前写入的数据同样保留
+            assert_eq!(installed_version(plugin_id), "1.0.0");
+            fs::write(data_dir.join("notes.txt"), "written on release").unwrap();
+            released.set(true);
+        })
+        .unwrap();
+
+    assert!(released.get());
+    assert_eq!(installed_version(plugin_id), "2.0.0");
+    assert_eq!(
+        fs::read_to_string(plugin_dir.join(USER_DATA_DIRECTORY).join("notes.txt")).unwrap(),
+        "written on release"
+    );
+    assert_eq!(backup_version(plugin_id).as_deref(), Some("1.0.0"));
+}
+
+#[test]
+fn rollback_without_backup_does_not_release() {
+    common::isolated_home();
+    let plugin_id = "rollback_without_backup";
+    copy_example_plugin(&installed_dir(plugin_id), plugin_id, "1.0.0");
+
+    let error =
+        rollback_installed_plugin(plugin_id, || panic!("没有备份时不应释放实例")).unwrap_err();
+    assert!(error.contains("没有可回滚的旧版本"), "{}", error);
+    assert_eq!(installed_version(plugin_id), "1.0.0");
+}
+
+#[test]
+fn rollback_swaps_current_version_with_backup() {
+    common::isolated_home();
+    let plugin_id = "rollback_swap";
+    copy_example_plugin(&installed_dir(plugin_id), plugin_id, "2.0.0");
+    copy_example_plugin(
+        &get_plugin_backup_directory().join(plugin_id),
+        plugin_id,
+        "1.0.0",
+    );
+
+    let released = Cell::new(false);
+    rollback_installed_plugin(plugin_id, || released.set(true)).unwrap();
+
+    assert!(released.get());
+    assert_eq!(installed_version(plugin_id), "1.0.0");
+    assert_eq!(backup_version(plugin_id).as_deref(), Some("2.0.0"));
+}
+
+#[tokio::test]
+async fn failed_upgrade_keeps_instances_running() {
+    let plugin_id = "upgrade_missing";
+    let instance_id = "upgrade-missing-instance";
+    mount_installed_copy(plugin_id, instance_id);
+
+    // 插件仓库中没有该插件，升级在下载前失败
+    let task = DownloadTask::new(None, None).unwrap();
+    let result = plugin_manager().upgrade_plugin(plugin_id, &task).await;
+    assert!(!result.success, "{}", result.message);
+
+    assert_eq!(
+        plugin_manager().get_plugin_instance_state(instance_id),
+        Some(PluginInstanceState::Connected)
+    );
+    plugin_manager()
+        .remove_plugin_instance(instance_id)
+        .unwrap();
+}
+
+#[test]
+fn rollback_releases_instances_only_when_backup_exists() {
+    let plugin_id = "rollback_instances";
+    let instance_id = "rollback-instance";
+    mount_installed_copy(plugin_id, instance_id);
+    let manager = plugin_manager();
+
+    let result = manager.rollback_plugin(plugin_id);
+    assert!(!result.success);
+    assert_eq!(
+        manager.get_plugin_instance_state(instance_id),
+        Some(PluginInstanceState::Connected)
+    );
+
+    copy_example_plugin(
+        &get_plugin_backup_directory().join(plugin_id),
+        plugin_id,
+        "0.9.0",
+    );
+    let result = manager.rollback_plugin(plugin_id);
+    assert!(result.success, "{}", result.message);
+    assert!(manager.get_plugin_status(instance_id).is_none());
+    assert_eq!(installed_version(plugin_id), "0.9.0");
+}
@@ -0,0 +1,141 @@
+//! 使用本地 HTTP 服务验证注册表索引的条件请求和本地缓存
+
+use chat_client_lib::plugins::registry::{fetch_index, load_index, REGISTRY_INDEX_FILE};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+const INDEX: &str = r#"{
+  "version": 1,
+  "plugins": [
+    {
+      "id": "example",
+      "name": "示例插件",
+      "version": "1.0.0",
+      "config_url": "example/config.toml",
+      "config_checksum": "sha256:00",
+      "artifacts": {
+        "linux": { "checksum": "sha256:00", "download_url": "example/libexample.so" }
+      }
+    }
+  ]
+}"#;
+
+const ETAG: &str = "\"index-v1\"";
+
+/// 统计服务器返回的各类响应
+#[derive(Default)]
+struct Hits {
+    full: AtomicUsize,
+    not_modified: AtomicUsize,
+}
+
+/// 启动只提供 `/index.json` 的本地服务，请求携带匹配的 `If-None-Match` 时返回 304
+fn start_server(hits: Arc<Hits>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            handle_connection(stream, &hits);
+        }
+    });
+    format!("http://{}/index.json", address)
+}
+
+fn handle_connection(mut stream: TcpStream, hits: &Hits) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_string();
+
+    let mut if_none_match = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("if-none-match") {
+                if_none_match = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let response = if path != "/index.json" {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    } else if if_none_match.as_deref() == Some(ETAG) {
+        hits.not_modified.fetch_add(1, Ordering::SeqCst);
+        format!(
+            "HTTP/1.1 304 Not Modified\r\nETag: {}\r\nConnection: close\r\n\r\n",
+            ETAG
+        )
+    } else {
+        hits.full.fetch_add(1, Ordering::SeqCst);
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nETag: {}\r\nLast-Modified: Fri, 16 Oct 2026 00:00:00 GMT\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            ETAG,
+            INDEX.len(),
+            INDEX
+        )
+    };
+    stream.write_all(response.as_bytes()).unwrap();
+}
+
+fn temp_cache_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "chat-client-registry-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[tokio::test]
+async fn second_fetch_uses_cache_when_not_modified() {
+    let hits = Arc::new(Hits::default());
+    let url = start_server(hits.clone());
+    let cache_dir = temp_cache_dir("conditional");
+    let client = reqwest::Client::new();
+
+    let first = fetch_index(&client, &url, &cache_dir).await.unwrap();
+    assert!(!first.not_modified);
+    assert_eq!(first.index.plugins.len(), 1);
+    assert_eq!(first.index.plugins[0].id, "example");
+    assert!(cache_dir.join(REGISTRY_INDEX_FILE).is_file());
+
+    let second = fetch_index(&client, &url, &cache_dir).await.unwrap();
+    assert!(second.not_modified);
+    assert_eq!(second.index.plugins[0].version, "1.0.0");
+
+    assert_eq!(hits.full.load(Ordering::SeqCst), 1);
+    assert_eq!(hits.not_modified.load(Ordering::SeqCst), 1);
+
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}
+
+#[tokio::test]
+async fn missing_cache_sends_unconditional_request() {
+    let hits = Arc::new(Hits::default());
+    let url = start_server(hits.clone());
+    let cache_dir = temp_cache_dir("unconditional");
+    let client = reqwest::Client::new();
+
+    fetch_index(&client, &url, &cache_dir).await.unwrap();
+    // 删除缓存的索引后，即使元数据仍在也应重新完整获取
+    std::fs::remove_file(cache_dir.join(REGISTRY_INDEX_FILE)).unwrap();
+    let fetched = fetch_index(&client, &url, &cache_dir).await.unwrap();
+
+    assert!(!fetched.not_modified);
+    assert_eq!(hits.full.load(Ordering::SeqCst), 2);
+    assert!(load_index(&cache_dir.join(REGISTRY_INDEX_FILE)).is_ok());
+
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}
@@ -0,0 +1,134 @@
+//! 验证密钥库：加密保存后重新解锁读取、口令错误、密文被篡改，以及重新保存时原子替换文件
+
+mod common;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chat_client_lib::secrets::SecretVault;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const PASSPHRASE: &str = "correct horse battery staple";
+
+/// 在独立目录中创建密钥库并写入一个插件密钥和一个主程序密钥，返回密钥库文件路径
+fn create_vault(name: &str) -> PathBuf {
+    let path = common::isolated_home().join("vaults").join(name);
+    let mut vault = SecretVault::new(&path);
+    vault.unlock(PASSPHRASE).unwrap();
+    vault.set("example_plugin", "api_key", "sk-plugin").unwrap();
+    vault.set_host_secret("proxy_password", "hunter2").unwrap();
+    path
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut temp_name = path.file_name().unwrap().to_os_string();
+    temp_name.push(".tmp");
+    path.with_file_name(temp_name)
+}
+
+#[test]
+fn secrets_survive_lock_and_reopen() {
+    let path = create_vault("round_trip.vault");
+
+    let content = fs::read_to_string(&path).unwrap();
+    assert!(!content.contains("sk-plugin") && !content.contains("hunter2"));
+
+    let mut vault = SecretVault::new(&path);
+    assert!(vault.status().initialized);
+    assert!(!vault.status().unlocked);
+    assert!(vault.get("example_plugin", "api_key").is_err());
+
+    vault.unlock(PASSPHRASE).unwrap();
+    assert_eq!(
+        vault.get("example_plugin", "api_key").unwrap().as_deref(),
+        Some("sk-plugin")
+    );
+    assert_eq!(vault.list("example_plugin").unwrap(), vec!["api_key"]);
+    assert_eq!(
+        vault.get_host_secret("proxy_password").unwrap().as_deref(),
+        Some("hunter2")
+    );
+    // 插件无法读取主程序的密钥
+    assert_eq!(vault.get("example_plugin", "proxy_password").unwrap(), None);
+
+    vault.lock();
+    assert!(!vault.status().unlocked);
+    assert!(vault.get("example_plugin", "api_key").is_err());
+}
+
+#[test]
+fn wrong_passphrase_is_rejected() {
+    let path = create_vault("wrong_passphrase.vault");
+
+    let mut vault = SecretVault::new(&path);
+    let error = vault.unlock("wrong passphrase").unwrap_err();
+    assert!(error.contains("口令错误"), "{}", error);
+    assert!(!vault.status().unlocked);
+    assert!(vault.unlock("").is_err());
+
+    vault.unlock(PASSPHRASE).unwrap();
+}
+
+#[test]
+fn tampered_ciphertext_is_rejected() {
+    let path = create_vault("tampered.vault");
+
+    let mut file: toml::Table = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    let mut ciphertext = BASE64.decode(file["ciphertext"].as_str().unwrap()).unwrap();
+    ciphertext[0] ^= 0x01;
+    file.insert(
+        "ciphertext".to_string(),
+        toml::Value::String(BASE64.encode(ciphertext)),
+    );
+    fs::write(&path, toml::to_string(&file).unwrap()).unwrap();
+
+    let mut vault = SecretVault::new(&path);
+    let error = vault.unlock(PASSPHRASE).unwrap_err();
+    assert!(error.contains("已损坏"), "{}", error);
+    assert!(!vault.status().unlocked);
+}
+
+#[test]
+fn resave_replaces_vault_atomically() {
+    let path = create_vault("resave.vault");
+    let before = fs::read_to_string(&path).unwrap();
+
+    let mut vault = SecretVault::new(&path);
+    vault.unlock(PASSPHRASE).unwrap();
+    vault
+        .set("example_plugin", "api_key", "sk-rotated")
+        .unwrap();
+
+    // 每次保存使用新的 nonce，且不残留临时文件
+    assert_ne!(fs::read_to_string(&path).unwrap(), before);
+    assert!(!temp_path(&path).exists());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    // 临时文件无法写入时保存失败，原有密钥库保持不变
+    let saved = fs::read_to_string(&path).unwrap();
+    fs::create_dir(temp_path(&path)).unwrap();
+    assert!(vault.set("example_plugin", "api_key", "sk-lost").is_err());
+    fs::remove_dir(temp_path(&path)).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), saved);
+
+    let mut reopened = SecretVault::new(&path);
+    reopened.unlock(PASSPHRASE).unwrap();
+    assert_eq!(
+        reopened
+            .get("example_plugin", "api_key")
+            .unwrap()
+            .as_deref(),
+        Some("sk-rotated")
+    );
+    assert_eq!(
+        reopened
+            .get_host_secret("proxy_password")
+            .unwrap()
+            .as_deref(),
+        Some("hunter2")
+    );
+}
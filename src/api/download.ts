@@ -3,18 +3,83 @@
  */
 
 import { invoke } from '@tauri-apps/api/core'
-import type { DownloadResponse } from './types'
+import { listen, UnlistenFn } from '@tauri-apps/api/event'
+import type { DownloadProgress, DownloadResponse, RepositorySource, ResolvedRepository } from './types'
 
 /**
- * 下载GitHub仓库到plugins目录
+ * 刷新所有已启用的插件仓库源
+ * @param downloadId 下载ID，用于匹配进度事件和取消下载
  * @returns Promise<DownloadResponse> 下载结果
  */
-export async function downloadGithubRepo(): Promise<DownloadResponse> {
+export async function downloadGithubRepo(downloadId?: string): Promise<DownloadResponse> {
   try {
-    const response = await invoke<DownloadResponse>('download_github_repo')
+    const response = await invoke<DownloadResponse>('download_github_repo', { downloadId })
     return response
   } catch (error) {
     console.error('Failed to download GitHub repo:', error)
     throw error
   }
 }
+
+/**
+ * 获取插件仓库源配置
+ * @returns Promise<RepositorySource[]> 仓库源列表
+ */
+export async function getPluginRepositories(): Promise<RepositorySource[]> {
+  try {
+    return await invoke<RepositorySource[]>('get_plugin_repositories')
+  } catch (error) {
+    console.error('Failed to get plugin repositories:', error)
+    throw error
+  }
+}
+
+/**
+ * 保存插件仓库源配置
+ * @param repositories 仓库源列表
+ */
+export async function setPluginRepositories(repositories: RepositorySource[]): Promise<void> {
+  try {
+    await invoke('set_plugin_repositories', { repositories })
+  } catch (error) {
+    console.error('Failed to set plugin repositories:', error)
+    throw error
+  }
+}
+
+/**
+ * 获取各仓库源最近一次下载的快照及其对应的提交
+ * @returns Promise<ResolvedRepository[]> 快照记录列表
+ */
+export async function getResolvedRepositories(): Promise<ResolvedRepository[]> {
+  try {
+    return await invoke<ResolvedRepository[]>('get_resolved_repositories')
+  } catch (error) {
+    console.error('Failed to get resolved repositories:', error)
+    throw error
+  }
+}
+
+/**
+ * 取消进行中的下载
+ * @param downloadId 下载ID
+ */
+export async function cancelDownload(downloadId: string): Promise<void> {
+  try {
+    await invoke('cancel_download', { downloadId })
+  } catch (error) {
+    console.error('Failed to cancel download:', error)
+    throw error
+  }
+}
+
+/**
+ * 监听下载进度事件
+ * @param callback 进度回调
+ * @returns Promise<UnlistenFn> 取消监听函数
+ */
+export async function listenDownloadProgress(
+  callback: (progress: DownloadProgress) => void
+): Promise<UnlistenFn> {
+  return await listen<DownloadProgress>('plugin-download-progress', (event) => callback(event.payload))
+}
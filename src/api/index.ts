@@ -9,17 +9,31 @@ export type * from './types'
 // 导出插件相关 API
 export {
   scanPlugins,
+  scanIncompatiblePlugins,
   mountPlugin,
   disposePlugin,
+  removePluginInstance,
   connectPlugin,
   disconnectPlugin,
   getPluginStatus,
+  getPluginInstanceState,
+  getPluginAbiVersion,
+  restartPlugin,
+  listenPluginCrashed,
+  listenPluginError,
   sendMessageToPlugin,
   sendMessageToCurrentPlugin,
   scanAvailablePlugins,
   downloadPlugin,
   uninstallPlugin,
-  cancelStreamMessage
+  installPluginFromPath,
+  rollbackPlugin,
+  checkPluginUpdates,
+  upgradePlugin,
+  cancelStreamMessage,
+  getPluginSettingsSchema,
+  getPluginSettings,
+  setPluginSettings
 } from './plugins'
 
 // 导出插件UI相关 API
@@ -29,11 +43,39 @@ export {
   listenPluginUiUpdate
 } from './plugin-ui'
 
+// 导出应用配置相关 API
+export {
+  getAppSettings,
+  getAppConfig,
+  setAppConfig,
+  updateAppConfig,
+  isProxyPasswordSet,
+  setProxyPassword,
+  listenAppConfigChanged
+} from './settings'
+
+// 导出密钥库相关 API
+export {
+  getSecretVaultStatus,
+  unlockSecretVault,
+  lockSecretVault,
+  setPluginSecret,
+  listPluginSecrets,
+  deletePluginSecret
+} from './secrets'
+
 // 导出事件监听相关 API
 export { setupEventListeners, cleanupEventListeners } from './listener'
 
 // 导出下载相关 API
-export { downloadGithubRepo } from './download'
+export {
+  downloadGithubRepo,
+  cancelDownload,
+  listenDownloadProgress,
+  getPluginRepositories,
+  setPluginRepositories,
+  getResolvedRepositories
+} from './download'
 
 // 导出常用的 Tauri API（重新导出以便统一管理）
 export { invoke } from '@tauri-apps/api/core'
@@ -3,7 +3,19 @@
  */
 
 import { invoke } from '@tauri-apps/api/core'
-import type { PluginMetadata, AvailablePluginInfo, PluginDownloadResult } from './types'
+import type {
+  PluginMetadata,
+  AvailablePluginInfo,
+  IncompatiblePluginInfo,
+  PluginDownloadResult,
+  PluginUpdateInfo,
+  PluginSettingField,
+  PluginSettingsView,
+  PluginInstanceState,
+  PluginCrashedEvent,
+  PluginError
+} from './types'
+import { listen, UnlistenFn } from '@tauri-apps/api/event'
 import type { BaseMessage } from '../stores/history'
 
 /**
@@ -21,6 +33,19 @@ export async function scanPlugins(): Promise<PluginMetadata[]> {
   }
 }
 
+/**
+ * 扫描与当前客户端版本或平台不兼容的本地插件
+ * @returns Promise<IncompatiblePluginInfo[]> 不兼容插件及原因
+ */
+export async function scanIncompatiblePlugins(): Promise<IncompatiblePluginInfo[]> {
+  try {
+    return await invoke<IncompatiblePluginInfo[]>('scan_incompatible_plugins')
+  } catch (error) {
+    console.error('Failed to scan incompatible plugins:', error)
+    throw error
+  }
+}
+
 /**
  * 挂载插件实例
  * @param pluginId 插件ID
@@ -40,6 +65,15 @@ export async function disposePlugin(instanceId: string): Promise<string> {
   return await invoke<string>('dispose_plugin', { instanceId })
 }
 
+/**
+ * 关闭插件实例并删除其实例级别设置
+ * @param instanceId 实例ID
+ * @returns Promise<string> 成功消息
+ */
+export async function removePluginInstance(instanceId: string): Promise<string> {
+  return await invoke<string>('remove_plugin_instance', { instanceId })
+}
+
 /**
  * 连接插件实例
  * @param instanceId 实例ID
@@ -67,6 +101,55 @@ export async function getPluginStatus(instanceId: string): Promise<[boolean, boo
   return await invoke<[boolean, boolean] | null>('get_plugin_status', { instanceId })
 }
 
+/**
+ * 获取插件握手得到的 ABI 版本
+ * @param pluginId 插件ID
+ * @returns Promise<number | null> ABI 版本，插件尚未加载过时为 null
+ */
+export async function getPluginAbiVersion(pluginId: string): Promise<number | null> {
+  return await invoke<number | null>('get_plugin_abi_version', { pluginId })
+}
+
+/**
+ * 获取插件实例生命周期状态
+ * @param instanceId 实例ID
+ * @returns Promise<PluginInstanceState | null> 实例状态，实例不存在时为 null
+ */
+export async function getPluginInstanceState(instanceId: string): Promise<PluginInstanceState | null> {
+  return await invoke<PluginInstanceState | null>('get_plugin_instance_state', { instanceId })
+}
+
+/**
+ * 重启插件实例（用于恢复隔离模式下崩溃的插件）
+ * @param instanceId 实例ID
+ * @returns Promise<string> 成功消息
+ */
+export async function restartPlugin(instanceId: string): Promise<string> {
+  return await invoke<string>('restart_plugin', { instanceId })
+}
+
+/**
+ * 监听插件调用错误事件
+ * @param callback 错误回调
+ * @returns Promise<UnlistenFn> 取消监听函数
+ */
+export async function listenPluginError(
+  callback: (error: PluginError) => void
+): Promise<UnlistenFn> {
+  return await listen<PluginError>('plugin-error', (event) => callback(event.payload))
+}
+
+/**
+ * 监听插件崩溃事件（仅隔离模式）
+ * @param callback 崩溃回调
+ * @returns Promise<UnlistenFn> 取消监听函数
+ */
+export async function listenPluginCrashed(
+  callback: (event: PluginCrashedEvent) => void
+): Promise<UnlistenFn> {
+  return await listen<PluginCrashedEvent>('plugin-crashed', (event) => callback(event.payload))
+}
+
 /**
  * 向指定插件实例发送消息
  * @param pluginId 插件ID
@@ -100,12 +183,13 @@ export async function sendMessageToCurrentPlugin(message: string): Promise<strin
 
 /**
  * 扫描可用插件列表（从插件仓库）
+ * @param hideIncompatible 是否隐藏与当前客户端不兼容的插件，默认只标记不隐藏
  * @returns Promise<AvailablePluginInfo[]> 可用插件列表
  */
-export async function scanAvailablePlugins(): Promise<AvailablePluginInfo[]> {
+export async function scanAvailablePlugins(hideIncompatible = false): Promise<AvailablePluginInfo[]> {
   console.log('扫描可用插件列表')
   try {
-    const plugins = await invoke<AvailablePluginInfo[]>('scan_available_plugins')
+    const plugins = await invoke<AvailablePluginInfo[]>('scan_available_plugins', { hideIncompatible })
     return plugins
   } catch (error) {
     console.error('Failed to scan available plugins:', error)
@@ -116,12 +200,16 @@ export async function scanAvailablePlugins(): Promise<AvailablePluginInfo[]> {
 /**
  * 下载并安装插件
  * @param pluginId 插件ID
+ * @param downloadId 下载ID，用于匹配进度事件和取消下载
  * @returns Promise<PluginDownloadResult> 下载结果
  */
-export async function downloadPlugin(pluginId: string): Promise<PluginDownloadResult> {
+export async function downloadPlugin(
+  pluginId: string,
+  downloadId?: string
+): Promise<PluginDownloadResult> {
   console.log('下载插件:', pluginId)
   try {
-    const result = await invoke<PluginDownloadResult>('download_plugin', { pluginId })
+    const result = await invoke<PluginDownloadResult>('download_plugin', { pluginId, downloadId })
     return result
   } catch (error) {
     console.error('Failed to download plugin:', error)
@@ -130,14 +218,85 @@ export async function downloadPlugin(pluginId: string): Promise<PluginDownloadRe
 }
 
 /**
- * 卸载已安装的插件
+ * 检查已安装插件的可用更新
+ * @returns Promise<PluginUpdateInfo[]> 有新版本的插件列表
+ */
+export async function checkPluginUpdates(): Promise<PluginUpdateInfo[]> {
+  try {
+    return await invoke<PluginUpdateInfo[]>('check_plugin_updates')
+  } catch (error) {
+    console.error('Failed to check plugin updates:', error)
+    throw error
+  }
+}
+
+/**
+ * 升级已安装的插件，保留插件设置和用户数据
  * @param pluginId 插件ID
+ * @param downloadId 下载ID，用于匹配进度事件和取消下载
+ * @returns Promise<PluginDownloadResult> 升级结果
+ */
+export async function upgradePlugin(
+  pluginId: string,
+  downloadId?: string
+): Promise<PluginDownloadResult> {
+  console.log('升级插件:', pluginId)
+  try {
+    const result = await invoke<PluginDownloadResult>('upgrade_plugin', { pluginId, downloadId })
+    return result
+  } catch (error) {
+    console.error('Failed to upgrade plugin:', error)
+    throw error
+  }
+}
+
+/**
+ * 从本地插件包安装插件
+ * @param path .ccplugin、.zip、.tar.gz 插件包或包含 config.toml 和动态库的插件目录
+ * @returns Promise<PluginDownloadResult> 安装结果
+ */
+export async function installPluginFromPath(path: string): Promise<PluginDownloadResult> {
+  console.log('从本地插件包安装:', path)
+  try {
+    const result = await invoke<PluginDownloadResult>('install_plugin_from_path', { path })
+    return result
+  } catch (error) {
+    console.error('Failed to install plugin from path:', error)
+    throw error
+  }
+}
+
+/**
+ * 回滚插件到安装或升级前的版本
+ * @param pluginId 插件ID
+ * @returns Promise<PluginDownloadResult> 回滚结果
+ */
+export async function rollbackPlugin(pluginId: string): Promise<PluginDownloadResult> {
+  console.log('回滚插件:', pluginId)
+  try {
+    const result = await invoke<PluginDownloadResult>('rollback_plugin', { pluginId })
+    return result
+  } catch (error) {
+    console.error('Failed to rollback plugin:', error)
+    throw error
+  }
+}
+
+/**
+ * 卸载已安装的插件，插件的所有实例会先被销毁
+ * @param pluginId 插件ID
+ * @param force 其他插件依赖该插件时是否仍然强制卸载
+ * @param keepUserData 是否保留插件的 data 目录、设置和密钥
  * @returns Promise<PluginDownloadResult> 卸载结果
  */
-export async function uninstallPlugin(pluginId: string): Promise<PluginDownloadResult> {
+export async function uninstallPlugin(
+  pluginId: string,
+  force = false,
+  keepUserData = false
+): Promise<PluginDownloadResult> {
   console.log('卸载插件:', pluginId)
   try {
-    const result = await invoke<PluginDownloadResult>('uninstall_plugin', { pluginId })
+    const result = await invoke<PluginDownloadResult>('uninstall_plugin', { pluginId, force, keepUserData })
     return result
   } catch (error) {
     console.error('Failed to uninstall plugin:', error)
@@ -161,3 +320,47 @@ export async function cancelStreamMessage(instanceId: string, streamId: string):
     throw error
   }
 }
+
+/**
+ * 获取插件声明的设置项定义
+ * @param pluginId 插件ID
+ * @returns Promise<PluginSettingField[]> 设置项定义
+ */
+export async function getPluginSettingsSchema(pluginId: string): Promise<PluginSettingField[]> {
+  return await invoke<PluginSettingField[]>('get_plugin_settings_schema', { pluginId })
+}
+
+/**
+ * 获取插件设置
+ * @param pluginId 插件ID
+ * @param instanceId 可选的实例ID，提供时返回该实例生效的设置
+ * @returns Promise<PluginSettingsView> 插件设置
+ */
+export async function getPluginSettings(
+  pluginId: string,
+  instanceId?: string
+): Promise<PluginSettingsView> {
+  return await invoke<PluginSettingsView>('get_plugin_settings', {
+    pluginId,
+    instanceId: instanceId || null
+  })
+}
+
+/**
+ * 更新插件设置
+ * @param pluginId 插件ID
+ * @param values 设置值，值为 null 表示清除
+ * @param instanceId 可选的实例ID，提供时只修改该实例的设置
+ * @returns Promise<PluginSettingsView> 更新后的插件设置
+ */
+export async function setPluginSettings(
+  pluginId: string,
+  values: Record<string, unknown>,
+  instanceId?: string
+): Promise<PluginSettingsView> {
+  return await invoke<PluginSettingsView>('set_plugin_settings', {
+    pluginId,
+    instanceId: instanceId || null,
+    values
+  })
+}
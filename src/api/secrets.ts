@@ -0,0 +1,60 @@
+/**
+ * 插件密钥库相关的 Tauri API 调用
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import type { SecretVaultStatus } from './types'
+
+/**
+ * 获取密钥库状态
+ * @returns Promise<SecretVaultStatus> 密钥库状态
+ */
+export async function getSecretVaultStatus(): Promise<SecretVaultStatus> {
+  return await invoke<SecretVaultStatus>('get_secret_vault_status')
+}
+
+/**
+ * 使用口令解锁密钥库，密钥库不存在时创建
+ * @param passphrase 口令
+ * @returns Promise<SecretVaultStatus> 密钥库状态
+ */
+export async function unlockSecretVault(passphrase: string): Promise<SecretVaultStatus> {
+  return await invoke<SecretVaultStatus>('unlock_secret_vault', { passphrase })
+}
+
+/**
+ * 锁定密钥库
+ * @returns Promise<SecretVaultStatus> 密钥库状态
+ */
+export async function lockSecretVault(): Promise<SecretVaultStatus> {
+  return await invoke<SecretVaultStatus>('lock_secret_vault')
+}
+
+/**
+ * 设置插件密钥
+ * @param pluginId 插件ID
+ * @param name 密钥名称
+ * @param value 密钥值
+ */
+export async function setPluginSecret(pluginId: string, name: string, value: string): Promise<void> {
+  await invoke<void>('set_plugin_secret', { pluginId, name, value })
+}
+
+/**
+ * 列出插件的密钥名称
+ * @param pluginId 插件ID
+ * @returns Promise<string[]> 密钥名称列表
+ */
+export async function listPluginSecrets(pluginId: string): Promise<string[]> {
+  return await invoke<string[]>('list_plugin_secrets', { pluginId })
+}
+
+/**
+ * 删除插件密钥
+ * @param pluginId 插件ID
+ * @param name 密钥名称
+ * @returns Promise<boolean> 密钥是否存在
+ */
+export async function deletePluginSecret(pluginId: string, name: string): Promise<boolean> {
+  return await invoke<boolean>('delete_plugin_secret', { pluginId, name })
+}
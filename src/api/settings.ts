@@ -0,0 +1,70 @@
+/**
+ * 应用配置相关的 Tauri API 调用
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, UnlistenFn } from '@tauri-apps/api/event'
+import type { AppConfigChange } from './types'
+
+/**
+ * 获取完整的后端应用配置
+ * @returns Promise<Record<string, Record<string, unknown>>> 按分组组织的配置
+ */
+export async function getAppSettings(): Promise<Record<string, Record<string, unknown>>> {
+  return await invoke<Record<string, Record<string, unknown>>>('get_app_settings')
+}
+
+/**
+ * 获取单个配置值
+ * @param key 命名空间键，例如 general.theme
+ * @returns Promise<unknown> 配置值
+ */
+export async function getAppConfig(key: string): Promise<unknown> {
+  return await invoke<unknown>('get_app_config', { key })
+}
+
+/**
+ * 设置单个配置值
+ * @param key 命名空间键，例如 general.theme
+ * @param value 配置值
+ * @returns Promise<AppConfigChange> 变更结果
+ */
+export async function setAppConfig(key: string, value: unknown): Promise<AppConfigChange> {
+  return await invoke<AppConfigChange>('set_app_config', { key, value })
+}
+
+/**
+ * 批量设置配置值
+ * @param values 命名空间键到配置值的映射
+ * @returns Promise<AppConfigChange[]> 变更结果
+ */
+export async function updateAppConfig(values: Record<string, unknown>): Promise<AppConfigChange[]> {
+  return await invoke<AppConfigChange[]>('update_app_config', { values })
+}
+
+/**
+ * 代理密码是否已保存在密钥库中（需要先解锁密钥库）
+ * @returns Promise<boolean> 是否已保存
+ */
+export async function isProxyPasswordSet(): Promise<boolean> {
+  return await invoke<boolean>('is_proxy_password_set')
+}
+
+/**
+ * 在密钥库中保存代理密码
+ * @param password 代理密码，传入空字符串时删除
+ */
+export async function setProxyPassword(password: string): Promise<void> {
+  await invoke('set_proxy_password', { password })
+}
+
+/**
+ * 监听后端配置变更事件
+ * @param callback 变更回调
+ * @returns Promise<UnlistenFn> 取消监听函数
+ */
+export async function listenAppConfigChanged(
+  callback: (change: AppConfigChange) => void
+): Promise<UnlistenFn> {
+  return await listen<AppConfigChange>('app-config-changed', (event) => callback(event.payload))
+}
@@ -15,6 +15,7 @@ export interface PluginMetadata {
   icon?: string          // 插件图标
   color?: string         // 插件颜色
   require_history?: boolean  // 是否需要接收历史记录
+  abi_version?: number | null  // 扫描时协商得到的插件 ABI 版本
 }
 
 // 下载响应接口
@@ -24,6 +25,27 @@ export interface DownloadResponse {
   download_path?: string
 }
 
+/**
+ * 下载状态
+ */
+export type DownloadStatus = 'downloading' | 'finished' | 'cancelled' | 'failed'
+
+/**
+ * 下载进度事件（plugin-download-progress）
+ */
+export interface DownloadProgress {
+  download_id: string
+  /** 正在下载的文件地址 */
+  url: string
+  /** 已下载字节数 */
+  downloaded: number
+  /** 总字节数，服务器未返回长度时为空 */
+  total?: number
+  /** 平均下载速度（字节/秒） */
+  speed: number
+  status: DownloadStatus
+}
+
 /**
  * 可用插件信息（来自插件仓库）
  */
@@ -46,16 +68,95 @@ export interface AvailablePluginInfo {
     windows?: {
       checksum: string
       download_url: string
+      signature?: string
     }
     macos?: {
       checksum: string
       download_url: string
+      signature?: string
     }
     linux?: {
       checksum: string
       download_url: string
+      signature?: string
+    }
+    /** 按目标三元组或“系统-架构”区分的下载项，如 aarch64-unknown-linux-gnu、linux-aarch64 */
+    targets?: Record<
+      string,
+      {
+        checksum: string
+        download_url: string
+        signature?: string
+      }
+    >
+    /** 跨平台 .ccplugin 插件包 */
+    package?: {
+      checksum: string
+      download_url: string
+      signature?: string
     }
   }
+  /** 与当前客户端不兼容的原因，兼容时为空 */
+  incompatible_reason?: string
+  /** 插件来自的仓库源名称 */
+  source: string
+  /** 注册表索引中插件 config.toml 的地址 */
+  config_url?: string
+  /** config.toml 的校验值 */
+  config_checksum?: string
+}
+
+/**
+ * 插件仓库源
+ */
+export interface RepositorySource {
+  name: string
+  /** GitHub 仓库地址、zip 归档地址、本地目录或 file:// URL */
+  url: string
+  branch?: string
+  tag?: string
+  /** 固定的提交 SHA，与 branch、tag 只能指定一个，仅 GitHub 仓库源支持 */
+  commit?: string
+  /** 优先级，数值越大越优先 */
+  priority: number
+  enabled: boolean
+}
+
+/**
+ * 仓库源最近一次下载的快照
+ */
+export interface ResolvedRepository {
+  name: string
+  url: string
+  /** 下载时固定的引用，如 branch:main、tag:v1.0.0、commit:3f2a9c1，非 GitHub 仓库源为 none */
+  reference: string
+  /** 快照实际对应的提交 SHA */
+  commit?: string
+  /** 下载时间（Unix 时间戳，秒） */
+  fetched_at: number
+}
+
+/**
+ * 与当前客户端不兼容的本地插件
+ */
+export interface IncompatiblePluginInfo {
+  id: string
+  name: string
+  version: string
+  config_path: string
+  reason: string
+}
+
+/**
+ * 插件更新信息
+ */
+export interface PluginUpdateInfo {
+  id: string
+  name: string
+  installed_version: string
+  available_version: string
+  /** 新版本与当前客户端不兼容的原因 */
+  incompatible_reason?: string
 }
 
 /**
@@ -68,5 +169,87 @@ export interface PluginDownloadResult {
   installed_path?: string
 }
 
+/**
+ * 插件设置项定义（来自插件 config.toml 的 [settings]）
+ */
+export interface PluginSettingField {
+  key: string
+  label?: string
+  description?: string
+  type: 'string' | 'number' | 'integer' | 'boolean' | 'select'
+  default?: unknown
+  required: boolean
+  secret: boolean
+  options: string[]
+  min?: number
+  max?: number
+}
+
+/**
+ * 插件设置视图
+ */
+export interface PluginSettingsView {
+  schema: PluginSettingField[]
+  values: Record<string, unknown>
+  configured_secrets: string[]
+  missing_required: string[]
+}
+
+/**
+ * 插件实例生命周期状态
+ */
+export type PluginInstanceState = 'disposed' | 'mounted' | 'connected' | 'crashed'
+
+/**
+ * 插件崩溃事件
+ */
+export interface PluginCrashedEvent {
+  plugin: string
+  instance: string
+}
+
+/**
+ * 调用插件时所处的阶段
+ */
+export type PluginPhase =
+  | 'create'
+  | 'initialize'
+  | 'mount'
+  | 'connect'
+  | 'disconnect'
+  | 'dispose'
+  | 'update_ui'
+  | 'set_history'
+  | 'handle_message'
+  | 'settings'
+
+/**
+ * 插件调用错误
+ */
+export interface PluginError {
+  kind: 'failed' | 'invalid_response' | 'remote' | 'panicked'
+  code?: number // 仅 failed 时存在
+  message: string
+  plugin_id: string
+  instance_id: string
+  phase: PluginPhase
+}
+
+/**
+ * 密钥库状态
+ */
+export interface SecretVaultStatus {
+  initialized: boolean
+  unlocked: boolean
+}
+
+/**
+ * 应用配置变更
+ */
+export interface AppConfigChange {
+  key: string
+  value: unknown
+}
+
 // 重新导出插件UI相关类型
 export * from './plugin-ui-types'
@@ -5,6 +5,7 @@ import {
   scanPlugins,
   mountPlugin,
   disposePlugin,
+  removePluginInstance,
   connectPlugin,
   disconnectPlugin,
   sendMessageToPlugin,
@@ -121,8 +122,8 @@ export const usePluginStore = defineStore('plugins', () => {
     }
   }
 
-  // 卸载插件实例
-  const disposePluginInstance = async (instanceId: string) => {
+  // 卸载插件实例，removeSettings 为 true 时同时删除实例级别设置（用户关闭实例）
+  const disposePluginInstance = async (instanceId: string, removeSettings = false) => {
     try {
       const instance = getInstanceState(instanceId)
       if (!instance) {
@@ -131,7 +132,9 @@ export const usePluginStore = defineStore('plugins', () => {
 
       setInstanceState(instanceId, instance.pluginId, { isLoading: true, error: undefined })
 
-      const result = await disposePlugin(instanceId)
+      const result = removeSettings
+        ? await removePluginInstance(instanceId)
+        : await disposePlugin(instanceId)
 
       delete instanceStates.value[instanceId]
 
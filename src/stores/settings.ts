@@ -1,5 +1,6 @@
 import { ref, reactive } from 'vue'
 import { defineStore } from 'pinia'
+import { getAppConfig, updateAppConfig } from '../api/settings'
 
 // 设置接口定义
 export interface AppSettings {
@@ -12,6 +13,12 @@ export interface AppSettings {
   pluginDirectory: string
   pluginHotReload: boolean
   pluginLogLevel: 'error' | 'warn' | 'info' | 'debug'
+  pluginIsolation: boolean
+  pluginAllowLegacyAbi: boolean
+  pluginSignaturePolicy: 'require' | 'warn' | 'off'
+  pluginArchiveMaxExtractedSizeMb: number
+  pluginArchiveMaxFileSizeMb: number
+  pluginArchiveMaxEntries: number
   
   // 消息设置
   messageRetentionDays: number
@@ -21,11 +28,51 @@ export interface AppSettings {
   renderInputMessageAsMarkdown: boolean
   clearMessageInputOnSend: boolean,
 
+  // 网络设置
+  networkConnectTimeoutSecs: number
+  networkReadTimeoutSecs: number
+  networkProxyUrl: string
+  networkProxyUsername: string
+  networkCaCertificates: string[]
+  networkMaxRetries: number
+  networkRetryBackoffMs: number
+
   // 高级设置
   developerMode: boolean
   hardwareAcceleration: boolean
 }
 
+// 前端设置项与后端命名空间配置键的对应关系
+const backendConfigKeys: Record<keyof AppSettings, string> = {
+  theme: 'general.theme',
+  language: 'general.language',
+  autoConnect: 'general.auto_connect',
+  pluginDirectory: 'plugin.directory',
+  pluginHotReload: 'plugin.hot_reload',
+  pluginLogLevel: 'plugin.log_level',
+  pluginIsolation: 'plugin.isolation',
+  pluginAllowLegacyAbi: 'plugin.allow_legacy_abi',
+  pluginSignaturePolicy: 'plugin.signature_policy',
+  pluginArchiveMaxExtractedSizeMb: 'plugin.archive_max_extracted_size_mb',
+  pluginArchiveMaxFileSizeMb: 'plugin.archive_max_file_size_mb',
+  pluginArchiveMaxEntries: 'plugin.archive_max_entries',
+  messageRetentionDays: 'message.retention_days',
+  maxDisplayMessages: 'message.max_display_messages',
+  autoScrollToLatest: 'message.auto_scroll_to_latest',
+  enableMarkdown: 'message.enable_markdown',
+  renderInputMessageAsMarkdown: 'message.render_input_message_as_markdown',
+  clearMessageInputOnSend: 'message.clear_message_input_on_send',
+  networkConnectTimeoutSecs: 'network.connect_timeout_secs',
+  networkReadTimeoutSecs: 'network.read_timeout_secs',
+  networkProxyUrl: 'network.proxy_url',
+  networkProxyUsername: 'network.proxy_username',
+  networkCaCertificates: 'network.ca_certificates',
+  networkMaxRetries: 'network.max_retries',
+  networkRetryBackoffMs: 'network.retry_backoff_ms',
+  developerMode: 'advanced.developer_mode',
+  hardwareAcceleration: 'advanced.hardware_acceleration',
+}
+
 // 默认设置
 const defaultSettings: AppSettings = {
   // 通用设置
@@ -37,6 +84,12 @@ const defaultSettings: AppSettings = {
   pluginDirectory: './plugins',
   pluginHotReload: false,
   pluginLogLevel: 'info',
+  pluginIsolation: false,
+  pluginAllowLegacyAbi: false,
+  pluginSignaturePolicy: 'warn',
+  pluginArchiveMaxExtractedSizeMb: 512,
+  pluginArchiveMaxFileSizeMb: 256,
+  pluginArchiveMaxEntries: 10000,
   
   // 消息设置
   messageRetentionDays: 30,
@@ -45,6 +98,15 @@ const defaultSettings: AppSettings = {
   enableMarkdown: true,
   renderInputMessageAsMarkdown: false,
   clearMessageInputOnSend: true,
+
+  // 网络设置
+  networkConnectTimeoutSecs: 10,
+  networkReadTimeoutSecs: 30,
+  networkProxyUrl: '',
+  networkProxyUsername: '',
+  networkCaCertificates: [],
+  networkMaxRetries: 3,
+  networkRetryBackoffMs: 500,
   
   // 高级设置
   developerMode: false,
@@ -62,6 +124,26 @@ export const useSettingsStore = defineStore('settings', () => {
   // 本地存储键名
   const STORAGE_KEY = 'chat-client-settings'
 
+  // 从后端配置存储加载设置
+  const loadBackendSettings = async () => {
+    const keys = Object.keys(backendConfigKeys) as (keyof AppSettings)[]
+    const values = await Promise.all(keys.map(key => getAppConfig(backendConfigKeys[key])))
+    const backendSettings: Partial<AppSettings> = {}
+    keys.forEach((key, index) => {
+      ;(backendSettings as Record<string, unknown>)[key] = values[index]
+    })
+    Object.assign(settings, backendSettings)
+  }
+
+  // 同步设置到后端配置存储
+  const syncBackendSettings = async () => {
+    const values: Record<string, unknown> = {}
+    ;(Object.keys(backendConfigKeys) as (keyof AppSettings)[]).forEach(key => {
+      values[backendConfigKeys[key]] = settings[key]
+    })
+    await updateAppConfig(values)
+  }
+
   // 加载设置
   const loadSettings = async () => {
     try {
@@ -71,7 +153,18 @@ export const useSettingsStore = defineStore('settings', () => {
       const savedSettings = localStorage.getItem(STORAGE_KEY)
       if (savedSettings) {
         const parsed = JSON.parse(savedSettings)
-        Object.assign(settings, { ...defaultSettings, ...parsed })
+        // 只保留已知的设置项，丢弃旧版本保存在本地的字段（如代理密码）
+        const known = Object.fromEntries(
+          Object.entries(parsed).filter(([key]) => key in defaultSettings)
+        )
+        Object.assign(settings, { ...defaultSettings, ...known })
+      }
+
+      // 后端配置存储优先
+      try {
+        await loadBackendSettings()
+      } catch (error) {
+        console.warn('从后端加载设置失败，使用本地设置:', error)
       }
       
       console.log('设置加载完成:', settings)
@@ -95,6 +188,9 @@ export const useSettingsStore = defineStore('settings', () => {
       
       // 保存到本地存储
       localStorage.setItem(STORAGE_KEY, JSON.stringify(settings))
+
+      // 同步到后端配置存储
+      await syncBackendSettings()
       
       console.log('设置保存完成:', settings)
       return true
@@ -116,6 +212,9 @@ export const useSettingsStore = defineStore('settings', () => {
       
       // 保存到本地存储
       localStorage.setItem(STORAGE_KEY, JSON.stringify(settings))
+
+      // 同步到后端配置存储
+      await syncBackendSettings()
       
       console.log('设置已重置为默认值')
       return true
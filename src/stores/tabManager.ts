@@ -214,7 +214,7 @@ export const useTabManagerStore = defineStore('tabManager', () => {
       if (otherTabsWithSameInstance.length === 0) {
         try {
           await pluginStore.disconnectPluginInstance(tab.instanceId)
-          await pluginStore.disposePluginInstance(tab.instanceId)
+          await pluginStore.disposePluginInstance(tab.instanceId, true)
           console.log(`插件实例 ${tab.instanceId} 已卸载（无其他标签页使用）`)
         } catch (error) {
           console.warn('断开插件实例失败:', error)